@@ -1,8 +1,12 @@
 use anyhow::Result;
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
     Argon2,
 };
+use sha2::{Digest, Sha256};
 use std::future::Future;
 use shuttle_axum::axum::{
     extract::{FromRequestParts, FromRef},
@@ -10,11 +14,15 @@ use shuttle_axum::axum::{
     response::{IntoResponse, Response},
 };
 use std::sync::Arc;
-use chrono::{Duration, Utc}; // used for JWT exp/iat timestamps
+use chrono::{DateTime, Duration, Utc}; // used for JWT exp/iat timestamps
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use uuid::Uuid;
 
-use crate::{error::AppError, models::Claims, state::AppState};
+use crate::{
+    error::AppError,
+    models::{Claims, Role},
+    state::AppState,
+};
 
 pub fn hash_password(password: &str) -> Result<String> {
     let salt = SaltString::generate(&mut OsRng);
@@ -35,14 +43,52 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
         .is_ok())
 }
 
-pub fn generate_jwt(user_id: Uuid, username: &str, secret: &str) -> Result<String> {
+/// Fallback JWT secret for local development only
+pub const DEV_JWT_SECRET: &str = "development-secret-change-me";
+
+/// Refuse the publicly known development secret outside development,
+/// since anyone could forge tokens signed with it.
+pub fn ensure_secure_jwt_secret(secret: &str, development: bool) -> Result<()> {
+    if secret != DEV_JWT_SECRET {
+        return Ok(());
+    }
+
+    if development {
+        tracing::warn!("JWT_SECRET is not set; using the insecure development secret");
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "JWT_SECRET is not set and the insecure development default is refused outside development \
+             (set JWT_SECRET, or APP_ENV=development for local use)"
+        ))
+    }
+}
+
+/// Lifetime of access tokens; clients renew them with a refresh token
+pub fn access_token_ttl() -> Duration {
+    Duration::minutes(15)
+}
+
+/// Lifetime of refresh tokens (one session row each)
+pub fn refresh_token_ttl() -> Duration {
+    Duration::days(30)
+}
+
+pub fn generate_jwt(
+    user_id: Uuid,
+    username: &str,
+    role: Role,
+    secret: &str,
+    ttl: Duration,
+) -> Result<String> {
     let now = Utc::now();
-    let exp = (now + Duration::days(7)).timestamp();
+    let exp = (now + ttl).timestamp();
     let iat = now.timestamp();
 
     let claims = Claims {
         sub: user_id,
         username: username.to_string(),
+        role,
         exp,
         iat,
     };
@@ -66,10 +112,71 @@ pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims> {
     Ok(token_data.claims)
 }
 
+/// Generate an opaque random refresh token (hex encoded)
+pub fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hash a refresh token for storage; only the hash is persisted
+pub fn hash_token(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A retired signing secret that still verifies tokens until `valid_until`
+#[derive(Debug, Clone)]
+pub struct PreviousSecret {
+    pub secret: String,
+    pub valid_until: DateTime<Utc>,
+}
+
+/// Verify a token with the current secret, falling back to the previous
+/// secret while its grace period lasts. New tokens always use the current one.
+pub fn verify_jwt_rotating(
+    token: &str,
+    current: &str,
+    previous: Option<&PreviousSecret>,
+) -> Result<Claims> {
+    verify_jwt_rotating_at(token, current, previous, Utc::now())
+}
+
+fn verify_jwt_rotating_at(
+    token: &str,
+    current: &str,
+    previous: Option<&PreviousSecret>,
+    now: DateTime<Utc>,
+) -> Result<Claims> {
+    match verify_jwt(token, current) {
+        Ok(claims) => Ok(claims),
+        Err(e) => match previous {
+            Some(prev) if now < prev.valid_until => verify_jwt(token, &prev.secret),
+            _ => Err(e),
+        },
+    }
+}
+
 // Authentication extractor
 pub struct AuthUser {
     pub user_id: Uuid,
     pub username: String,
+    pub role: Role,
+}
+
+impl AuthUser {
+    /// Admins may manage any post; authors only their own
+    pub fn can_manage(&self, author_id: Uuid) -> bool {
+        self.role == Role::Admin || self.user_id == author_id
+    }
+
+    /// Reject non-admin users from admin-only operations
+    pub fn require_admin(&self) -> Result<(), AppError> {
+        match self.role {
+            Role::Admin => Ok(()),
+            Role::Author => Err(AppError::Forbidden("Admin role required".to_string())),
+        }
+    }
 }
 
 impl<S> FromRequestParts<S> for AuthUser
@@ -95,13 +202,71 @@ where
             (StatusCode::UNAUTHORIZED, "Invalid authorization format").into_response()
         })?;
 
-        let claims = verify_jwt(token, &app_state.jwt_secret)
+        let claims = verify_jwt_rotating(
+            token,
+            &app_state.jwt_secret,
+            app_state.previous_jwt_secret.as_ref(),
+        )
             .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token").into_response())?;
 
         Ok(AuthUser {
             user_id: claims.sub,
             username: claims.username,
+            role: claims.role,
         })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_jwt_secret_refused_outside_development() {
+        assert!(ensure_secure_jwt_secret(DEV_JWT_SECRET, false).is_err());
+        assert!(ensure_secure_jwt_secret(DEV_JWT_SECRET, true).is_ok());
+        assert!(ensure_secure_jwt_secret("a-real-secret", false).is_ok());
+    }
+
+    #[test]
+    fn test_previous_secret_verifies_during_grace_period() {
+        let user_id = Uuid::new_v4();
+        let old_token =
+            generate_jwt(user_id, "admin", Role::Admin, "old-secret", Duration::minutes(15)).unwrap();
+        let previous = PreviousSecret {
+            secret: "old-secret".to_string(),
+            valid_until: Utc::now() + Duration::hours(1),
+        };
+
+        let claims = verify_jwt_rotating_at(&old_token, "new-secret", Some(&previous), Utc::now()).unwrap();
+        assert_eq!(claims.sub, user_id);
+
+        let after_grace = Utc::now() + Duration::hours(2);
+        assert!(verify_jwt_rotating_at(&old_token, "new-secret", Some(&previous), after_grace).is_err());
+        assert!(verify_jwt_rotating_at(&old_token, "new-secret", None, Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_role_decides_post_management() {
+        let owner = Uuid::new_v4();
+        let someone_else = Uuid::new_v4();
+        let user = |role| AuthUser {
+            user_id: owner,
+            username: "writer".to_string(),
+            role,
+        };
+
+        assert!(user(Role::Author).can_manage(owner));
+        assert!(!user(Role::Author).can_manage(someone_else));
+        assert!(user(Role::Admin).can_manage(someone_else));
+        assert!(user(Role::Author).require_admin().is_err());
+        assert!(user(Role::Admin).require_admin().is_ok());
+    }
+
+    #[test]
+    fn test_role_round_trips_through_jwt() {
+        let token = generate_jwt(Uuid::new_v4(), "writer", Role::Author, "secret", Duration::minutes(15)).unwrap();
+        assert_eq!(verify_jwt(&token, "secret").unwrap().role, Role::Author);
+    }
+}
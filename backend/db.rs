@@ -1,13 +1,18 @@
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde_json::json;
-use sqlx::{postgres::PgRow, PgPool, Row};
+use sqlx::{postgres::PgRow, PgPool, Postgres, Row, Transaction};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::{
     auth,
+    handlers::{admin::publish_blocker, tags::normalize_tag_name},
     models::{
-        CreatePostRequest, CreateTagRequest, Post, PostSummary, Tag, UpdatePostRequest, User,
+        AuthorInfo, BlogExport, CreatePostRequest, CreateTagRequest, Draft, IdempotencyClaim,
+        ImportReport, LinkingPost, Post, PostChange, PostSummary, ReindexReport, Role,
+        SaveDraftRequest, SeedReport, Session, SitemapEntry, StatsPeriod, Tag, TimelineBucket,
+        UpdatePostRequest, User,
     },
 };
 
@@ -20,90 +25,137 @@ pub async fn init_admin_user(pool: &PgPool, password: &str) -> Result<()> {
     .fetch_optional(pool)
     .await?;
 
-    let password_hash = auth::hash_password(password)?;
-
-    if existing.is_none() {
-        let id = Uuid::new_v4();
+    // Only set the password on first creation; later changes go through the
+    // password-change endpoint and must survive redeploys.
+    if existing.is_some() {
+        tracing::info!("Admin user already exists, leaving password unchanged");
+        return Ok(());
+    }
 
-        sqlx::query(
-            "INSERT INTO users (id, username, password_hash, created_at, updated_at)
-             VALUES ($1, $2, $3, $4, $5)",
-        )
-        .bind(id)
-        .bind("admin")
-        .bind(password_hash)
-        .bind(Utc::now())
-        .bind(Utc::now())
-        .execute(pool)
-        .await?;
+    let password_hash = auth::hash_password(password)?;
+    let id = Uuid::new_v4();
 
-        tracing::info!("Admin user created successfully");
-    } else {
-        // Update existing admin password
-        sqlx::query(
-            "UPDATE users SET password_hash = $1, updated_at = $2 WHERE username = $3",
-        )
-        .bind(password_hash)
-        .bind(Utc::now())
-        .bind("admin")
-        .execute(pool)
-        .await?;
+    sqlx::query(
+        "INSERT INTO users (id, username, password_hash, role, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(id)
+    .bind("admin")
+    .bind(password_hash)
+    .bind(Role::Admin.as_str())
+    .bind(Utc::now())
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
 
-        tracing::info!("Admin password updated successfully");
-    }
+    tracing::info!("Admin user created successfully");
 
     Ok(())
 }
 
-/// Seed sample posts for demonstration
-pub async fn seed_sample_posts(pool: &PgPool, author_id: Uuid) -> Result<()> {
+/// Tags created by the demo seed: (name, Catppuccin color)
+pub const SAMPLE_TAGS: [(&str, &str); 4] = [
+    ("haskell", "lavender"),
+    ("functional-programming", "blue"),
+    ("code-philosophy", "mauve"),
+    ("programming-languages", "peach"),
+];
+
+/// Seed sample posts for demonstration.
+/// - Skips entirely when posts already exist, unless `force` is set
+/// - With `force`, existing sample tags are reused and existing sample posts left alone
+pub async fn seed_sample_posts(pool: &PgPool, author_id: Uuid, force: bool) -> Result<SeedReport> {
     // Check if we already have posts
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM posts")
         .fetch_one(pool)
-        .await
-        .unwrap_or(0);
+        .await?;
 
-    if count > 0 {
-        return Ok(()); // Already have posts, skip seeding
+    if count > 0 && !force {
+        return Ok(SeedReport {
+            skipped: true,
+            ..Default::default()
+        });
     }
 
+    let mut report = SeedReport::default();
+
     // Create sample tags first
-    let haskell_tag = create_tag(
-        pool,
-        CreateTagRequest {
-            name: "haskell".to_string(),
-            color: "lavender".to_string(),
-        },
-    )
-    .await?;
+    let mut tag_ids = Vec::with_capacity(SAMPLE_TAGS.len());
+    for (name, color) in SAMPLE_TAGS {
+        let id = match get_tag_by_name(pool, name).await? {
+            Some(tag) => tag.id,
+            None => {
+                let tag = create_tag(
+                    pool,
+                    CreateTagRequest {
+                        name: name.to_string(),
+                        color: color.to_string(),
+                    },
+                )
+                .await?;
+                sqlx::query("UPDATE tags SET seeded = true WHERE id = $1")
+                    .bind(tag.id)
+                    .execute(pool)
+                    .await?;
+                report.tags.push(tag.name);
+                tag.id
+            }
+        };
+        tag_ids.push(id);
+    }
 
-    let fp_tag = create_tag(
-        pool,
-        CreateTagRequest {
-            name: "functional-programming".to_string(),
-            color: "blue".to_string(),
-        },
-    )
-    .await?;
+    let haskell_post = sample_haskell_post(tag_ids);
+    if get_post_by_slug_any(pool, &haskell_post.slug).await?.is_none() {
+        let post = create_post(pool, haskell_post, author_id).await?;
+        sqlx::query("UPDATE posts SET seeded = true WHERE id = $1")
+            .bind(post.id)
+            .execute(pool)
+            .await?;
+        report.posts.push(post.slug);
+    }
 
-    let philosophy_tag = create_tag(
-        pool,
-        CreateTagRequest {
-            name: "code-philosophy".to_string(),
-            color: "mauve".to_string(),
-        },
-    )
-    .await?;
+    tracing::info!(
+        "Sample content seeded: {} posts, {} tags",
+        report.posts.len(),
+        report.tags.len()
+    );
+    Ok(report)
+}
 
-    let languages_tag = create_tag(
-        pool,
-        CreateTagRequest {
-            name: "programming-languages".to_string(),
-            color: "peach".to_string(),
-        },
+/// Remove content created by the demo seed.
+/// Seeded tags still attached to a real post are kept.
+pub async fn delete_seeded_content(pool: &PgPool) -> Result<SeedReport> {
+    let mut tx = pool.begin().await?;
+
+    let seeded: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM posts WHERE seeded = true")
+        .fetch_all(&mut *tx)
+        .await?;
+    record_tombstones(&mut tx, &seeded).await?;
+
+    let posts: Vec<String> = sqlx::query_scalar("DELETE FROM posts WHERE seeded = true RETURNING slug")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let tags: Vec<String> = sqlx::query_scalar(
+        "DELETE FROM tags t
+         WHERE t.seeded = true
+           AND NOT EXISTS (SELECT 1 FROM post_tags pt WHERE pt.tag_id = t.id)
+         RETURNING t.name",
     )
+    .fetch_all(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
+    Ok(SeedReport {
+        posts,
+        tags,
+        skipped: false,
+    })
+}
+
+/// The sample Haskell post, tagged with the given tag ids
+fn sample_haskell_post(tag_ids: Vec<Uuid>) -> CreatePostRequest {
     // Read the sample Haskell post
     let haskell_content = r#"# The Quiet Elegance of Haskell: Where Code Becomes Poetry
 
@@ -174,44 +226,318 @@ In a world of ever-increasing complexity, Haskell whispers a radical promise: **
 
 That is elegance."#;
 
-    // Create the Haskell elegance post
-    let haskell_post = CreatePostRequest {
+    CreatePostRequest {
         slug: "haskell-elegance".to_string(),
         title: "The Quiet Elegance of Haskell: Where Code Becomes Poetry".to_string(),
         excerpt: "There's a moment every Haskell programmer remembers—the point where the syntax fades away and what remains feels less like programming and more like stating simple, timeless truths.".to_string(),
         body: haskell_content.to_string(),
-        tags: vec![haskell_tag.id, fp_tag.id, philosophy_tag.id, languages_tag.id],
+        tags: tag_ids,
         published: true,
-    };
+        trusted_html: false,
+        noindex: false,
+        publish_at: None,
+    }
+}
 
-    create_post(pool, haskell_post, author_id).await?;
+/// Map a `posts` row (selected with the aggregated `tags` column) to a Post
+fn post_from_row(row: &PgRow) -> Post {
+    let tags_json: serde_json::Value = row.get("tags");
+    let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
 
-    tracing::info!("Sample posts seeded successfully");
-    Ok(())
+    Post {
+        id: row.get("id"),
+        slug: row.get("slug"),
+        title: row.get("title"),
+        excerpt: row.get("excerpt"),
+        body: row.get("body"),
+        published: row.get("published"),
+        published_at: row.get("published_at"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        author_id: row.get("author_id"),
+        trusted_html: row.get("trusted_html"),
+        noindex: row.get("noindex"),
+        publish_at: row.get("publish_at"),
+        deleted_at: row.get("deleted_at"),
+        author: author_from_row(row),
+        tags,
+    }
+}
+
+/// Map a published post row (with `body`, aggregated `tags` and `author`) to a PostSummary
+fn summary_from_row(row: &PgRow) -> PostSummary {
+    let tags_json: serde_json::Value = row.get("tags");
+    let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
+    let body: String = row.get("body");
+    let reading_time = crate::markdown::calculate_reading_time(&body);
+
+    PostSummary {
+        id: row.get("id"),
+        slug: row.get("slug"),
+        title: row.get("title"),
+        excerpt: row.get("excerpt"),
+        published_at: row.get("published_at"),
+        reading_time,
+        author: author_from_row(row),
+        tags,
+    }
+}
+
+/// Read the aggregated `author` object (id + username only, never the password hash)
+fn author_from_row(row: &PgRow) -> AuthorInfo {
+    let author_json: Option<serde_json::Value> = row.get("author");
+    author_json
+        .and_then(|json| serde_json::from_value(json).ok())
+        .unwrap_or_default()
+}
+
+/// Map a `users` row (including `role`) to a User
+fn user_from_row(row: &PgRow) -> User {
+    let role: String = row.get("role");
+    User {
+        id: row.get("id"),
+        username: row.get("username"),
+        password_hash: row.get("password_hash"),
+        role: Role::from_db(&role),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
 }
 
 /// Get user by username
 pub async fn get_user_by_username(pool: &PgPool, username: &str) -> Result<Option<User>> {
     let row = sqlx::query(
-        "SELECT id, username, password_hash, created_at, updated_at FROM users WHERE username = $1",
+        "SELECT id, username, password_hash, role, created_at, updated_at FROM users WHERE username = $1",
     )
     .bind(username)
     .fetch_optional(pool)
     .await?;
 
-    let user = if let Some(row) = row {
-        Some(User {
-            id: row.get("id"),
-            username: row.get("username"),
-            password_hash: row.get("password_hash"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        })
-    } else {
-        None
+    Ok(row.as_ref().map(user_from_row))
+}
+
+/// Get user by id
+pub async fn get_user_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>> {
+    let row = sqlx::query(
+        "SELECT id, username, password_hash, role, created_at, updated_at FROM users WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(user_from_row))
+}
+
+/// Role of a post's author; missing users get the least privilege
+pub async fn get_author_role(pool: &PgPool, author_id: Uuid) -> Result<Role> {
+    let role: Option<String> = sqlx::query_scalar("SELECT role FROM users WHERE id = $1")
+        .bind(author_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(role.map_or(Role::Author, |r| Role::from_db(&r)))
+}
+
+/// Create a user with an already-hashed password
+pub async fn create_user(pool: &PgPool, username: &str, password_hash: &str, role: Role) -> Result<User> {
+    let row = sqlx::query(
+        r#"
+        INSERT INTO users (id, username, password_hash, role, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $5)
+        RETURNING id, username, password_hash, role, created_at, updated_at
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(username)
+    .bind(password_hash)
+    .bind(role.as_str())
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user_from_row(&row))
+}
+
+/// Replace a user's password hash and delete their sessions, so refresh tokens issued
+/// under the old password stop working. Returns how many sessions were revoked.
+pub async fn update_user_password(pool: &PgPool, id: Uuid, password_hash: &str) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE users SET password_hash = $1, updated_at = $2 WHERE id = $3")
+        .bind(password_hash)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    let revoked = sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    tx.commit().await?;
+    Ok(revoked)
+}
+
+/// Persist a session for a refresh token (stored hashed)
+pub async fn create_session(
+    pool: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<Session> {
+    let row = sqlx::query(
+        "INSERT INTO sessions (id, user_id, token_hash, expires_at, created_at)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, user_id, token_hash, expires_at, created_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(token_hash)
+    .bind(expires_at)
+    .bind(Utc::now())
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Session {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        token_hash: row.get("token_hash"),
+        expires_at: row.get("expires_at"),
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Find an unexpired session by refresh token hash
+pub async fn get_active_session(pool: &PgPool, token_hash: &str) -> Result<Option<Session>> {
+    let row = sqlx::query(
+        "SELECT id, user_id, token_hash, expires_at, created_at FROM sessions
+         WHERE token_hash = $1 AND expires_at > now()",
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| Session {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        token_hash: row.get("token_hash"),
+        expires_at: row.get("expires_at"),
+        created_at: row.get("created_at"),
+    }))
+}
+
+/// Delete the session for a refresh token (logout)
+pub async fn delete_session(pool: &PgPool, token_hash: &str) -> Result<()> {
+    sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+        .bind(token_hash)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// How long a processed `Idempotency-Key` keeps replaying its response
+const IDEMPOTENCY_KEY_TTL_HOURS: i32 = 24;
+
+/// How long an unfinished claim holds its key. Longer than the request timeout, so
+/// only a request that died mid-flight lets a retry take the key over.
+const IDEMPOTENCY_LEASE_SECS: i32 = 60;
+
+/// Claim a user's idempotency key for the request hashed as `request_hash`,
+/// clearing expired keys first
+pub async fn claim_idempotency_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    request_hash: &str,
+) -> Result<IdempotencyClaim> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE created_at < now() - make_interval(hours => $1)")
+        .bind(IDEMPOTENCY_KEY_TTL_HOURS)
+        .execute(pool)
+        .await?;
+
+    let inserted = sqlx::query(
+        "INSERT INTO idempotency_keys (user_id, key, request_hash, locked_until)
+         VALUES ($1, $2, $3, now() + make_interval(secs => $4))
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(request_hash)
+    .bind(f64::from(IDEMPOTENCY_LEASE_SECS))
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if inserted == 1 {
+        return Ok(IdempotencyClaim::New);
+    }
+
+    // The first request never finished and its lease ran out: this one takes over
+    let taken_over = sqlx::query(
+        "UPDATE idempotency_keys
+         SET request_hash = $3, locked_until = now() + make_interval(secs => $4), created_at = now()
+         WHERE user_id = $1 AND key = $2 AND response IS NULL AND locked_until < now()",
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(request_hash)
+    .bind(f64::from(IDEMPOTENCY_LEASE_SECS))
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if taken_over == 1 {
+        return Ok(IdempotencyClaim::New);
+    }
+
+    let row = sqlx::query(
+        "SELECT response, request_hash FROM idempotency_keys WHERE user_id = $1 AND key = $2",
+    )
+    .bind(user_id)
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+    // Released between our insert and this read: the caller may simply retry
+    let Some(row) = row else {
+        return Ok(IdempotencyClaim::InProgress);
     };
 
-    Ok(user)
+    // Keys stored before request hashing have an empty hash and match any request
+    let stored_hash: String = row.get("request_hash");
+    if !stored_hash.is_empty() && stored_hash != request_hash {
+        return Ok(IdempotencyClaim::Mismatch);
+    }
+    let response: Option<serde_json::Value> = row.get("response");
+    Ok(match response {
+        Some(response) => IdempotencyClaim::Replay(response),
+        None => IdempotencyClaim::InProgress,
+    })
+}
+
+/// Store the response to replay for a claimed key
+pub async fn complete_idempotency_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    response: &serde_json::Value,
+) -> Result<()> {
+    sqlx::query("UPDATE idempotency_keys SET response = $3 WHERE user_id = $1 AND key = $2")
+        .bind(user_id)
+        .bind(key)
+        .bind(response)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Give up a claimed key after a failed request, so a retry can try again
+pub async fn release_idempotency_key(pool: &PgPool, user_id: Uuid, key: &str) -> Result<()> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE user_id = $1 AND key = $2 AND response IS NULL")
+        .bind(user_id)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(())
 }
 
 /// List all published posts with tags
@@ -236,45 +562,69 @@ pub async fn list_published_posts(pool: &PgPool) -> Result<Vec<PostSummary>> {
                     ) tags_subq
                 ),
                 '[]'::json
-            ) as tags
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
         FROM posts p
         WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
         GROUP BY p.id
-        ORDER BY p.published_at DESC
+        ORDER BY p.published_at DESC, p.created_at DESC, p.id DESC
         "#
     )
     .fetch_all(pool)
     .await?;
 
-    let summaries: Vec<PostSummary> = rows
-        .into_iter()
-        .map(|row| {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
-            let body: String = row.get("body");
-            let reading_time = crate::markdown::calculate_reading_time(&body);
-
-            PostSummary {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                published_at: row.get("published_at"),
-                reading_time,
-                tags,
-            }
-        })
-        .collect();
+    let summaries: Vec<PostSummary> = rows.iter().map(summary_from_row).collect();
 
     Ok(summaries)
 }
 
-/// Get a published post by slug
-pub async fn get_post_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Post>> {
-    let row: Option<PgRow> = sqlx::query(
+/// Version of the public post list: the newest change among live posts and how many there are.
+/// The count catches deletions and scheduled posts going live, which move no timestamp.
+pub async fn published_posts_version(pool: &PgPool) -> Result<(Option<DateTime<Utc>>, i64)> {
+    let row = sqlx::query(
+        r#"
+        SELECT MAX(p.updated_at) AS last_updated, COUNT(*) AS count
+        FROM posts p
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok((row.get("last_updated"), row.get("count")))
+}
+
+/// Fingerprint of every tag's name and color. Renaming, recoloring or deleting a tag moves
+/// no post timestamp, yet changes the tags embedded in post responses.
+pub async fn tags_version(pool: &PgPool) -> Result<String> {
+    let row = sqlx::query(
+        r#"
+        SELECT COALESCE(md5(string_agg(id::text || ':' || name || ':' || color, ',' ORDER BY id)), '')
+            AS digest
+        FROM tags
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.get("digest"))
+}
+
+/// Most-read live posts, highest view count first
+pub async fn list_popular_posts(pool: &PgPool, limit: i64) -> Result<Vec<PostSummary>> {
+    let rows: Vec<PgRow> = sqlx::query(
         r#"
         SELECT
-            p.*,
+            p.id,
+            p.slug,
+            p.title,
+            p.excerpt,
+            p.body,
+            p.published_at,
             COALESCE(
                 (
                     SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
@@ -286,40 +636,51 @@ pub async fn get_post_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Post>>
                     ) tags_subq
                 ),
                 '[]'::json
-            ) as tags
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
         FROM posts p
-        WHERE p.slug = $1 AND p.published = true
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+        ORDER BY p.view_count DESC, p.published_at DESC, p.id DESC
+        LIMIT $1
         "#
     )
-    .bind(slug)
-    .fetch_optional(pool)
+    .bind(limit)
+    .fetch_all(pool)
     .await?;
 
-    match row {
-        Some(row) => {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
+    Ok(rows.iter().map(summary_from_row).collect())
+}
 
-            Ok(Some(Post {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                body: row.get("body"),
-                published: row.get("published"),
-                published_at: row.get("published_at"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                author_id: row.get("author_id"),
-                tags,
-            }))
-        }
-        None => Ok(None),
-    }
+/// Count one read of a post; doesn't touch `updated_at`
+pub async fn increment_view_count(pool: &PgPool, post_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE posts SET view_count = view_count + 1 WHERE id = $1")
+        .bind(post_id)
+        .execute(pool)
+        .await?;
+    Ok(())
 }
 
-/// Get any post by slug (including unpublished) - for admin operations
-pub async fn get_post_by_slug_any(pool: &PgPool, slug: &str) -> Result<Option<Post>> {
+/// Return which of `slugs` belong to posts that aren't in the trash
+pub async fn find_existing_slugs(pool: &PgPool, slugs: &[String]) -> Result<Vec<String>> {
+    let found = sqlx::query_scalar("SELECT slug FROM posts WHERE slug = ANY($1) AND deleted_at IS NULL")
+        .bind(slugs)
+        .fetch_all(pool)
+        .await?;
+    Ok(found)
+}
+
+/// Filenames of every uploaded asset
+pub async fn list_asset_filenames(pool: &PgPool) -> Result<Vec<String>> {
+    let filenames = sqlx::query_scalar("SELECT filename FROM assets")
+        .fetch_all(pool)
+        .await?;
+    Ok(filenames)
+}
+
+/// Get a published post by slug
+pub async fn get_post_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Post>> {
     let row: Option<PgRow> = sqlx::query(
         r#"
         SELECT
@@ -335,38 +696,202 @@ pub async fn get_post_by_slug_any(pool: &PgPool, slug: &str) -> Result<Option<Po
                     ) tags_subq
                 ),
                 '[]'::json
-            ) as tags
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
         FROM posts p
-        WHERE p.slug = $1
+        WHERE p.slug = $1 AND p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
         "#
     )
     .bind(slug)
     .fetch_optional(pool)
     .await?;
 
-    match row {
-        Some(row) => {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
-
-            Ok(Some(Post {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                body: row.get("body"),
-                published: row.get("published"),
-                published_at: row.get("published_at"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                author_id: row.get("author_id"),
-                tags,
-            }))
-        }
-        None => Ok(None),
-    }
+    Ok(row.as_ref().map(post_from_row))
 }
 
+/// List every live post for the sitemap, including the `noindex` flag
+pub async fn list_sitemap_entries(pool: &PgPool) -> Result<Vec<SitemapEntry>> {
+    let rows = sqlx::query(
+        "SELECT slug, updated_at, noindex FROM posts
+         WHERE published = true
+           AND (publish_at IS NULL OR publish_at <= now())
+           AND deleted_at IS NULL
+         ORDER BY published_at DESC, created_at DESC, id DESC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SitemapEntry {
+            slug: row.get("slug"),
+            updated_at: row.get("updated_at"),
+            noindex: row.get("noindex"),
+        })
+        .collect())
+}
+
+/// Posts that are or once were public and changed after the `(since, after)` cursor,
+/// oldest change first. Mirrors the `Post::changed_at` rule; changes at exactly `since`
+/// are included only past `after`, so a page boundary never skips a row.
+/// Purged posts are reported by `list_tombstones_since`.
+pub async fn list_posts_changed_since(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+    after: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<Post>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT * FROM (
+            SELECT
+                p.*,
+                COALESCE(
+                    (
+                        SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
+                        FROM (
+                            SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
+                            FROM post_tags pt
+                            JOIN tags t ON pt.tag_id = t.id
+                            WHERE pt.post_id = p.id
+                        ) tags_subq
+                    ),
+                    '[]'::json
+                ) as tags,
+                (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author,
+                GREATEST(
+                    p.updated_at,
+                    p.deleted_at,
+                    CASE WHEN p.publish_at <= now() THEN p.publish_at END
+                ) as changed_at
+            FROM posts p
+            WHERE p.was_public
+                OR (p.published = true
+                    AND (p.publish_at IS NULL OR p.publish_at <= now())
+                    AND p.deleted_at IS NULL)
+        ) changes
+        WHERE changed_at > $1 OR (changed_at = $1 AND id > $2)
+        ORDER BY changed_at ASC, id ASC
+        LIMIT $3
+        "#
+    )
+    .bind(since)
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(post_from_row).collect())
+}
+
+/// Purged posts past the `(since, after)` cursor, as deletions, oldest first
+pub async fn list_tombstones_since(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+    after: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<PostChange>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT post_id, slug, purged_at FROM post_tombstones
+        WHERE purged_at > $1 OR (purged_at = $1 AND post_id > $2)
+        ORDER BY purged_at ASC, post_id ASC
+        LIMIT $3
+        "#,
+    )
+    .bind(since)
+    .bind(after)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| PostChange {
+            id: row.get("post_id"),
+            slug: row.get("slug"),
+            changed_at: row.get("purged_at"),
+            deleted: true,
+            post: None,
+        })
+        .collect())
+}
+
+/// Remember that a post is public before a change that may hide it (unpublishing,
+/// trashing, rescheduling), so incremental sync still reports its removal afterwards
+async fn mark_if_public<'e>(executor: impl sqlx::PgExecutor<'e>, id: Uuid) -> Result<()> {
+    sqlx::query(
+        "UPDATE posts SET was_public = true
+         WHERE id = $1 AND published = true
+           AND (publish_at IS NULL OR publish_at <= now())
+           AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Leave a tombstone for each post about to be purged that was ever public
+async fn record_tombstones(tx: &mut Transaction<'_, Postgres>, ids: &[Uuid]) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO post_tombstones (post_id, slug, purged_at)
+         SELECT id, slug, now() FROM posts
+         WHERE id = ANY($1)
+           AND (was_public
+                OR (published = true
+                    AND (publish_at IS NULL OR publish_at <= now())
+                    AND deleted_at IS NULL))
+         ON CONFLICT (post_id) DO NOTHING",
+    )
+    .bind(ids)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Get any post by slug (including unpublished) - for admin operations
+pub async fn get_post_by_slug_any(pool: &PgPool, slug: &str) -> Result<Option<Post>> {
+    let row: Option<PgRow> = sqlx::query(
+        r#"
+        SELECT
+            p.*,
+            COALESCE(
+                (
+                    SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
+                    FROM (
+                        SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
+                        FROM post_tags pt
+                        JOIN tags t ON pt.tag_id = t.id
+                        WHERE pt.post_id = p.id
+                    ) tags_subq
+                ),
+                '[]'::json
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
+        FROM posts p
+        WHERE p.slug = $1
+        "#
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(post_from_row))
+}
+
+
+/// Slugs (including trashed posts) equal to `base` or of the form `base-N`
+pub async fn list_slugs_with_prefix(pool: &PgPool, base: &str) -> Result<Vec<String>> {
+    let slugs: Vec<String> =
+        sqlx::query_scalar("SELECT slug FROM posts WHERE slug = $1 OR slug LIKE $1 || '-%'")
+            .bind(base)
+            .fetch_all(pool)
+            .await?;
+    Ok(slugs)
+}
 
 /// Get any post by ID (including unpublished)
 pub async fn get_post_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Post>> {
@@ -385,7 +910,8 @@ pub async fn get_post_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Post>> {
                     ) tags_subq
                 ),
                 '[]'::json
-            ) as tags
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
         FROM posts p
         WHERE p.id = $1
         "#
@@ -394,34 +920,29 @@ pub async fn get_post_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Post>> {
     .fetch_optional(pool)
     .await?;
 
-    match row {
-        Some(row) => {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
-
-            Ok(Some(Post {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                body: row.get("body"),
-                published: row.get("published"),
-                published_at: row.get("published_at"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                author_id: row.get("author_id"),
-                tags,
-            }))
-        }
-        None => Ok(None),
-    }
+    Ok(row.as_ref().map(post_from_row))
 }
 
 /// Create a new post
 pub async fn create_post(pool: &PgPool, req: CreatePostRequest, author_id: Uuid) -> Result<Post> {
+    create_post_with_tags(pool, req, &[], author_id).await
+}
+
+/// Create a post that is also tagged with `new_tags` by name. Tags that don't exist yet
+/// are created in the post's transaction, so a failed insert leaves none of them behind.
+pub async fn create_post_with_tags(
+    pool: &PgPool,
+    req: CreatePostRequest,
+    new_tags: &[CreateTagRequest],
+    author_id: Uuid,
+) -> Result<Post> {
     let id = Uuid::new_v4();
     let now = Utc::now();
-    let published_at = if req.published { Some(now) } else { None };
+    let published_at = if req.published {
+        Some(req.publish_at.unwrap_or(now))
+    } else {
+        None
+    };
 
     // Start transaction
     let mut tx = pool.begin().await?;
@@ -429,8 +950,8 @@ pub async fn create_post(pool: &PgPool, req: CreatePostRequest, author_id: Uuid)
     // Insert post
     sqlx::query(
         r#"
-        INSERT INTO posts (id, slug, title, excerpt, body, published, published_at, created_at, updated_at, author_id)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        INSERT INTO posts (id, slug, title, excerpt, body, published, published_at, created_at, updated_at, author_id, trusted_html, publish_at, noindex)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
         "#
     )
     .bind(id)
@@ -443,11 +964,41 @@ pub async fn create_post(pool: &PgPool, req: CreatePostRequest, author_id: Uuid)
     .bind(now)
     .bind(now)
     .bind(author_id)
+    .bind(req.trusted_html)
+    .bind(req.publish_at)
+    .bind(req.noindex)
     .execute(&mut *tx)
     .await?;
 
+    replace_post_links(&mut tx, id, &req.body).await?;
+
+    let mut tag_ids = req.tags.clone();
+    for tag in new_tags {
+        let existing: Option<Uuid> = sqlx::query_scalar("SELECT id FROM tags WHERE name = $1")
+            .bind(&tag.name)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let tag_id = match existing {
+            Some(tag_id) => tag_id,
+            None => {
+                let tag_id = Uuid::new_v4();
+                sqlx::query("INSERT INTO tags (id, name, color, created_at) VALUES ($1, $2, $3, $4)")
+                    .bind(tag_id)
+                    .bind(&tag.name)
+                    .bind(&tag.color)
+                    .bind(now)
+                    .execute(&mut *tx)
+                    .await?;
+                tag_id
+            }
+        };
+        if !tag_ids.contains(&tag_id) {
+            tag_ids.push(tag_id);
+        }
+    }
+
     // Insert tags
-    for tag_id in req.tags.iter() {
+    for tag_id in tag_ids.iter() {
         sqlx::query("INSERT INTO post_tags (post_id, tag_id) VALUES ($1, $2)")
             .bind(id)
             .bind(tag_id)
@@ -463,17 +1014,56 @@ pub async fn create_post(pool: &PgPool, req: CreatePostRequest, author_id: Uuid)
         .ok_or_else(|| anyhow::anyhow!("Failed to fetch created post"))
 }
 
+/// Returned (inside `anyhow`) by `update_post` when `expected_updated_at` is stale
+#[derive(Debug, thiserror::Error)]
+#[error("Post was modified by someone else; reload it and try again")]
+pub struct StalePostUpdate;
+
 /// Update an existing post
 pub async fn update_post(pool: &PgPool, id: Uuid, req: UpdatePostRequest) -> Result<Post> {
     let mut tx = pool.begin().await?;
+    apply_post_update(&mut tx, id, req).await?;
+    tx.commit().await?;
+
+    get_post_by_id(pool, id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Post not found"))
+}
+
+/// The writes of `update_post`, inside the caller's transaction.
+/// A stale `expected_updated_at` fails before anything is written.
+async fn apply_post_update(
+    tx: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    req: UpdatePostRequest,
+) -> Result<()> {
+    // Optimistic lock: claim the row only if nobody saved since the editor loaded it.
+    // The row stays locked until commit, so a concurrent save can't slip in between.
+    if let Some(expected) = req.expected_updated_at {
+        let claimed = sqlx::query(
+            "UPDATE posts SET updated_at = $1 WHERE id = $2 AND updated_at = $3",
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .bind(expected)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+        if claimed == 0 {
+            return Err(StalePostUpdate.into());
+        }
+    }
 
     // Update post fields
     if let Some(slug) = &req.slug {
+        record_slug_redirect(tx, id, slug).await?;
+
         sqlx::query("UPDATE posts SET slug = $1, updated_at = $2 WHERE id = $3")
             .bind(slug)
             .bind(Utc::now())
             .bind(id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await?;
     }
 
@@ -482,7 +1072,7 @@ pub async fn update_post(pool: &PgPool, id: Uuid, req: UpdatePostRequest) -> Res
             .bind(title)
             .bind(Utc::now())
             .bind(id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await?;
     }
 
@@ -491,7 +1081,7 @@ pub async fn update_post(pool: &PgPool, id: Uuid, req: UpdatePostRequest) -> Res
             .bind(excerpt)
             .bind(Utc::now())
             .bind(id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await?;
     }
 
@@ -500,16 +1090,53 @@ pub async fn update_post(pool: &PgPool, id: Uuid, req: UpdatePostRequest) -> Res
             .bind(body)
             .bind(Utc::now())
             .bind(id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
+            .await?;
+
+        replace_post_links(tx, id, body).await?;
+    }
+
+    if let Some(trusted_html) = req.trusted_html {
+        sqlx::query("UPDATE posts SET trusted_html = $1, updated_at = $2 WHERE id = $3")
+            .bind(trusted_html)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    if let Some(noindex) = req.noindex {
+        sqlx::query("UPDATE posts SET noindex = $1, updated_at = $2 WHERE id = $3")
+            .bind(noindex)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    if let Some(publish_at) = req.publish_at {
+        mark_if_public(&mut **tx, id).await?;
+        sqlx::query("UPDATE posts SET publish_at = $1, updated_at = $2 WHERE id = $3")
+            .bind(publish_at)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut **tx)
             .await?;
     }
 
     // Update tags if provided
     if let Some(tag_ids) = req.tags {
+        // Retagging is an edit: it changes the post's response and its related posts
+        sqlx::query("UPDATE posts SET updated_at = $1 WHERE id = $2")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
         // Delete existing tags
         sqlx::query("DELETE FROM post_tags WHERE post_id = $1")
             .bind(id)
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await?;
 
         // Insert new tags
@@ -517,142 +1144,395 @@ pub async fn update_post(pool: &PgPool, id: Uuid, req: UpdatePostRequest) -> Res
             sqlx::query("INSERT INTO post_tags (post_id, tag_id) VALUES ($1, $2)")
                 .bind(id)
                 .bind(tag_id)
-                .execute(&mut *tx)
+                .execute(&mut **tx)
                 .await?;
         }
     }
 
-    tx.commit().await?;
-
-    get_post_by_id(pool, id)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Post not found"))
+    Ok(())
 }
 
-/// Delete a post
-pub async fn delete_post(pool: &PgPool, id: Uuid) -> Result<()> {
-    sqlx::query("DELETE FROM posts WHERE id = $1")
-        .bind(id)
-        .execute(pool)
+/// Remember the post's current slug before it becomes `new_slug`.
+/// The new slug stops being a redirect, so renaming back and forth can never loop.
+async fn record_slug_redirect(
+    tx: &mut Transaction<'_, Postgres>,
+    post_id: Uuid,
+    new_slug: &str,
+) -> Result<()> {
+    sqlx::query("DELETE FROM slug_redirects WHERE old_slug = $1")
+        .bind(new_slug)
+        .execute(&mut **tx)
         .await?;
-    Ok(())
-}
 
-/// Publish a post
-pub async fn publish_post(pool: &PgPool, id: Uuid) -> Result<Post> {
     sqlx::query(
-        "UPDATE posts SET published = true, published_at = $1, updated_at = $2 WHERE id = $3",
+        "INSERT INTO slug_redirects (old_slug, post_id)
+         SELECT slug, id FROM posts WHERE id = $1 AND slug <> $2
+         ON CONFLICT (old_slug) DO UPDATE SET post_id = EXCLUDED.post_id, created_at = now()",
     )
-    .bind(Utc::now())
-    .bind(Utc::now())
-    .bind(id)
-    .execute(pool)
+    .bind(post_id)
+    .bind(new_slug)
+    .execute(&mut **tx)
     .await?;
 
-    get_post_by_id(pool, id)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Post not found"))
+    Ok(())
 }
 
-/// Unpublish a post
-pub async fn unpublish_post(pool: &PgPool, id: Uuid) -> Result<Post> {
-    sqlx::query(
-        "UPDATE posts SET published = false, published_at = NULL, updated_at = $1 WHERE id = $2",
+/// Current slug of the live post an old slug was renamed to
+pub async fn find_slug_redirect(pool: &PgPool, old_slug: &str) -> Result<Option<String>> {
+    let slug = sqlx::query_scalar(
+        "SELECT p.slug
+         FROM slug_redirects r
+         JOIN posts p ON p.id = r.post_id
+         WHERE r.old_slug = $1
+           AND p.published = true
+           AND p.deleted_at IS NULL
+           AND (p.publish_at IS NULL OR p.publish_at <= now())",
     )
-    .bind(Utc::now())
-    .bind(id)
-    .execute(pool)
+    .bind(old_slug)
+    .fetch_optional(pool)
     .await?;
 
-    get_post_by_id(pool, id)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("Post not found"))
+    Ok(slug)
 }
 
-/// List all posts (including unpublished) for admin
-pub async fn list_all_posts(pool: &PgPool) -> Result<Vec<Post>> {
-    let rows: Vec<PgRow> = sqlx::query(
-        r#"
-        SELECT
-            p.*,
-            COALESCE(
-                (
-                    SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
-                    FROM (
-                        SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
-                        FROM post_tags pt
-                        JOIN tags t ON pt.tag_id = t.id
-                        WHERE pt.post_id = p.id
-                    ) tags_subq
-                ),
-                '[]'::json
-            ) as tags
-        FROM posts p
-        GROUP BY p.id
-        ORDER BY p.created_at DESC
-        "#
-    )
-    .fetch_all(pool)
-    .await?;
+/// Rewrite a post's outgoing links from its body; returns how many were stored
+async fn replace_post_links(
+    tx: &mut Transaction<'_, Postgres>,
+    post_id: Uuid,
+    body: &str,
+) -> Result<usize> {
+    sqlx::query("DELETE FROM post_links WHERE from_post_id = $1")
+        .bind(post_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let links = crate::markdown::extract_links(body);
+    let count = links.len();
+    for link in links {
+        sqlx::query(
+            "INSERT INTO post_links (id, from_post_id, to_post_slug, link_text, created_at)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(post_id)
+        .bind(crate::markdown::link_slug(&link))
+        .bind(&link)
+        .bind(Utc::now())
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(count)
+}
 
-    let result: Vec<Post> = rows
+/// The `(to_post_slug, link_text)` rows `replace_post_links` would store for `body`, sorted
+fn derived_links(body: &str) -> Vec<(String, Option<String>)> {
+    let mut links: Vec<(String, Option<String>)> = crate::markdown::extract_links(body)
         .into_iter()
-        .map(|row| {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
+        .map(|link| (crate::markdown::link_slug(&link), Some(link)))
+        .collect();
+    links.sort();
+    links
+}
 
-            Post {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                body: row.get("body"),
-                published: row.get("published"),
-                published_at: row.get("published_at"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                author_id: row.get("author_id"),
-                tags,
+/// Re-derive the `post_links` rows from every post body, in batches of `batch_size` posts
+/// with one transaction each, so no lock is held for the whole run. A batch's rows stay
+/// locked until it commits, so an edit can't land between the read and the rewrite.
+/// Links are the only data stored from a body: reading times and excerpts are computed
+/// on read, and body `#tags` are never persisted. Posts whose stored links already match
+/// their body are untouched.
+pub async fn reindex_posts(pool: &PgPool, batch_size: i64) -> Result<ReindexReport> {
+    let mut report = ReindexReport::default();
+    let mut after: Option<Uuid> = None;
+
+    loop {
+        let mut tx = pool.begin().await?;
+        let rows = sqlx::query(
+            r#"
+            SELECT id, body FROM posts
+            WHERE $1::uuid IS NULL OR id > $1
+            ORDER BY id LIMIT $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(after)
+        .bind(batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after = Some(last.get("id"));
+
+        for row in &rows {
+            let id: Uuid = row.get("id");
+            let body: String = row.get("body");
+
+            let mut stored: Vec<(String, Option<String>)> = sqlx::query_as(
+                "SELECT to_post_slug, link_text FROM post_links WHERE from_post_id = $1",
+            )
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await?;
+            stored.sort();
+
+            if stored != derived_links(&body) {
+                report.links_written += replace_post_links(&mut tx, id, &body).await?;
+                report.posts_updated += 1;
             }
-        })
-        .collect();
+        }
+        tx.commit().await?;
 
-    Ok(result)
+        report.posts += rows.len();
+        report.batches += 1;
+    }
+
+    Ok(report)
 }
 
-/// Get post statistics
-pub async fn get_post_stats(pool: &PgPool) -> Result<serde_json::Value> {
-    let row: PgRow = sqlx::query(
+/// Every stored wiki-link whose target slug matches no post (nor an old, redirected slug),
+/// as `(target, linking post)` pairs ordered by target. Trashed posts neither link nor count.
+pub async fn list_unresolved_links(pool: &PgPool) -> Result<Vec<(String, LinkingPost)>> {
+    let rows = sqlx::query(
         r#"
-        SELECT
-            COUNT(*) FILTER (WHERE published = true) as published_count,
-            COUNT(*) FILTER (WHERE published = false) as draft_count,
-            COUNT(*) as total_count,
-            COUNT(DISTINCT author_id) as author_count,
-            (SELECT COUNT(*) FROM tags) as tag_count
-        FROM posts
+        SELECT pl.to_post_slug, p.slug, p.title
+        FROM post_links pl
+        JOIN posts p ON p.id = pl.from_post_id AND p.deleted_at IS NULL
+        WHERE NOT EXISTS (
+                SELECT 1 FROM posts t
+                WHERE t.slug = pl.to_post_slug AND t.deleted_at IS NULL
+            )
+            AND NOT EXISTS (SELECT 1 FROM slug_redirects r WHERE r.old_slug = pl.to_post_slug)
+        ORDER BY pl.to_post_slug, p.slug
         "#,
     )
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await?;
 
-    Ok(json!({
-        "published": row.get::<Option<i64>, _>("published_count").unwrap_or(0),
-        "drafts": row.get::<Option<i64>, _>("draft_count").unwrap_or(0),
-        "total": row.get::<Option<i64>, _>("total_count").unwrap_or(0),
-        "authors": row.get::<Option<i64>, _>("author_count").unwrap_or(0),
-        "tags": row.get::<Option<i64>, _>("tag_count").unwrap_or(0)
-    }))
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let post = LinkingPost {
+                slug: row.get("slug"),
+                title: row.get("title"),
+            };
+            (row.get("to_post_slug"), post)
+        })
+        .collect())
 }
 
-/// Search posts with full-text search
-pub async fn search_posts(pool: &PgPool, query: &str) -> Result<Vec<Post>> {
-    let search_pattern = format!("%{}%", query);
+/// Move a post to the trash; it stays restorable until purged
+pub async fn delete_post(pool: &PgPool, id: Uuid) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    mark_if_public(&mut *tx, id).await?;
+    sqlx::query("UPDATE posts SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
 
-    let rows: Vec<PgRow> = sqlx::query(
-        r#"
-        SELECT
-            p.*,
+/// Bring a trashed post back
+pub async fn restore_post(pool: &PgPool, id: Uuid) -> Result<Post> {
+    sqlx::query("UPDATE posts SET deleted_at = NULL, updated_at = $1 WHERE id = $2")
+        .bind(Utc::now())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    get_post_by_id(pool, id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Post not found"))
+}
+
+/// Permanently delete a post, leaving a tombstone if mirrors may have seen it
+pub async fn purge_post(pool: &PgPool, id: Uuid) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    record_tombstones(&mut tx, &[id]).await?;
+    sqlx::query("DELETE FROM posts WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Insert or replace the autosaved draft for a post
+pub async fn upsert_draft(
+    pool: &PgPool,
+    post_id: Uuid,
+    author_id: Uuid,
+    req: &SaveDraftRequest,
+) -> Result<Draft> {
+    let row: PgRow = sqlx::query(
+        r#"
+        WITH saved AS (
+            INSERT INTO post_drafts (id, post_id, title, excerpt, body, author_id, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, now(), now())
+            ON CONFLICT (post_id) DO UPDATE
+            SET title = EXCLUDED.title,
+                excerpt = EXCLUDED.excerpt,
+                body = EXCLUDED.body,
+                author_id = EXCLUDED.author_id,
+                updated_at = now()
+            RETURNING *
+        )
+        SELECT saved.*, p.slug
+        FROM saved
+        JOIN posts p ON p.id = saved.post_id
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(post_id)
+    .bind(&req.title)
+    .bind(&req.excerpt)
+    .bind(&req.body)
+    .bind(author_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(draft_from_row(&row))
+}
+
+/// Get the autosaved draft for a post, if any
+pub async fn get_draft(pool: &PgPool, post_id: Uuid) -> Result<Option<Draft>> {
+    let row: Option<PgRow> = sqlx::query(
+        r#"
+        SELECT d.*, p.slug
+        FROM post_drafts d
+        JOIN posts p ON p.id = d.post_id
+        WHERE d.post_id = $1
+        "#,
+    )
+    .bind(post_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(draft_from_row))
+}
+
+/// Discard a post's autosaved draft
+pub async fn delete_draft(pool: &PgPool, post_id: Uuid) -> Result<()> {
+    sqlx::query("DELETE FROM post_drafts WHERE post_id = $1")
+        .bind(post_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+fn draft_from_row(row: &PgRow) -> Draft {
+    Draft {
+        id: row.get("id"),
+        post_id: row.get("post_id"),
+        slug: row.get("slug"),
+        title: row.get("title"),
+        excerpt: row.get("excerpt"),
+        body: row.get("body"),
+        author_id: row.get("author_id"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Publish a post, first merging its staged draft (`merge`) and clearing the draft slot.
+/// All of it commits together or not at all. A post that is already live only takes the merge.
+pub async fn publish_post(
+    pool: &PgPool,
+    id: Uuid,
+    merge: Option<UpdatePostRequest>,
+) -> Result<Post> {
+    let mut tx = pool.begin().await?;
+
+    if let Some(merge) = merge {
+        apply_post_update(&mut tx, id, merge).await?;
+        sqlx::query("DELETE FROM post_drafts WHERE post_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query(
+        "UPDATE posts SET published = true, published_at = $1, updated_at = $1
+         WHERE id = $2 AND published = false",
+    )
+    .bind(Utc::now())
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    get_post_by_id(pool, id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Post not found"))
+}
+
+/// Publish drafts whose scheduled `publish_at` has passed, returning their slugs.
+/// Posts that aren't fit to publish (see `publish_blocker`) stay drafts and are logged.
+pub async fn publish_scheduled_posts(pool: &PgPool) -> Result<Vec<String>> {
+    let due = sqlx::query(
+        r#"
+        SELECT id, slug, title, body FROM posts
+        WHERE published = false AND publish_at IS NOT NULL AND publish_at <= now()
+            AND deleted_at IS NULL
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut ready: Vec<Uuid> = Vec::new();
+    for row in &due {
+        let slug: &str = row.get("slug");
+        match publish_blocker(row.get("title"), row.get("body")) {
+            Some(reason) => tracing::warn!("Scheduled post {} held back: {}", slug, reason),
+            None => ready.push(row.get("id")),
+        }
+    }
+    if ready.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let slugs: Vec<String> = sqlx::query_scalar(
+        r#"
+        UPDATE posts
+        SET published = true, published_at = publish_at, updated_at = now()
+        WHERE id = ANY($1) AND published = false AND deleted_at IS NULL
+        RETURNING slug
+        "#,
+    )
+    .bind(&ready)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(slugs)
+}
+
+/// Unpublish a post. Any schedule is dropped too, or the scheduler would republish it.
+pub async fn unpublish_post(pool: &PgPool, id: Uuid) -> Result<Post> {
+    let mut tx = pool.begin().await?;
+    mark_if_public(&mut *tx, id).await?;
+    sqlx::query(
+        "UPDATE posts SET published = false, published_at = NULL, publish_at = NULL, updated_at = $1
+         WHERE id = $2",
+    )
+    .bind(Utc::now())
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    get_post_by_id(pool, id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Post not found"))
+}
+
+/// List all posts (including unpublished) for admin
+pub async fn list_all_posts(pool: &PgPool) -> Result<Vec<Post>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT
+            p.*,
             COALESCE(
                 (
                     SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
@@ -664,46 +1544,176 @@ pub async fn search_posts(pool: &PgPool, query: &str) -> Result<Vec<Post>> {
                     ) tags_subq
                 ),
                 '[]'::json
-            ) as tags
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
         FROM posts p
-        WHERE
-            p.title ILIKE $1 OR
-            p.body ILIKE $1 OR
-            p.excerpt ILIKE $1 OR
-            p.slug ILIKE $1
+        WHERE p.deleted_at IS NULL
         GROUP BY p.id
-        ORDER BY p.created_at DESC
+        ORDER BY p.created_at DESC, p.id DESC
         "#
     )
-    .bind(search_pattern)
     .fetch_all(pool)
     .await?;
 
-    let result: Vec<Post> = rows
-        .into_iter()
-        .map(|row| {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
+    let result: Vec<Post> = rows.iter().map(post_from_row).collect();
 
-            Post {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                body: row.get("body"),
-                published: row.get("published"),
-                published_at: row.get("published_at"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                author_id: row.get("author_id"),
-                tags,
-            }
+    Ok(result)
+}
+
+/// Get post statistics
+pub async fn get_post_stats(pool: &PgPool) -> Result<serde_json::Value> {
+    let row: PgRow = sqlx::query(
+        r#"
+        SELECT
+            COUNT(*) FILTER (WHERE published = true) as published_count,
+            COUNT(*) FILTER (WHERE published = false) as draft_count,
+            COUNT(*) as total_count,
+            COUNT(DISTINCT author_id) as author_count,
+            (SELECT COUNT(*) FROM tags) as tag_count
+        FROM posts
+        WHERE deleted_at IS NULL
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(json!({
+        "published": row.get::<Option<i64>, _>("published_count").unwrap_or(0),
+        "drafts": row.get::<Option<i64>, _>("draft_count").unwrap_or(0),
+        "total": row.get::<Option<i64>, _>("total_count").unwrap_or(0),
+        "authors": row.get::<Option<i64>, _>("author_count").unwrap_or(0),
+        "tags": row.get::<Option<i64>, _>("tag_count").unwrap_or(0)
+    }))
+}
+
+/// Live posts published in the last year, counted per `period` (UTC), oldest bucket first.
+/// Empty buckets are omitted.
+pub async fn get_post_timeline(pool: &PgPool, period: StatsPeriod) -> Result<Vec<TimelineBucket>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT
+            date_trunc($1, published_at AT TIME ZONE 'UTC')::date as bucket,
+            COUNT(*) as count
+        FROM posts
+        WHERE published = true
+            AND deleted_at IS NULL
+            AND published_at IS NOT NULL
+            AND published_at >= now() - interval '1 year'
+        GROUP BY bucket
+        ORDER BY bucket ASC
+        "#,
+    )
+    .bind(period.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    let buckets = rows
+        .iter()
+        .map(|row| TimelineBucket {
+            bucket: row.get("bucket"),
+            count: row.get("count"),
         })
         .collect();
 
+    Ok(buckets)
+}
+
+/// Longest and shortest search snippet, in words
+const SNIPPET_MAX_WORDS: u32 = 35;
+const SNIPPET_MIN_WORDS: u32 = 15;
+
+/// `ts_headline` options: matches wrapped in `<mark>`, up to two fragments per snippet
+fn snippet_headline_options() -> String {
+    format!(
+        "StartSel=<mark>, StopSel=</mark>, MaxWords={}, MinWords={}, ShortWord=3, MaxFragments=2, FragmentDelimiter=\" … \"",
+        SNIPPET_MAX_WORDS, SNIPPET_MIN_WORDS
+    )
+}
+
+/// Search posts, pairing each with a `<mark>`-highlighted snippet of its body.
+/// The snippet is raw `ts_headline` output and must be sanitized before display.
+/// When `tag` is given, only posts carrying that tag are matched.
+pub async fn search_posts_with_snippets(
+    pool: &PgPool,
+    query: &str,
+    tag: Option<&str>,
+) -> Result<Vec<(Post, Option<String>)>> {
+    let prefix_query = prefix_tsquery(query);
+    let search_pattern = format!("%{}%", query);
+
+    // Each term is matched as a prefix so partial words ("ha") still find "haskell".
+    // Queries with no usable lexemes (stopwords, punctuation) fall back to substring matching.
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        WITH q AS (
+            SELECT to_tsquery('english', $1) AS query
+        )
+        SELECT
+            p.*,
+            COALESCE(
+                (
+                    SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
+                    FROM (
+                        SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
+                        FROM post_tags pt
+                        JOIN tags t ON pt.tag_id = t.id
+                        WHERE pt.post_id = p.id
+                    ) tags_subq
+                ),
+                '[]'::json
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author,
+            ts_rank(p.search_vector, q.query) as rank,
+            CASE WHEN numnode(q.query) = 0 THEN NULL ELSE
+                ts_headline('english', p.body, q.query, $4)
+            END as snippet
+        FROM posts p, q
+        WHERE p.deleted_at IS NULL AND
+            CASE WHEN numnode(q.query) = 0 THEN
+                p.title ILIKE $2 OR
+                p.body ILIKE $2 OR
+                p.excerpt ILIKE $2 OR
+                p.slug ILIKE $2
+            ELSE
+                p.search_vector @@ q.query
+            END
+            AND (
+                $3::text IS NULL OR EXISTS (
+                    SELECT 1
+                    FROM post_tags pt
+                    JOIN tags t ON pt.tag_id = t.id
+                    WHERE pt.post_id = p.id AND t.name = $3
+                )
+            )
+        ORDER BY rank DESC, p.created_at DESC, p.id DESC
+        "#
+    )
+    .bind(prefix_query)
+    .bind(search_pattern)
+    .bind(tag)
+    .bind(snippet_headline_options())
+    .fetch_all(pool)
+    .await?;
+
+    let result = rows
+        .iter()
+        .map(|row| (post_from_row(row), row.get("snippet")))
+        .collect();
+
     Ok(result)
 }
 
+/// Build a prefix-matching tsquery (`term:* & term:*`) from free-form input.
+/// Only alphanumeric runs are kept, so the result is always valid tsquery syntax.
+fn prefix_tsquery(query: &str) -> String {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("{}:*", term.to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
 /// List all tags
 pub async fn list_tags(pool: &PgPool) -> Result<Vec<Tag>> {
     let rows = sqlx::query("SELECT id, name, color, created_at FROM tags ORDER BY name")
@@ -723,6 +1733,39 @@ pub async fn list_tags(pool: &PgPool) -> Result<Vec<Tag>> {
     Ok(tags)
 }
 
+/// Get a tag by its exact name
+pub async fn get_tag_by_name(pool: &PgPool, name: &str) -> Result<Option<Tag>> {
+    let row = sqlx::query("SELECT id, name, color, created_at FROM tags WHERE name = $1")
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.map(|row| Tag {
+        id: row.get("id"),
+        name: row.get("name"),
+        color: row.get("color"),
+        created_at: row.get("created_at"),
+    }))
+}
+
+/// Return the ids from `ids` that don't match any existing tag
+pub async fn find_missing_tag_ids(pool: &PgPool, ids: &[Uuid]) -> Result<Vec<Uuid>> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let existing: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM tags WHERE id = ANY($1)")
+        .bind(ids)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(ids
+        .iter()
+        .filter(|id| !existing.contains(id))
+        .copied()
+        .collect())
+}
+
 /// Create a new tag
 pub async fn create_tag(pool: &PgPool, req: CreateTagRequest) -> Result<Tag> {
     let id = Uuid::new_v4();
@@ -748,6 +1791,98 @@ pub async fn create_tag(pool: &PgPool, req: CreateTagRequest) -> Result<Tag> {
     Ok(tag)
 }
 
+/// Recreate exported tags and posts in one transaction.
+/// Tags are matched by name and posts by slug; existing ones are left untouched.
+pub async fn import_blog(pool: &PgPool, export: &BlogExport, author_id: Uuid) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut tx = pool.begin().await?;
+
+    // Tags match on their normalized name, the form the unique index enforces
+    let mut tag_ids: HashMap<String, Uuid> = HashMap::new();
+    for tag in &export.tags {
+        let name = normalize_tag_name(&tag.name);
+        let existing: Option<Uuid> = sqlx::query_scalar("SELECT id FROM tags WHERE name = $1")
+            .bind(&name)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let id = Uuid::new_v4();
+                sqlx::query("INSERT INTO tags (id, name, color, created_at) VALUES ($1, $2, $3, $4)")
+                    .bind(id)
+                    .bind(&name)
+                    .bind(&tag.color)
+                    .bind(tag.created_at)
+                    .execute(&mut *tx)
+                    .await?;
+                report.tags.push(name.clone());
+                id
+            }
+        };
+        tag_ids.insert(name, id);
+    }
+
+    for post in &export.posts {
+        let taken: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM posts WHERE slug = $1)")
+            .bind(&post.slug)
+            .fetch_one(&mut *tx)
+            .await?;
+        if taken {
+            report.skipped.push(post.slug.clone());
+            continue;
+        }
+
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO posts (id, slug, title, excerpt, body, published, published_at, created_at, updated_at, author_id, trusted_html, publish_at, noindex)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#
+        )
+        .bind(id)
+        .bind(&post.slug)
+        .bind(&post.title)
+        .bind(&post.excerpt)
+        .bind(&post.body)
+        .bind(post.published)
+        .bind(post.published_at)
+        .bind(post.created_at)
+        .bind(post.updated_at)
+        .bind(author_id)
+        .bind(post.trusted_html)
+        .bind(post.publish_at)
+        .bind(post.noindex)
+        .execute(&mut *tx)
+        .await?;
+
+        replace_post_links(&mut tx, id, &post.body).await?;
+
+        let mut post_tag_ids = Vec::new();
+        for name in &post.tags {
+            let Some(&tag_id) = tag_ids.get(&normalize_tag_name(name)) else {
+                anyhow::bail!("Post '{}' references unknown tag '{}'", post.slug, name);
+            };
+            // "Rust" and "rust" are one tag
+            if !post_tag_ids.contains(&tag_id) {
+                post_tag_ids.push(tag_id);
+            }
+        }
+        for tag_id in post_tag_ids {
+            sqlx::query("INSERT INTO post_tags (post_id, tag_id) VALUES ($1, $2)")
+                .bind(id)
+                .bind(tag_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        report.posts.push(post.slug.clone());
+    }
+
+    tx.commit().await?;
+    Ok(report)
+}
+
 /// Update a tag
 pub async fn update_tag(pool: &PgPool, id: Uuid, req: CreateTagRequest) -> Result<Tag> {
     let row = sqlx::query(
@@ -778,11 +1913,12 @@ pub async fn delete_tag(pool: &PgPool, id: Uuid) -> Result<()> {
     Ok(())
 }
 
-/// Get related posts by tags
+/// Get related posts by tags; only posts sharing at least `min_common_tags` tags count
 pub async fn get_related_posts(
     pool: &PgPool,
     post_id: Uuid,
     limit: i64,
+    min_common_tags: i64,
 ) -> Result<Vec<PostSummary>> {
     let rows: Vec<PgRow> = sqlx::query(
         r#"
@@ -805,46 +1941,261 @@ pub async fn get_related_posts(
                     ) tags_subq
                 ),
                 '[]'::json
-            ) as tags
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
         FROM posts p
         INNER JOIN post_tags pt1 ON pt1.post_id = $1
         INNER JOIN post_tags pt2 ON pt2.tag_id = pt1.tag_id AND pt2.post_id = p.id
         WHERE p.id != $1 AND p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
         GROUP BY p.id
-        ORDER BY common_tags DESC, p.published_at DESC
+        HAVING COUNT(DISTINCT pt2.tag_id) >= $3
+        ORDER BY common_tags DESC, p.published_at DESC, p.created_at DESC, p.id DESC
         LIMIT $2
         "#
     )
     .bind(post_id)
     .bind(limit)
+    .bind(min_common_tags)
     .fetch_all(pool)
     .await?;
 
-    let summaries: Vec<PostSummary> = rows
-        .into_iter()
-        .map(|row| {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
-            let body: String = row.get("body");
-            let reading_time = crate::markdown::calculate_reading_time(&body);
-
-            PostSummary {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                published_at: row.get("published_at"),
-                reading_time,
-                tags,
-            }
-        })
-        .collect();
+    let summaries: Vec<PostSummary> = rows.iter().map(summary_from_row).collect();
 
     Ok(summaries)
 }
 
+/// Most recent published posts, leaving out `exclude`
+pub async fn list_recent_posts_excluding(
+    pool: &PgPool,
+    exclude: &[Uuid],
+    limit: i64,
+) -> Result<Vec<PostSummary>> {
+    let rows: Vec<PgRow> = sqlx::query(
+        r#"
+        SELECT
+            p.id,
+            p.slug,
+            p.title,
+            p.excerpt,
+            p.body,
+            p.published_at,
+            COALESCE(
+                (
+                    SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
+                    FROM (
+                        SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
+                        FROM post_tags pt
+                        JOIN tags t ON pt.tag_id = t.id
+                        WHERE pt.post_id = p.id
+                    ) tags_subq
+                ),
+                '[]'::json
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
+        FROM posts p
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+            AND NOT (p.id = ANY($1))
+        ORDER BY p.published_at DESC, p.created_at DESC, p.id DESC
+        LIMIT $2
+        "#
+    )
+    .bind(exclude)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(summary_from_row).collect())
+}
+
+/// Get the published posts immediately older and newer than `post`.
+/// Uses the same (published_at, created_at, id) ordering as listings so
+/// posts sharing a timestamp still chain deterministically.
+pub async fn get_adjacent_posts(
+    pool: &PgPool,
+    post: &Post,
+) -> Result<(Option<PostSummary>, Option<PostSummary>)> {
+    let Some(published_at) = post.published_at else {
+        return Ok((None, None));
+    };
+
+    let previous = sqlx::query(
+        r#"
+        SELECT
+            p.id,
+            p.slug,
+            p.title,
+            p.excerpt,
+            p.body,
+            p.published_at,
+            COALESCE(
+                (
+                    SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
+                    FROM (
+                        SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
+                        FROM post_tags pt
+                        JOIN tags t ON pt.tag_id = t.id
+                        WHERE pt.post_id = p.id
+                    ) tags_subq
+                ),
+                '[]'::json
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
+        FROM posts p
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+            AND (p.published_at, p.created_at, p.id) < ($1, $2, $3)
+        ORDER BY p.published_at DESC, p.created_at DESC, p.id DESC
+        LIMIT 1
+        "#
+    )
+    .bind(published_at)
+    .bind(post.created_at)
+    .bind(post.id)
+    .fetch_optional(pool)
+    .await?;
+
+    let next = sqlx::query(
+        r#"
+        SELECT
+            p.id,
+            p.slug,
+            p.title,
+            p.excerpt,
+            p.body,
+            p.published_at,
+            COALESCE(
+                (
+                    SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
+                    FROM (
+                        SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
+                        FROM post_tags pt
+                        JOIN tags t ON pt.tag_id = t.id
+                        WHERE pt.post_id = p.id
+                    ) tags_subq
+                ),
+                '[]'::json
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
+        FROM posts p
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+            AND (p.published_at, p.created_at, p.id) > ($1, $2, $3)
+        ORDER BY p.published_at ASC, p.created_at ASC, p.id ASC
+        LIMIT 1
+        "#
+    )
+    .bind(published_at)
+    .bind(post.created_at)
+    .bind(post.id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok((
+        previous.as_ref().map(summary_from_row),
+        next.as_ref().map(summary_from_row),
+    ))
+}
+
+/// Get a page of published posts linking to `slug`, plus the total count
+pub async fn get_backlinks(
+    pool: &PgPool,
+    slug: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<PostSummary>, i64)> {
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM posts p
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+            AND EXISTS (
+                SELECT 1 FROM post_links pl
+                WHERE pl.from_post_id = p.id AND pl.to_post_slug = $1
+            )
+        "#,
+    )
+    .bind(slug)
+    .fetch_one(pool)
+    .await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            p.id,
+            p.slug,
+            p.title,
+            p.excerpt,
+            p.body,
+            p.published_at,
+            COALESCE(
+                (
+                    SELECT json_agg(tag_obj ORDER BY (tag_obj->>'name'))
+                    FROM (
+                        SELECT json_build_object('id', t.id, 'name', t.name, 'color', t.color, 'created_at', t.created_at) as tag_obj
+                        FROM post_tags pt
+                        JOIN tags t ON pt.tag_id = t.id
+                        WHERE pt.post_id = p.id
+                    ) tags_subq
+                ),
+                '[]'::json
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
+        FROM posts p
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+            AND EXISTS (
+                SELECT 1 FROM post_links pl
+                WHERE pl.from_post_id = p.id AND pl.to_post_slug = $1
+            )
+        ORDER BY p.published_at DESC, p.created_at DESC, p.id DESC
+        LIMIT $2 OFFSET $3
+        "#
+    )
+    .bind(slug)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let summaries: Vec<PostSummary> = rows.iter().map(summary_from_row).collect();
+
+    Ok((summaries, total))
+}
+
 /// Get published posts by tag name
-pub async fn get_posts_by_tag(pool: &PgPool, tag_name: &str) -> Result<Vec<PostSummary>> {
+pub async fn get_posts_by_tag(
+    pool: &PgPool,
+    tag_name: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<PostSummary>, i64)> {
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM posts p
+        WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
+            AND EXISTS (
+                SELECT 1 FROM post_tags pt2
+                JOIN tags t2 ON pt2.tag_id = t2.id
+                WHERE pt2.post_id = p.id AND t2.name = $1
+            )
+        "#,
+    )
+    .bind(tag_name)
+    .fetch_one(pool)
+    .await?;
+
     let rows = sqlx::query(
         r#"
         SELECT
@@ -865,9 +2216,12 @@ pub async fn get_posts_by_tag(pool: &PgPool, tag_name: &str) -> Result<Vec<PostS
                     ) tags_subq
                 ),
                 '[]'::json
-            ) as tags
+            ) as tags,
+            (SELECT json_build_object('id', u.id, 'username', u.username) FROM users u WHERE u.id = p.author_id) as author
         FROM posts p
         WHERE p.published = true
+            AND (p.publish_at IS NULL OR p.publish_at <= now())
+            AND p.deleted_at IS NULL
             AND p.id IN (
                 SELECT pt2.post_id
                 FROM post_tags pt2
@@ -875,34 +2229,19 @@ pub async fn get_posts_by_tag(pool: &PgPool, tag_name: &str) -> Result<Vec<PostS
                 WHERE t2.name = $1
             )
         GROUP BY p.id
-        ORDER BY p.published_at DESC
+        ORDER BY p.published_at DESC, p.created_at DESC, p.id DESC
+        LIMIT $2 OFFSET $3
         "#
     )
     .bind(tag_name)
+    .bind(limit)
+    .bind(offset)
     .fetch_all(pool)
     .await?;
 
-    let summaries: Vec<PostSummary> = rows
-        .into_iter()
-        .map(|row| {
-            let tags_json: serde_json::Value = row.get("tags");
-            let tags: Vec<Tag> = serde_json::from_value(tags_json).unwrap_or_default();
-            let body: String = row.get("body");
-            let reading_time = crate::markdown::calculate_reading_time(&body);
+    let summaries: Vec<PostSummary> = rows.iter().map(summary_from_row).collect();
 
-            PostSummary {
-                id: row.get("id"),
-                slug: row.get("slug"),
-                title: row.get("title"),
-                excerpt: row.get("excerpt"),
-                published_at: row.get("published_at"),
-                reading_time,
-                tags,
-            }
-        })
-        .collect();
-
-    Ok(summaries)
+    Ok((summaries, total))
 }
 
 /// Get tag statistics
@@ -914,10 +2253,10 @@ pub async fn get_tag_stats(pool: &PgPool) -> Result<Vec<crate::handlers::tags::T
             t.name,
             t.color,
             t.created_at,
-            COUNT(pt.post_id) as post_count
+            COUNT(p.id) as post_count
         FROM tags t
         LEFT JOIN post_tags pt ON t.id = pt.tag_id
-        LEFT JOIN posts p ON pt.post_id = p.id AND p.published = true
+        LEFT JOIN posts p ON pt.post_id = p.id AND p.published = true AND p.deleted_at IS NULL
         GROUP BY t.id, t.name, t.color, t.created_at
         ORDER BY post_count DESC, t.name
         "#
@@ -925,22 +2264,461 @@ pub async fn get_tag_stats(pool: &PgPool) -> Result<Vec<crate::handlers::tags::T
     .fetch_all(pool)
     .await?;
 
-    let stats: Vec<crate::handlers::tags::TagStats> = rows
-        .into_iter()
-        .map(|row| {
-            let tag = Tag {
-                id: row.get("id"),
-                name: row.get("name"),
-                color: row.get("color"),
-                created_at: row.get("created_at"),
-            };
-            let post_count: i64 = row.get("post_count");
-            crate::handlers::tags::TagStats {
-                tag,
-                post_count: post_count as usize,
+    Ok(rows.iter().map(tag_stats_from_row).collect())
+}
+
+/// Tags whose name contains `query`, names starting with it first, then by how many
+/// live posts use them. An empty query returns the most-used tags.
+pub async fn search_tags(
+    pool: &PgPool,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<crate::handlers::tags::TagStats>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            t.id,
+            t.name,
+            t.color,
+            t.created_at,
+            COUNT(p.id) as post_count
+        FROM tags t
+        LEFT JOIN post_tags pt ON t.id = pt.tag_id
+        LEFT JOIN posts p ON pt.post_id = p.id AND p.published = true AND p.deleted_at IS NULL
+        WHERE strpos(t.name, $1) > 0
+        GROUP BY t.id, t.name, t.color, t.created_at
+        ORDER BY strpos(t.name, $1) = 1 DESC, post_count DESC, t.name
+        LIMIT $2
+        "#,
+    )
+    .bind(query)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(tag_stats_from_row).collect())
+}
+
+fn tag_stats_from_row(row: &PgRow) -> crate::handlers::tags::TagStats {
+    let post_count: i64 = row.get("post_count");
+    crate::handlers::tags::TagStats {
+        tag: Tag {
+            id: row.get("id"),
+            name: row.get("name"),
+            color: row.get("color"),
+            created_at: row.get("created_at"),
+        },
+        post_count: post_count as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derived_links_match_stored_shape() {
+        let links = derived_links("See [[Zeta Notes]] then [[Alpha]].");
+        assert_eq!(
+            links,
+            vec![
+                ("alpha".to_string(), Some("Alpha".to_string())),
+                ("zeta-notes".to_string(), Some("Zeta Notes".to_string())),
+            ]
+        );
+        assert!(derived_links("No links here").is_empty());
+
+        // A heading link points at the post, not a page named after the heading
+        let links = derived_links("See [[Zeta Notes#Setup]].");
+        assert_eq!(links, vec![("zeta-notes".to_string(), Some("Zeta Notes#Setup".to_string()))]);
+    }
+
+    #[test]
+    fn test_snippet_options_mark_matches() {
+        let options = snippet_headline_options();
+        assert!(options.contains("StartSel=<mark>, StopSel=</mark>"));
+        assert!(options.contains(&format!("MaxWords={}", SNIPPET_MAX_WORDS)));
+        assert!(options.contains(&format!("MinWords={}", SNIPPET_MIN_WORDS)));
+        // Postgres rejects MinWords >= MaxWords
+        assert!(SNIPPET_MIN_WORDS < SNIPPET_MAX_WORDS);
+    }
+
+    #[test]
+    fn test_prefix_tsquery() {
+        assert_eq!(prefix_tsquery("ha"), "ha:*");
+        assert_eq!(prefix_tsquery("Rust, async!"), "rust:* & async:*");
+        assert_eq!(prefix_tsquery("'); DROP--"), "drop:*");
+        assert_eq!(prefix_tsquery("  !! "), "");
+    }
+
+    #[test]
+    fn test_sample_post_uses_all_sample_tags() {
+        let tag_ids: Vec<Uuid> = SAMPLE_TAGS.iter().map(|_| Uuid::new_v4()).collect();
+        let post = sample_haskell_post(tag_ids.clone());
+        assert_eq!(post.slug, "haskell-elegance");
+        assert_eq!(post.tags, tag_ids);
+        assert!(post.published);
+        assert!(post.body.starts_with("# The Quiet Elegance of Haskell"));
+    }
+
+    async fn create_author(pool: &PgPool, username: &str) -> Result<Uuid> {
+        Ok(create_user(pool, username, "not-a-real-hash", Role::Admin).await?.id)
+    }
+
+    /// A published post with `tags` that went live at `at`
+    fn published_post(slug: &str, tags: Vec<Uuid>, at: DateTime<Utc>) -> CreatePostRequest {
+        CreatePostRequest {
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            excerpt: String::new(),
+            body: format!("# {}\n\nBody text for {}.", slug, slug),
+            tags,
+            published: true,
+            trusted_html: false,
+            noindex: false,
+            publish_at: Some(at),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_posts_sharing_a_timestamp_paginate_stably(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let tag = create_tag(
+            &pool,
+            CreateTagRequest {
+                name: "bulk".to_string(),
+                color: "blue".to_string(),
+            },
+        )
+        .await?;
+        let at = Utc::now() - chrono::Duration::days(1);
+        for i in 0..7 {
+            let req = published_post(&format!("bulk-{}", i), vec![tag.id], at);
+            create_post(&pool, req, author).await?;
+        }
+        // A bulk import can share created_at too, leaving only the id to break the tie
+        sqlx::query("UPDATE posts SET created_at = $1").bind(at).execute(&pool).await?;
+
+        let listed: Vec<Uuid> = list_published_posts(&pool).await?.iter().map(|p| p.id).collect();
+        let mut by_id = listed.clone();
+        by_id.sort_by(|a, b| b.cmp(a));
+        assert_eq!(listed, by_id);
+
+        // Every page walk sees each post exactly once, in the listing's order
+        for _ in 0..3 {
+            let mut paged = Vec::new();
+            loop {
+                let (page, total) = get_posts_by_tag(&pool, "bulk", 3, paged.len() as i64).await?;
+                assert_eq!(total, 7);
+                if page.is_empty() {
+                    break;
+                }
+                paged.extend(page.iter().map(|p| p.id));
             }
-        })
-        .collect();
+            assert_eq!(paged, listed);
+        }
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_seeding_an_empty_database(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "admin").await?;
+
+        let report = seed_sample_posts(&pool, author, false).await?;
+        assert!(!report.skipped);
+        assert_eq!(report.posts, vec!["haskell-elegance"]);
+        let names: Vec<&str> = SAMPLE_TAGS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(report.tags, names);
+
+        let post = get_post_by_slug(&pool, "haskell-elegance").await?.expect("sample post");
+        let mut tags: Vec<String> = post.tags.into_iter().map(|t| t.name).collect();
+        tags.sort();
+        let mut expected: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        expected.sort();
+        assert_eq!(tags, expected);
+
+        // Posts now exist, so an unforced seed leaves everything alone
+        let again = seed_sample_posts(&pool, author, false).await?;
+        assert!(again.skipped);
+        assert!(again.posts.is_empty() && again.tags.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_backlinks_are_paginated(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let now = Utc::now();
+        let hub = published_post("hub", vec![], now - chrono::Duration::days(10));
+        create_post(&pool, hub, author).await?;
+        for (i, slug) in ["oldest", "middle", "newest"].iter().enumerate() {
+            let req = CreatePostRequest {
+                body: "Builds on [[Hub]].".to_string(),
+                ..published_post(slug, vec![], now - chrono::Duration::days(3 - i as i64))
+            };
+            create_post(&pool, req, author).await?;
+        }
+        // Drafts link too, but don't show up publicly
+        let draft = CreatePostRequest {
+            body: "Draft about [[Hub]].".to_string(),
+            published: false,
+            publish_at: None,
+            ..published_post("draft", vec![], now)
+        };
+        create_post(&pool, draft, author).await?;
+
+        let slugs = |page: Vec<PostSummary>| -> Vec<String> {
+            page.into_iter().map(|p| p.slug).collect()
+        };
+        let (first, total) = get_backlinks(&pool, "hub", 2, 0).await?;
+        assert_eq!(total, 3);
+        assert_eq!(slugs(first), vec!["newest", "middle"]);
+        let (second, total) = get_backlinks(&pool, "hub", 2, 2).await?;
+        assert_eq!(total, 3);
+        assert_eq!(slugs(second), vec!["oldest"]);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_trashed_posts_are_hidden_until_restored(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let tag = create_tag(
+            &pool,
+            CreateTagRequest {
+                name: "notes".to_string(),
+                color: "green".to_string(),
+            },
+        )
+        .await?;
+        let at = Utc::now() - chrono::Duration::days(1);
+        let kept = create_post(&pool, published_post("kept", vec![tag.id], at), author).await?;
+        let trashed = published_post("trashed", vec![tag.id], at);
+        let trashed = create_post(&pool, trashed, author).await?;
+
+        delete_post(&pool, trashed.id).await?;
+
+        assert!(get_post_by_slug(&pool, "trashed").await?.is_none());
+        let found = get_post_by_slug_any(&pool, "trashed").await?.expect("trashed post");
+        assert!(found.deleted_at.is_some());
+        let listed: Vec<Uuid> = list_published_posts(&pool).await?.iter().map(|p| p.id).collect();
+        assert_eq!(listed, vec![kept.id]);
+        let all: Vec<Uuid> = list_all_posts(&pool).await?.iter().map(|p| p.id).collect();
+        assert_eq!(all, vec![kept.id]);
+        // Tag counts ignore the trash too
+        let stats = get_tag_stats(&pool).await?;
+        assert_eq!(stats[0].post_count, 1);
+
+        let restored = restore_post(&pool, trashed.id).await?;
+        assert!(restored.deleted_at.is_none());
+        assert!(get_post_by_slug(&pool, "trashed").await?.is_some());
+
+        purge_post(&pool, trashed.id).await?;
+        assert!(get_post_by_slug_any(&pool, "trashed").await?.is_none());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_deleting_seeded_content_keeps_real_posts(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "admin").await?;
+        seed_sample_posts(&pool, author, false).await?;
+
+        // A real post reusing one of the sample tags
+        let haskell = get_tag_by_name(&pool, "haskell").await?.expect("sample tag");
+        let at = Utc::now() - chrono::Duration::days(1);
+        create_post(&pool, published_post("my-monads", vec![haskell.id], at), author).await?;
+
+        let report = delete_seeded_content(&pool).await?;
+        assert_eq!(report.posts, vec!["haskell-elegance"]);
+        assert!(!report.tags.contains(&"haskell".to_string()));
+        assert_eq!(report.tags.len(), SAMPLE_TAGS.len() - 1);
+
+        assert!(get_post_by_slug_any(&pool, "haskell-elegance").await?.is_none());
+        assert!(get_post_by_slug(&pool, "my-monads").await?.is_some());
+        assert!(get_tag_by_name(&pool, "haskell").await?.is_some());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_posts_by_tag_are_paginated(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let tag = create_tag(
+            &pool,
+            CreateTagRequest {
+                name: "popular".to_string(),
+                color: "peach".to_string(),
+            },
+        )
+        .await?;
+        let now = Utc::now();
+        for day in 1..=5 {
+            let at = now - chrono::Duration::days(day);
+            let req = published_post(&format!("day-{}", day), vec![tag.id], at);
+            create_post(&pool, req, author).await?;
+        }
+        // Untagged posts don't count towards the tag
+        create_post(&pool, published_post("untagged", vec![], now), author).await?;
+
+        let mut pages = Vec::new();
+        for offset in [0, 2, 4, 6] {
+            let (page, total) = get_posts_by_tag(&pool, "popular", 2, offset).await?;
+            assert_eq!(total, 5);
+            pages.push(page.into_iter().map(|p| p.slug).collect::<Vec<_>>());
+        }
+        assert_eq!(
+            pages,
+            vec![
+                vec!["day-1", "day-2"],
+                vec!["day-3", "day-4"],
+                vec!["day-5"],
+                vec![],
+            ]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_changed_since_reports_only_newer_changes(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let long_ago = Utc::now() - chrono::Duration::days(30);
+        let older = create_post(&pool, published_post("older", vec![], long_ago), author).await?;
+        let newer = create_post(&pool, published_post("newer", vec![], long_ago), author).await?;
+        let trashed = published_post("trashed", vec![], long_ago);
+        let trashed = create_post(&pool, trashed, author).await?;
+        sqlx::query("UPDATE posts SET updated_at = $1")
+            .bind(long_ago)
+            .execute(&pool)
+            .await?;
 
-    Ok(stats)
+        let since = Utc::now() - chrono::Duration::hours(1);
+        update_post(
+            &pool,
+            newer.id,
+            UpdatePostRequest {
+                title: Some("Newer, edited".to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        delete_post(&pool, trashed.id).await?;
+
+        let changed = list_posts_changed_since(&pool, since, None, 500).await?;
+        let ids: Vec<Uuid> = changed.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![newer.id, trashed.id]);
+        assert!(!ids.contains(&older.id));
+        assert!(changed[1].deleted_at.is_some());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_changed_since_hides_drafts_and_reports_purges(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let draft = CreatePostRequest {
+            published: false,
+            publish_at: None,
+            ..published_post("secret-draft", vec![], since)
+        };
+        create_post(&pool, draft, author).await?;
+        let scheduled = CreatePostRequest {
+            published: false,
+            ..published_post("secret-launch", vec![], Utc::now() + chrono::Duration::days(7))
+        };
+        let scheduled = create_post(&pool, scheduled, author).await?;
+        let gone = create_post(&pool, published_post("gone", vec![], since), author).await?;
+
+        // Trashing a never-public post and purging a public one
+        delete_post(&pool, scheduled.id).await?;
+        purge_post(&pool, gone.id).await?;
+
+        let changed = list_posts_changed_since(&pool, since, None, 500).await?;
+        assert!(changed.is_empty(), "{:?}", changed.iter().map(|p| &p.slug).collect::<Vec<_>>());
+
+        let purged = list_tombstones_since(&pool, since, None, 500).await?;
+        assert_eq!(purged.len(), 1);
+        assert_eq!((purged[0].id, purged[0].slug.as_str()), (gone.id, "gone"));
+        assert!(purged[0].deleted);
+        // Resuming after the tombstone's own position finds nothing more
+        let rest = list_tombstones_since(&pool, purged[0].changed_at, Some(gone.id), 500).await?;
+        assert!(rest.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_unpublished_post_stays_unpublished(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let at = Utc::now() - chrono::Duration::minutes(5);
+        let post = create_post(&pool, published_post("pulled", vec![], at), author).await?;
+
+        let pulled = unpublish_post(&pool, post.id).await?;
+        assert!(!pulled.published);
+        assert!(pulled.publish_at.is_none());
+
+        // The scheduler's next tick finds nothing due
+        assert!(publish_scheduled_posts(&pool).await?.is_empty());
+        let post = get_post_by_id(&pool, post.id).await?.expect("post");
+        assert!(!post.published);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_stale_update_is_refused(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let at = Utc::now() - chrono::Duration::minutes(5);
+        let post = create_post(&pool, published_post("edited", vec![], at), author).await?;
+
+        // Two editors loaded the same version; the first save wins
+        let save = |title: &str| UpdatePostRequest {
+            title: Some(title.to_string()),
+            expected_updated_at: Some(post.updated_at),
+            ..Default::default()
+        };
+        update_post(&pool, post.id, save("First save")).await?;
+        let err = update_post(&pool, post.id, save("Second save")).await.unwrap_err();
+        assert!(err.downcast_ref::<StalePostUpdate>().is_some());
+        assert!(matches!(crate::error::AppError::from(err), crate::error::AppError::Conflict(_)));
+
+        let post = get_post_by_id(&pool, post.id).await?.expect("post");
+        assert_eq!(post.title, "First save");
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_search_snippet_marks_the_hit(pool: PgPool) -> Result<()> {
+        let author = create_author(&pool, "writer").await?;
+        let at = Utc::now() - chrono::Duration::minutes(5);
+        let mut req = published_post("type-classes", vec![], at);
+        req.body = "# Type classes\n\nNotes on how Haskell resolves instances.".to_string();
+        create_post(&pool, req, author).await?;
+        create_post(&pool, published_post("unrelated", vec![], at), author).await?;
+
+        let hits = search_posts_with_snippets(&pool, "haskell", None).await?;
+        assert_eq!(hits.len(), 1);
+        let (post, snippet) = &hits[0];
+        assert_eq!(post.slug, "type-classes");
+        let snippet = snippet.as_deref().expect("snippet");
+        assert!(snippet.contains("<mark>Haskell</mark>"), "{}", snippet);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_abandoned_idempotency_claim_lapses(pool: PgPool) -> Result<()> {
+        let user = create_author(&pool, "writer").await?;
+        let claim = claim_idempotency_key(&pool, user, "k", "hash-a").await?;
+        assert!(matches!(claim, IdempotencyClaim::New));
+        let claim = claim_idempotency_key(&pool, user, "k", "hash-a").await?;
+        assert!(matches!(claim, IdempotencyClaim::InProgress));
+        let claim = claim_idempotency_key(&pool, user, "k", "hash-b").await?;
+        assert!(matches!(claim, IdempotencyClaim::Mismatch));
+
+        // The first handler was killed mid-request: once its lease lapses a retry takes over
+        sqlx::query("UPDATE idempotency_keys SET locked_until = now() - interval '1 second'")
+            .execute(&pool)
+            .await?;
+        let claim = claim_idempotency_key(&pool, user, "k", "hash-a").await?;
+        assert!(matches!(claim, IdempotencyClaim::New));
+
+        complete_idempotency_key(&pool, user, "k", &json!({ "slug": "done" })).await?;
+        let claim = claim_idempotency_key(&pool, user, "k", "hash-a").await?;
+        assert!(matches!(claim, IdempotencyClaim::Replay(_)));
+        Ok(())
+    }
 }
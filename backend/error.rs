@@ -1,15 +1,21 @@
 use shuttle_axum::axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
 
+use crate::models::FieldError;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    Database(sqlx::Error),
+
+    /// Every pooled connection stayed busy past the acquire timeout
+    #[error("Database busy: {0}")]
+    DatabaseBusy(sqlx::Error),
 
     #[error("Authentication failed: {0}")]
     Unauthorized(String),
@@ -23,70 +29,178 @@ pub enum AppError {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Unprocessable: {0}")]
+    Unprocessable(String),
+
+    #[error("Gone: {0}")]
+    Gone(String),
+
     #[error("Internal server error: {0}")]
     Internal(String),
 
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
 
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Validation failed: {0:?}")]
+    Validation(Vec<FieldError>),
 
     #[error("JWT error: {0}")]
     Jwt(#[from] jsonwebtoken::errors::Error),
 
     #[error("Anyhow error: {0}")]
-    Anyhow(#[from] anyhow::Error),
+    Anyhow(anyhow::Error),
+}
+
+/// Seconds a client is told to wait before retrying a busy-database 503
+const BUSY_RETRY_AFTER_SECS: &str = "1";
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::PoolTimedOut => AppError::DatabaseBusy(e),
+            // A check-then-insert lost a race; the constraint caught the duplicate
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                AppError::Conflict("Resource already exists".to_string())
+            }
+            e => AppError::Database(e),
+        }
+    }
+}
+
+// db helpers return anyhow, so a pool timeout or stale update can arrive wrapped in one
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(stale) = e.downcast_ref::<crate::db::StalePostUpdate>() {
+            return AppError::Conflict(stale.to_string());
+        }
+        match e.downcast::<sqlx::Error>() {
+            Ok(e) => e.into(),
+            Err(e) => AppError::Anyhow(e),
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
+        match self {
             AppError::Database(ref e) => {
                 tracing::error!("Database error: {}", e);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "A database error occurred".to_string(),
-                )
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, "A database error occurred")
             }
             AppError::Unauthorized(ref msg) => {
                 tracing::warn!("Unauthorized attempt: {}", msg);
-                (StatusCode::UNAUTHORIZED, msg.clone())
+                error_response(StatusCode::UNAUTHORIZED, msg)
             }
             AppError::Forbidden(ref msg) => {
                 tracing::warn!("Forbidden access: {}", msg);
-                (StatusCode::FORBIDDEN, msg.clone())
+                error_response(StatusCode::FORBIDDEN, msg)
             }
             AppError::NotFound(ref msg) => {
                 tracing::info!("Resource not found: {}", msg);
-                (StatusCode::NOT_FOUND, msg.clone())
+                error_response(StatusCode::NOT_FOUND, msg)
             }
             AppError::BadRequest(ref msg) => {
                 tracing::info!("Bad request: {}", msg);
-                (StatusCode::BAD_REQUEST, msg.clone())
+                error_response(StatusCode::BAD_REQUEST, msg)
+            }
+            AppError::Conflict(ref msg) => {
+                tracing::info!("Conflict: {}", msg);
+                error_response(StatusCode::CONFLICT, msg)
+            }
+            AppError::Unprocessable(ref msg) => {
+                tracing::info!("Unprocessable request: {}", msg);
+                error_response(StatusCode::UNPROCESSABLE_ENTITY, msg)
+            }
+            AppError::Gone(ref msg) => {
+                tracing::info!("Resource gone: {}", msg);
+                error_response(StatusCode::GONE, msg)
             }
             AppError::Internal(ref msg) => {
                 tracing::error!("Internal error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, msg.clone())
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, msg)
+            }
+            AppError::TooManyRequests(ref msg) => {
+                tracing::warn!("Rate limited: {}", msg);
+                error_response(StatusCode::TOO_MANY_REQUESTS, msg)
+            }
+            AppError::ServiceUnavailable(ref msg) => {
+                tracing::warn!("Service unavailable: {}", msg);
+                error_response(StatusCode::SERVICE_UNAVAILABLE, msg)
+            }
+            // Field-level errors carry an extra `fields` list alongside the usual shape
+            AppError::Validation(fields) => {
+                tracing::info!("Validation failed: {:?}", fields);
+                let status = StatusCode::BAD_REQUEST;
+                let body = Json(json!({
+                    "error": "Validation failed",
+                    "status": status.as_u16(),
+                    "fields": fields,
+                }));
+                (status, body).into_response()
+            }
+            AppError::DatabaseBusy(ref e) => {
+                tracing::warn!("Database busy: {}", e);
+                let status = StatusCode::SERVICE_UNAVAILABLE;
+                let body = Json(json!({
+                    "error": "Service busy, please retry",
+                    "status": status.as_u16(),
+                }));
+                let retry = [(header::RETRY_AFTER, BUSY_RETRY_AFTER_SECS)];
+                (status, retry, body).into_response()
             }
-
             AppError::Jwt(ref e) => {
                 tracing::warn!("JWT error: {}", e);
-                (StatusCode::UNAUTHORIZED, "Invalid token".to_string())
+                error_response(StatusCode::UNAUTHORIZED, "Invalid token")
             }
             AppError::Anyhow(ref e) => {
                 tracing::error!("Anyhow error: {}", e);
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "An internal error occurred".to_string(),
-                )
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, "An internal error occurred")
             }
-        };
-
-        let body = Json(json!({
-            "error": error_message,
-            "status": status.as_u16(),
-        }));
-
-        (status, body).into_response()
+        }
     }
 }
 
+/// The usual error shape: a message and the status code repeated in the body
+fn error_response(status: StatusCode, message: &str) -> Response {
+    let body = Json(json!({
+        "error": message,
+        "status": status.as_u16(),
+    }));
+    (status, body).into_response()
+}
+
 // Convenience type alias
 pub type Result<T> = std::result::Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_timeout_is_busy_not_internal() {
+        let res = AppError::from(sqlx::Error::PoolTimedOut).into_response();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers()[header::RETRY_AFTER], "1");
+
+        // Same through the anyhow errors db helpers return
+        let wrapped = anyhow::Error::from(sqlx::Error::PoolTimedOut);
+        assert!(matches!(AppError::from(wrapped), AppError::DatabaseBusy(_)));
+
+        let res = AppError::from(sqlx::Error::RowNotFound).into_response();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let stale = AppError::from(anyhow::Error::from(crate::db::StalePostUpdate));
+        assert!(matches!(stale, AppError::Conflict(_)));
+
+        let res = AppError::Unprocessable("key reused".to_string()).into_response();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let other = AppError::from(anyhow::anyhow!("boom"));
+        assert!(matches!(other, AppError::Anyhow(_)));
+    }
+}
@@ -1,56 +1,281 @@
 use shuttle_axum::axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, StatusCode},
     response::IntoResponse,
     Json,
 };
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::{
     auth::AuthUser,
     db,
     error::AppError,
-    markdown::{calculate_reading_time, extract_tags, render_obsidian_markdown},
+    handlers::{
+        posts::SearchParams,
+        tags::{is_valid_tag_name, normalize_tag_name, validate_tag_request, IMPORTED_TAG_COLOR},
+    },
+    markdown::{
+        build_toc, calculate_reading_time, check_markdown_size, count_words, document_counts,
+        extract_cover_image, extract_tags, link_refs, link_slug, lint_structure, normalize_excerpt,
+        outline_tree, parse_frontmatter, reading_minutes, render_with_deadline, render_with_timings,
+        slugify, split_frontmatter, strip_title_heading, validate_frontmatter_strict, Frontmatter,
+        RenderOptions, DEFAULT_WORDS_PER_MINUTE,
+    },
     models::{
-        AdminPostSummary, CreatePostRequest, CreateTagRequest, MarkdownPreviewRequest, MarkdownPreviewResponse, Post,
-        Tag, UpdatePostRequest,
+        AdminPostSummary, AutosaveRequest, BlogExport, CreatePostRequest, CreateTagRequest, Draft,
+        ExportedPost, FieldError, IdempotencyClaim, ImportReport, LinkingPost, LintKind,
+        LintRequest, LintWarning, MarkdownPreviewRequest, MarkdownPreviewResponse, NavPreview,
+        OutlineRequest, OutlineResponse, PhaseTiming, Post, ReindexReport, RenderTimingResponse,
+        SaveDraftRequest, SeedReport, StatsPeriod, Tag, TimelineBucket, UnresolvedLink,
+        UpdatePostRequest, EXPORT_SCHEMA_VERSION,
     },
     state::AppState,
+    webhook::WebhookEvent,
 };
 
-/// Create a new blog post
+/// Create a new blog post.
+/// With an `Idempotency-Key` header, retries of a processed request replay the
+/// original response instead of creating the post again.
 pub async fn create_post(
     State(state): State<Arc<AppState>>,
     user: AuthUser,
+    headers: HeaderMap,
     Json(req): Json<CreatePostRequest>,
-) -> Result<(StatusCode, Json<Post>), AppError> {
-    // Validate slug format
-    if !is_valid_slug(&req.slug) {
-        return Err(AppError::BadRequest(
-            "Invalid slug format. Use lowercase letters, numbers, and hyphens only.".to_string(),
-        ));
+) -> Result<Created<Post>, AppError> {
+    let Some(key) = idempotency_key(&headers)? else {
+        let post = insert_post(&state, &user, req).await?;
+        return Ok(created_post(post));
+    };
+
+    let request_hash = request_fingerprint(&req)?;
+    match db::claim_idempotency_key(&state.pool, user.user_id, &key, &request_hash).await? {
+        IdempotencyClaim::Replay(response) => Ok(created_post(replay_post(response)?)),
+        IdempotencyClaim::InProgress => Err(AppError::Conflict(
+            "A request with this Idempotency-Key is still being processed".to_string(),
+        )),
+        IdempotencyClaim::Mismatch => Err(AppError::Unprocessable(
+            "This Idempotency-Key was already used with a different request body".to_string(),
+        )),
+        IdempotencyClaim::New => match insert_post(&state, &user, req).await {
+            Ok(post) => {
+                let response = serde_json::to_value(&post)
+                    .map_err(|e| AppError::Internal(format!("Failed to store response: {}", e)))?;
+                db::complete_idempotency_key(&state.pool, user.user_id, &key, &response).await?;
+                Ok(created_post(post))
+            }
+            Err(e) => {
+                db::release_idempotency_key(&state.pool, user.user_id, &key).await?;
+                Err(e)
+            }
+        },
     }
+}
 
-    // Check if slug already exists
-    if let Some(_) = db::get_post_by_slug(&state.pool, &req.slug).await? {
+/// Longest `Idempotency-Key` accepted
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// The request's `Idempotency-Key`, if it sent one
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, AppError> {
+    let Some(value) = headers.get("idempotency-key") else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| AppError::BadRequest("Idempotency-Key must be visible ASCII".to_string()))?
+        .trim();
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
         return Err(AppError::BadRequest(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_IDEMPOTENCY_KEY_LEN
+        )));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// SHA-256 of the request as received, binding an `Idempotency-Key` to one body
+fn request_fingerprint(req: &CreatePostRequest) -> Result<String, AppError> {
+    let bytes = serde_json::to_vec(req)
+        .map_err(|e| AppError::Internal(format!("Failed to hash request: {}", e)))?;
+    let digest = Sha256::digest(&bytes);
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// A 201 response with a `Location` header pointing at the new resource
+type Created<T> = (StatusCode, [(HeaderName, String); 1], Json<T>);
+
+fn created<T>(location: String, body: T) -> Created<T> {
+    (StatusCode::CREATED, [(header::LOCATION, location)], Json(body))
+}
+
+fn created_post(post: Post) -> Created<Post> {
+    created(format!("/api/posts/{}", post.slug), post)
+}
+
+/// Rebuild the post from a stored response
+fn replay_post(response: serde_json::Value) -> Result<Post, AppError> {
+    serde_json::from_value(response)
+        .map_err(|e| AppError::Internal(format!("Stored idempotent response is unreadable: {}", e)))
+}
+
+async fn insert_post(state: &AppState, user: &AuthUser, mut req: CreatePostRequest) -> Result<Post, AppError> {
+    // Empty fields fall back to the body's front-matter, as in an Obsidian note.
+    // Its tags are only created along with the post, once every check has passed.
+    let frontmatter_tags = apply_frontmatter(&mut req);
+
+    // The same checks `validate_post` reports, which judge the slug trimmed
+    req.slug = req.slug.trim().to_string();
+    let errors = create_field_errors(&req, state.strict_frontmatter, state.require_cover_image);
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    // Derive a free slug from the title when none was given
+    if req.slug.is_empty() {
+        let base = slugify(&req.title);
+        let taken = db::list_slugs_with_prefix(&state.pool, &base).await?;
+        req.slug = dedupe_slug(&base, &taken);
+    }
+
+    // Check if slug already exists
+    if let Some(_) = db::get_post_by_slug_any(&state.pool, &req.slug).await? {
+        return Err(AppError::Conflict(format!(
             "A post with slug '{}' already exists",
             req.slug
         )));
     }
 
+    ensure_tags_exist(state, &req.tags).await?;
+
     // Extract tags from markdown content if not explicitly provided
     let auto_tags = extract_tags(&req.body);
 
+    // Blank excerpts are generated from the body, unless it is too short to need one
+    req.excerpt = normalize_excerpt(&req.excerpt, &req.body, state.excerpt_min_body_chars);
+
     // Create the post
-    let post = db::create_post(&state.pool, req, user.user_id).await?;
+    let new_tags: Vec<CreateTagRequest> = frontmatter_tags
+        .into_iter()
+        .map(|name| CreateTagRequest {
+            name,
+            color: IMPORTED_TAG_COLOR.to_string(),
+        })
+        .collect();
+    let post = db::create_post_with_tags(&state.pool, req, &new_tags, user.user_id).await?;
 
     // Log the creation
     tracing::info!("Post created: {} by user {}", post.slug, user.username);
+    state.notify(WebhookEvent::Created, &post);
+    if post.is_live() {
+        state.notify(WebhookEvent::Published, &post);
+    }
+
+    Ok(post)
+}
+
+/// Dry run of `create_post`: every check it makes, reported together, without inserting
+pub async fn validate_post(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+    Json(mut req): Json<CreatePostRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    apply_frontmatter(&mut req);
+    let mut errors = create_field_errors(&req, state.strict_frontmatter, state.require_cover_image);
+
+    // A blank slug is derived from the title and deduplicated, so only explicit ones can clash
+    let slug = req.slug.trim();
+    if !slug.is_empty()
+        && is_valid_slug(slug)
+        && db::get_post_by_slug_any(&state.pool, slug).await?.is_some()
+    {
+        errors.push(FieldError::new(
+            "slug",
+            format!("A post with slug '{}' already exists", slug),
+        ));
+    }
+
+    let missing = db::find_missing_tag_ids(&state.pool, &req.tags).await?;
+    if !missing.is_empty() {
+        let ids: Vec<String> = missing.iter().map(Uuid::to_string).collect();
+        errors.push(FieldError::new("tags", format!("Unknown tag ids: {}", ids.join(", "))));
+    }
 
-    Ok((StatusCode::CREATED, Json(post)))
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
+
+/// Fill a blank title or excerpt from the body's front-matter. When the request has no
+/// tags, returns the valid front-matter tag names for the caller to resolve to ids.
+fn apply_frontmatter(req: &mut CreatePostRequest) -> Vec<String> {
+    let (Some(frontmatter), _) = parse_frontmatter(&req.body) else {
+        return Vec::new();
+    };
+
+    if req.title.trim().is_empty() {
+        if let Some(title) = frontmatter.title {
+            req.title = title;
+        }
+    }
+    if req.excerpt.trim().is_empty() {
+        if let Some(excerpt) = frontmatter.excerpt {
+            req.excerpt = excerpt;
+        }
+    }
+    if !req.tags.is_empty() {
+        return Vec::new();
+    }
+
+    let mut names: Vec<String> = frontmatter
+        .tags
+        .iter()
+        .map(|name| normalize_tag_name(name))
+        .filter(|name| is_valid_tag_name(name))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The checks `create_post` makes that don't need the database, shared with `validate_post`.
+/// Scheduled posts go live unattended, so they must be fit to publish now.
+fn create_field_errors(req: &CreatePostRequest, strict: bool, require_cover: bool) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if req.slug.trim().is_empty() {
+        let base = slugify(&req.title);
+        if base.is_empty() || !is_valid_slug(&base) {
+            errors.push(FieldError::new(
+                "slug",
+                "Could not derive a slug from the title; please provide one.",
+            ));
+        }
+    } else if !is_valid_slug(req.slug.trim()) {
+        errors.push(FieldError::new(
+            "slug",
+            "Invalid slug format. Use lowercase letters, numbers, and hyphens only.",
+        ));
+    }
+
+    if strict {
+        errors.extend(validate_frontmatter_strict(&req.body));
+    }
+    if req.published || req.publish_at.is_some() {
+        if let Some(reason) = publish_blocker(&req.title, &req.body) {
+            errors.push(FieldError::new("published", reason));
+        }
+    }
+    if req.published {
+        errors.extend(cover_image_errors(&req.body, require_cover));
+    }
+
+    errors
 }
 
 /// Update an existing blog post
@@ -60,12 +285,56 @@ pub async fn update_post(
     Path(slug): Path<String>,
     Json(req): Json<UpdatePostRequest>,
 ) -> Result<Json<Post>, AppError> {
-    // Check if post exists and user owns it (including unpublished posts)
+    // Check if post exists (including unpublished posts)
     let existing = db::get_post_by_slug_any(&state.pool, &slug)
         .await?
         .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
 
-    if existing.author_id != user.user_id {
+    update_existing_post(&state, &user, existing, req).await
+}
+
+/// Update a post addressed by id, so an open editor survives a concurrent slug rename
+pub async fn update_post_by_id(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdatePostRequest>,
+) -> Result<Json<Post>, AppError> {
+    let existing = post_by_id(&state, &id).await?;
+    update_existing_post(&state, &user, existing, req).await
+}
+
+/// Look up any post (draft, scheduled or trashed) by its id path segment
+async fn post_by_id(state: &AppState, id: &str) -> Result<Post, AppError> {
+    let id = Uuid::parse_str(id).map_err(|_| AppError::BadRequest("Invalid post ID".to_string()))?;
+    db::get_post_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))
+}
+
+/// Get any post by id, including drafts, for the admin editor
+pub async fn get_post_by_id(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Post>, AppError> {
+    let post = post_by_id(&state, &id).await?;
+    if !user.can_manage(post.author_id) {
+        return Err(AppError::Forbidden(
+            "You don't have permission to view this post".to_string(),
+        ));
+    }
+    Ok(Json(post))
+}
+
+/// Shared by the slug and id update routes once the post is found
+async fn update_existing_post(
+    state: &AppState,
+    user: &AuthUser,
+    existing: Post,
+    mut req: UpdatePostRequest,
+) -> Result<Json<Post>, AppError> {
+    if !user.can_manage(existing.author_id) {
         return Err(AppError::Forbidden(
             "You don't have permission to update this post".to_string(),
         ));
@@ -82,8 +351,8 @@ pub async fn update_post(
 
         // Check if new slug is already taken by another post
         if new_slug != &existing.slug {
-            if let Some(_) = db::get_post_by_slug(&state.pool, new_slug).await? {
-                return Err(AppError::BadRequest(format!(
+            if let Some(_) = db::get_post_by_slug_any(&state.pool, new_slug).await? {
+                return Err(AppError::Conflict(format!(
                     "A post with slug '{}' already exists",
                     new_slug
                 )));
@@ -91,27 +360,88 @@ pub async fn update_post(
         }
     }
 
+    if let Some(ref tags) = req.tags {
+        ensure_tags_exist(state, tags).await?;
+    }
+
+    // A save from the editor can promote the autosaved edits it doesn't override.
+    // Other updates (tags, flags) leave the slot alone, stale or not.
+    let autosaved = if req.promote_autosave {
+        db::get_draft(&state.pool, existing.id).await?
+    } else {
+        None
+    };
+    let had_autosave = autosaved.is_some();
+    if let Some(draft) = autosaved {
+        promote_autosave(&mut req, draft);
+    }
+
+    // Live and scheduled posts must stay fit to publish
+    if existing.published || existing.publish_at.is_some() || req.publish_at.is_some() {
+        let title = req.title.as_deref().unwrap_or(&existing.title);
+        let body = req.body.as_deref().unwrap_or(&existing.body);
+        if let Some(reason) = publish_blocker(title, body) {
+            return Err(AppError::BadRequest(reason));
+        }
+    }
+
+    if let Some(excerpt) = excerpt_for_update(&req, &existing, state.excerpt_min_body_chars) {
+        req.excerpt = Some(excerpt);
+    }
+
     // Update the post
     let updated_post = db::update_post(&state.pool, existing.id, req).await?;
+    if had_autosave {
+        db::delete_draft(&state.pool, existing.id).await?;
+    }
 
     tracing::info!(
         "Post updated: {} by user {}",
         updated_post.slug,
         user.username
     );
+    state.notify(WebhookEvent::Updated, &updated_post);
+    // Moving publish_at into the past puts a published post live right away
+    if !existing.is_live() && updated_post.is_live() {
+        state.notify(WebhookEvent::Published, &updated_post);
+    }
 
     Ok(Json(updated_post))
 }
 
-/// Delete a blog post
+/// The excerpt an update should store when the resulting excerpt would be blank:
+/// generated from the resulting body, or empty when the body is too short.
+/// None leaves the excerpt as the request has it.
+fn excerpt_for_update(req: &UpdatePostRequest, existing: &Post, min_body_chars: usize) -> Option<String> {
+    let excerpt = req.excerpt.as_deref().unwrap_or(&existing.excerpt);
+    if !excerpt.trim().is_empty() {
+        return None;
+    }
+
+    let body = req.body.as_deref().unwrap_or(&existing.body);
+    let generated = normalize_excerpt(excerpt, body, min_body_chars);
+    // Nothing to write if the stored excerpt stays empty
+    (req.excerpt.is_some() || !generated.is_empty()).then_some(generated)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteParams {
+    #[serde(default)]
+    pub purge: bool,
+}
+
+/// Delete a blog post.
+/// - Moves the post to the trash by default so it can be restored
+/// - `?purge=true` removes it permanently
 pub async fn delete_post(
     State(state): State<Arc<AppState>>,
     user: AuthUser,
     Path(slug): Path<String>,
+    Query(params): Query<DeleteParams>,
 ) -> Result<StatusCode, AppError> {
     tracing::info!("Delete request for slug: {} by user {}", slug, user.username);
     
-    // Check if post exists and user owns it (including unpublished posts)
+    // Check if post exists (including unpublished posts)
     let existing = db::get_post_by_slug_any(&state.pool, &slug)
         .await?
         .ok_or_else(|| {
@@ -119,24 +449,171 @@ pub async fn delete_post(
             AppError::NotFound("Post not found".to_string())
         })?;
 
+    delete_existing_post(&state, &user, existing, params.purge).await
+}
+
+/// Delete a post addressed by id; same trash/purge behaviour as the slug route
+pub async fn delete_post_by_id(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Query(params): Query<DeleteParams>,
+) -> Result<StatusCode, AppError> {
+    let existing = post_by_id(&state, &id).await?;
+    delete_existing_post(&state, &user, existing, params.purge).await
+}
+
+async fn delete_existing_post(
+    state: &AppState,
+    user: &AuthUser,
+    existing: Post,
+    purge: bool,
+) -> Result<StatusCode, AppError> {
     tracing::info!("Found post: {} with author_id: {}, user_id: {}", 
         existing.slug, existing.author_id, user.user_id);
 
-    if existing.author_id != user.user_id {
+    if !user.can_manage(existing.author_id) {
         return Err(AppError::Forbidden(
             "You don't have permission to delete this post".to_string(),
         ));
     }
 
-    // Delete the post
-    db::delete_post(&state.pool, existing.id).await?;
-
-    tracing::info!("Post deleted: {} by user {}", existing.slug, user.username);
+    state.render_cache.invalidate(existing.id);
+    if purge {
+        db::purge_post(&state.pool, existing.id).await?;
+        tracing::info!("Post purged: {} by user {}", existing.slug, user.username);
+    } else {
+        db::delete_post(&state.pool, existing.id).await?;
+        tracing::info!("Post moved to trash: {} by user {}", existing.slug, user.username);
+    }
+    state.notify(WebhookEvent::Deleted, &existing);
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Publish a draft post
+/// Restore a post from the trash
+pub async fn restore_post(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(slug): Path<String>,
+) -> Result<Json<Post>, AppError> {
+    let existing = db::get_post_by_slug_any(&state.pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if !user.can_manage(existing.author_id) {
+        return Err(AppError::Forbidden(
+            "You don't have permission to restore this post".to_string(),
+        ));
+    }
+
+    if existing.deleted_at.is_none() {
+        return Err(AppError::BadRequest("Post is not in the trash".to_string()));
+    }
+
+    let post = db::restore_post(&state.pool, existing.id).await?;
+
+    tracing::info!("Post restored: {} by user {}", post.slug, user.username);
+
+    Ok(Json(post))
+}
+
+/// Autosave edits to a post without touching the live version
+pub async fn save_draft(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(slug): Path<String>,
+    Json(req): Json<SaveDraftRequest>,
+) -> Result<Json<Draft>, AppError> {
+    let existing = db::get_post_by_slug_any(&state.pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if !user.can_manage(existing.author_id) {
+        return Err(AppError::Forbidden(
+            "You don't have permission to edit this post".to_string(),
+        ));
+    }
+
+    let draft = db::upsert_draft(&state.pool, existing.id, user.user_id, &req).await?;
+
+    Ok(Json(draft))
+}
+
+/// Editor autosave: writes title/body into the post's draft slot.
+/// The live post is untouched (no `updated_at`/`published_at` change), and repeated
+/// saves overwrite the one slot; a save of the post with `promote_autosave` promotes it.
+pub async fn autosave_post(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(slug): Path<String>,
+    Json(req): Json<AutosaveRequest>,
+) -> Result<Json<Draft>, AppError> {
+    let existing = db::get_post_by_slug_any(&state.pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if !user.can_manage(existing.author_id) {
+        return Err(AppError::Forbidden(
+            "You don't have permission to edit this post".to_string(),
+        ));
+    }
+
+    let slot = db::get_draft(&state.pool, existing.id).await?;
+    let merged = merge_autosave(slot.as_ref(), &existing, req);
+    let draft = db::upsert_draft(&state.pool, existing.id, user.user_id, &merged).await?;
+
+    Ok(Json(draft))
+}
+
+/// Apply an autosave on top of the current slot, or the post when there is none
+fn merge_autosave(slot: Option<&Draft>, post: &Post, req: AutosaveRequest) -> SaveDraftRequest {
+    let (title, excerpt, body) = match slot {
+        Some(d) => (&d.title, &d.excerpt, &d.body),
+        None => (&post.title, &post.excerpt, &post.body),
+    };
+    SaveDraftRequest {
+        title: req.title.unwrap_or_else(|| title.clone()),
+        excerpt: excerpt.clone(),
+        body: req.body.unwrap_or_else(|| body.clone()),
+    }
+}
+
+/// Fill fields an explicit save leaves out from the autosaved draft
+fn promote_autosave(req: &mut UpdatePostRequest, draft: Draft) {
+    req.title.get_or_insert(draft.title);
+    req.excerpt.get_or_insert(draft.excerpt);
+    req.body.get_or_insert(draft.body);
+}
+
+/// Get the last autosaved draft of a post
+pub async fn get_draft(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(slug): Path<String>,
+) -> Result<Json<Draft>, AppError> {
+    let existing = db::get_post_by_slug_any(&state.pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if !user.can_manage(existing.author_id) {
+        return Err(AppError::Forbidden(
+            "You don't have permission to view this draft".to_string(),
+        ));
+    }
+
+    let draft = db::get_draft(&state.pool, existing.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No draft saved for this post".to_string()))?;
+
+    Ok(Json(draft))
+}
+
+/// Fewest characters of body text (front-matter excluded) a post needs to be published
+const MIN_PUBLISH_BODY_CHARS: usize = 20;
+
+/// Publish a post, merging its staged draft first.
+/// A post that is already live can still take its staged draft this way.
 pub async fn publish_post(
     State(state): State<Arc<AppState>>,
     user: AuthUser,
@@ -147,26 +624,65 @@ pub async fn publish_post(
         .await?
         .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
 
-    if existing.author_id != user.user_id {
+    if !user.can_manage(existing.author_id) {
         return Err(AppError::Forbidden(
             "You don't have permission to publish this post".to_string(),
         ));
     }
 
-    if existing.published {
+    let draft = db::get_draft(&state.pool, existing.id).await?;
+    if existing.published && draft.is_none() {
         return Err(AppError::BadRequest(
             "Post is already published".to_string(),
         ));
     }
 
-    // Publish the post
-    let published_post = db::publish_post(&state.pool, existing.id).await?;
+    // Validate what will actually go live: the staged draft if there is one
+    let body = draft.as_ref().map_or(&existing.body, |d| &d.body);
+    let title = draft.as_ref().map_or(&existing.title, |d| &d.title);
+
+    if let Some(reason) = publish_blocker(title, body) {
+        return Err(AppError::BadRequest(reason));
+    }
+
+    if state.strict_frontmatter {
+        let errors = validate_frontmatter_strict(body);
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+    }
+
+    let errors = cover_image_errors(body, state.require_cover_image);
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    // The draft's fields, with a blank excerpt generated as on any other update
+    let merge = draft.map(|draft| {
+        let mut merge = UpdatePostRequest {
+            title: Some(draft.title),
+            excerpt: Some(draft.excerpt),
+            body: Some(draft.body),
+            ..Default::default()
+        };
+        if let Some(excerpt) = excerpt_for_update(&merge, &existing, state.excerpt_min_body_chars) {
+            merge.excerpt = Some(excerpt);
+        }
+        merge
+    });
+
+    let published_post = db::publish_post(&state.pool, existing.id, merge).await?;
 
     tracing::info!(
         "Post published: {} by user {}",
         published_post.slug,
         user.username
     );
+    if existing.published {
+        state.notify(WebhookEvent::Updated, &published_post);
+    } else {
+        state.notify(WebhookEvent::Published, &published_post);
+    }
 
     Ok(Json(published_post))
 }
@@ -182,7 +698,7 @@ pub async fn unpublish_post(
         .await?
         .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
 
-    if existing.author_id != user.user_id {
+    if !user.can_manage(existing.author_id) {
         return Err(AppError::Forbidden(
             "You don't have permission to unpublish this post".to_string(),
         ));
@@ -206,44 +722,232 @@ pub async fn unpublish_post(
     Ok(Json(unpublished_post))
 }
 
+/// Preview a post's previous/next neighbours as if it were published.
+/// Drafts are placed at their scheduled `publish_at`, or now if unscheduled.
+pub async fn nav_preview(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(slug): Path<String>,
+) -> Result<Json<NavPreview>, AppError> {
+    let existing = db::get_post_by_slug_any(&state.pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    if !user.can_manage(existing.author_id) {
+        return Err(AppError::Forbidden(
+            "You don't have permission to view this post".to_string(),
+        ));
+    }
+
+    let projected = as_if_published(existing, Utc::now());
+    let (previous, next) = db::get_adjacent_posts(&state.pool, &projected).await?;
+
+    Ok(Json(NavPreview {
+        slug: projected.slug,
+        published_at: projected.published_at.unwrap_or(projected.created_at),
+        previous,
+        next,
+    }))
+}
+
+/// The post with the `published_at` it will have once live
+fn as_if_published(mut post: Post, now: DateTime<Utc>) -> Post {
+    post.published_at = post.published_at.or(post.publish_at).or(Some(now));
+    post
+}
+
 /// Preview markdown content
 pub async fn preview_markdown(
-    _user: AuthUser,
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
     Json(req): Json<MarkdownPreviewRequest>,
 ) -> Result<Json<MarkdownPreviewResponse>, AppError> {
-    // Render the markdown with Obsidian features
-    let html = render_obsidian_markdown(&req.markdown);
+    check_markdown_size(&req.markdown)?;
 
     // Calculate reading time
     let reading_time = calculate_reading_time(&req.markdown);
 
-    Ok(Json(MarkdownPreviewResponse { html, reading_time }))
+    // Render the markdown with Obsidian features, as the requesting author would get it,
+    // on the blocking pool so large previews don't stall other requests
+    let opts = RenderOptions::for_author(user.role, req.trusted_html)
+        .with_url_schemes(&state.url_schemes)
+        .with_max_callout_depth(state.max_callout_depth);
+    let markdown = req.markdown;
+    let frontmatter = parse_frontmatter(&markdown).0.unwrap_or_default();
+    let tags = preview_tags(&markdown, &frontmatter);
+    let links = preview_links(&markdown);
+    let excerpt = normalize_excerpt(
+        frontmatter.excerpt.as_deref().unwrap_or(""),
+        &markdown,
+        state.excerpt_min_body_chars,
+    );
+    let outline = outline_tree(build_toc(&markdown, 6));
+
+    let slugs: Vec<String> = links.iter().map(|l| link_slug(l)).collect();
+    let existing: HashSet<String> = db::find_existing_slugs(&state.pool, &slugs)
+        .await?
+        .into_iter()
+        .collect();
+    let unresolved_links = links
+        .iter()
+        .filter(|l| !existing.contains(&link_slug(l)))
+        .cloned()
+        .collect();
+
+    let html = render_with_deadline(markdown, opts, state.render_timeout).await?;
+
+    Ok(Json(MarkdownPreviewResponse {
+        html,
+        reading_time,
+        title: frontmatter.title,
+        tags,
+        links,
+        unresolved_links,
+        excerpt,
+        outline,
+    }))
+}
+
+/// Distinct wiki-link and page-embed targets, found the way `lint_markdown` finds them
+fn preview_links(markdown: &str) -> Vec<String> {
+    let mut links: Vec<String> = link_refs(markdown)
+        .into_iter()
+        .filter(|r| !r.is_image())
+        .map(|r| r.target)
+        .collect();
+    links.sort();
+    links.dedup();
+    links
+}
+
+/// Body tags plus valid front-matter tags, sorted and without duplicates
+fn preview_tags(markdown: &str, frontmatter: &Frontmatter) -> Vec<String> {
+    let mut tags = extract_tags(markdown);
+    tags.extend(
+        frontmatter
+            .tags
+            .iter()
+            .map(|name| normalize_tag_name(name))
+            .filter(|name| is_valid_tag_name(name)),
+    );
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Heading outline and structural counts for the editor, without a full render
+pub async fn outline_markdown(
+    _user: AuthUser,
+    Json(req): Json<OutlineRequest>,
+) -> Result<Json<OutlineResponse>, AppError> {
+    check_markdown_size(&req.markdown)?;
+
+    let headings = build_toc(&req.markdown, req.max_depth.unwrap_or(6));
+    Ok(Json(OutlineResponse {
+        outline: outline_tree(headings),
+        counts: document_counts(&req.markdown),
+    }))
+}
+
+/// Check markdown for problems before publishing: unresolved wiki-links, missing
+/// image assets, unknown callout types and skipped heading levels
+pub async fn lint_markdown(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+    Json(req): Json<LintRequest>,
+) -> Result<Json<Vec<LintWarning>>, AppError> {
+    check_markdown_size(&req.markdown)?;
+
+    let mut warnings = lint_structure(&req.markdown);
+    let refs = link_refs(&req.markdown);
+
+    let slugs: Vec<String> = refs
+        .iter()
+        .filter(|r| !r.is_image())
+        .map(|r| link_slug(&r.target))
+        .collect();
+    let existing: HashSet<String> = db::find_existing_slugs(&state.pool, &slugs)
+        .await?
+        .into_iter()
+        .collect();
+    let assets: HashSet<String> = if refs.iter().any(|r| r.is_image()) {
+        db::list_asset_filenames(&state.pool)
+            .await?
+            .iter()
+            .map(|name| slugify(name))
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    for r in refs {
+        if r.is_image() {
+            if !assets.contains(&slugify(&r.target)) {
+                warnings.push(LintWarning {
+                    line: r.line,
+                    kind: LintKind::MissingImage,
+                    message: format!("No uploaded asset named '{}'", r.target),
+                });
+            }
+        } else if !existing.contains(&link_slug(&r.target)) {
+            warnings.push(LintWarning {
+                line: r.line,
+                kind: LintKind::UnresolvedLink,
+                message: format!("No post matches [[{}]]", r.target),
+            });
+        }
+    }
+
+    warnings.sort_by_key(|w| w.line);
+    Ok(Json(warnings))
+}
+
+/// Report per-phase render timings for a stored post (diagnostics)
+pub async fn render_timing(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+    Path(slug): Path<String>,
+) -> Result<Json<RenderTimingResponse>, AppError> {
+    let post = db::get_post_by_slug_any(&state.pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Post not found".to_string()))?;
+
+    // Render exactly what the public post endpoint renders
+    let body = strip_title_heading(&post.body, &post.title);
+    let author_role = db::get_author_role(&state.pool, post.author_id).await?;
+    let opts = RenderOptions::for_author(author_role, post.trusted_html)
+        .with_url_schemes(&state.url_schemes)
+        .with_max_callout_depth(state.max_callout_depth);
+    let (_, timings) = render_with_timings(&body, &opts);
+
+    let phases: Vec<PhaseTiming> = timings
+        .into_iter()
+        .map(|(phase, duration)| PhaseTiming {
+            phase: phase.to_string(),
+            duration_ms: duration.as_secs_f64() * 1000.0,
+        })
+        .collect();
+    let total_ms = phases.iter().map(|p| p.duration_ms).sum();
+
+    Ok(Json(RenderTimingResponse {
+        slug: post.slug,
+        total_ms,
+        phases,
+    }))
 }
 
 /// Create a new tag
 pub async fn create_tag(
     State(state): State<Arc<AppState>>,
     user: AuthUser,
-    Json(req): Json<CreateTagRequest>,
-) -> Result<(StatusCode, Json<Tag>), AppError> {
-    // Validate tag name
-    if !is_valid_tag_name(&req.name) {
-        return Err(AppError::BadRequest(
-            "Invalid tag name. Use lowercase letters, numbers, and hyphens only.".to_string(),
-        ));
-    }
-
-    // Validate color is a valid Catppuccin color
-    if !is_valid_catppuccin_color(&req.color) {
-        return Err(AppError::BadRequest(
-            "Invalid color. Must be a valid Catppuccin color name.".to_string(),
-        ));
-    }
+    Json(mut req): Json<CreateTagRequest>,
+) -> Result<Created<Tag>, AppError> {
+    req.name = normalize_tag_name(&req.name);
+    validate_tag_request(&req)?;
 
     // Check if tag already exists
-    let existing_tags = db::list_tags(&state.pool).await?;
-    if existing_tags.iter().any(|t| t.name == req.name) {
-        return Err(AppError::BadRequest(format!(
+    if db::get_tag_by_name(&state.pool, &req.name).await?.is_some() {
+        return Err(AppError::Conflict(format!(
             "Tag '{}' already exists",
             req.name
         )));
@@ -254,7 +958,7 @@ pub async fn create_tag(
 
     tracing::info!("Tag created: {} by user {}", tag.name, user.username);
 
-    Ok((StatusCode::CREATED, Json(tag)))
+    Ok(created(format!("/api/tags/{}", tag.id), tag))
 }
 
 /// Update an existing tag
@@ -262,24 +966,24 @@ pub async fn update_tag(
     State(state): State<Arc<AppState>>,
     user: AuthUser,
     Path(id): Path<Uuid>,
-    Json(req): Json<CreateTagRequest>,
+    Json(mut req): Json<CreateTagRequest>,
 ) -> Result<Json<Tag>, AppError> {
-    // Validate tag name
-    if !is_valid_tag_name(&req.name) {
-        return Err(AppError::BadRequest(
-            "Invalid tag name. Use lowercase letters, numbers, and hyphens only.".to_string(),
-        ));
-    }
+    req.name = normalize_tag_name(&req.name);
+    validate_tag_request(&req)?;
 
-    // Validate color is a valid Catppuccin color
-    if !is_valid_catppuccin_color(&req.color) {
-        return Err(AppError::BadRequest(
-            "Invalid color. Must be a valid Catppuccin color name.".to_string(),
-        ));
+    // Renaming onto another tag's name would merge them silently
+    if let Some(existing) = db::get_tag_by_name(&state.pool, &req.name).await? {
+        if existing.id != id {
+            return Err(AppError::Conflict(format!(
+                "Tag '{}' already exists",
+                req.name
+            )));
+        }
     }
 
     // Update the tag
     let tag = db::update_tag(&state.pool, id, req).await?;
+    state.render_cache.clear();
 
     tracing::info!("Tag updated: {} by user {}", tag.name, user.username);
 
@@ -294,6 +998,7 @@ pub async fn delete_tag(
 ) -> Result<StatusCode, AppError> {
     // Delete the tag (will cascade to remove from post_tags)
     db::delete_tag(&state.pool, id).await?;
+    state.render_cache.clear();
 
     tracing::info!("Tag deleted: {} by user {}", id, user.username);
 
@@ -306,26 +1011,88 @@ pub async fn list_all_posts(
     _user: AuthUser,
 ) -> Result<Json<Vec<AdminPostSummary>>, AppError> {
     let posts = db::list_all_posts(&state.pool).await?;
-    let summaries: Vec<AdminPostSummary> = posts
-        .into_iter()
-        .map(|p| AdminPostSummary {
-            id: p.id.to_string(),
-            slug: p.slug,
-            title: p.title,
-            excerpt: p.excerpt,
-            published_at: p.published_at.unwrap_or(p.created_at).to_rfc3339(),
-            reading_time: calculate_reading_time(&p.body),
-            tags: p.tags,
-        })
-        .collect();
+    let summaries: Vec<AdminPostSummary> = posts.into_iter().map(admin_summary).collect();
     Ok(Json(summaries))
 }
 
+/// Map a post to its admin table row, including size and tagging metrics
+fn admin_summary(p: Post) -> AdminPostSummary {
+    AdminPostSummary {
+        id: p.id.to_string(),
+        slug: p.slug,
+        title: p.title,
+        excerpt: p.excerpt,
+        published_at: p.published_at.unwrap_or(p.created_at).to_rfc3339(),
+        reading_time: calculate_reading_time(&p.body),
+        word_count: count_words(&p.body),
+        tag_count: p.tags.len(),
+        tags: p.tags,
+        published: p.published,
+        publish_at: p.publish_at.map(|at| at.to_rfc3339()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SeedParams {
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Seed demo content (admin only).
+/// - Only runs against an empty posts table unless `?force=true`
+pub async fn seed_demo_content(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(params): Query<SeedParams>,
+) -> Result<(StatusCode, Json<SeedReport>), AppError> {
+    user.require_admin()?;
+
+    let report = db::seed_sample_posts(&state.pool, user.user_id, params.force).await?;
+    if report.skipped {
+        return Err(AppError::Conflict(
+            "Posts already exist; pass force=true to seed anyway".to_string(),
+        ));
+    }
+
+    tracing::info!(
+        "Demo content seeded by {}: posts {:?}, tags {:?}",
+        user.username,
+        report.posts,
+        report.tags
+    );
+
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+/// Remove seeded demo content, leaving real posts and tags intact (admin only)
+pub async fn clear_demo_content(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<SeedReport>, AppError> {
+    user.require_admin()?;
+
+    let report = db::delete_seeded_content(&state.pool).await?;
+
+    tracing::info!(
+        "Demo content removed by {}: posts {:?}, tags {:?}",
+        user.username,
+        report.posts,
+        report.tags
+    );
+
+    Ok(Json(report))
+}
+
 /// Get post statistics for admin dashboard
 pub async fn get_post_stats(State(state): State<Arc<AppState>>, _user: AuthUser) -> impl IntoResponse {
-    let stats = match db::get_post_stats(&state.pool).await {
-        Ok(stats) => stats,
-        Err(_) => {
+    let counts = db::get_post_stats(&state.pool).await;
+    let posts = db::list_all_posts(&state.pool).await;
+    let stats = match (counts, posts) {
+        (Ok(mut stats), Ok(posts)) => {
+            add_content_stats(&mut stats, &posts);
+            stats
+        }
+        _ => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({
@@ -338,19 +1105,300 @@ pub async fn get_post_stats(State(state): State<Arc<AppState>>, _user: AuthUser)
     (StatusCode::OK, Json(stats))
 }
 
-/// Search posts with full-text search
-pub async fn search_posts(
+/// Word and reading-time metrics over live posts, added beside the existing counts.
+/// Words are counted the way `calculate_reading_time` counts them.
+fn add_content_stats(stats: &mut serde_json::Value, posts: &[Post]) {
+    let live: Vec<(&Post, usize)> = posts
+        .iter()
+        .filter(|p| p.is_live())
+        .map(|p| (p, count_words(&p.body)))
+        .collect();
+
+    let total_words: usize = live.iter().map(|(_, words)| words).sum();
+    let total_minutes: u32 = live
+        .iter()
+        .map(|(p, _)| reading_minutes(&p.body, DEFAULT_WORDS_PER_MINUTE))
+        .sum();
+    let average = |total: f64| {
+        if live.is_empty() {
+            0.0
+        } else {
+            (total / live.len() as f64 * 10.0).round() / 10.0
+        }
+    };
+    let longest = live.iter().max_by_key(|(_, words)| *words).map(|(p, _)| &p.slug);
+    let shortest = live.iter().min_by_key(|(_, words)| *words).map(|(p, _)| &p.slug);
+
+    if let Some(stats) = stats.as_object_mut() {
+        stats.insert("total_words".to_string(), json!(total_words));
+        stats.insert("average_words".to_string(), json!(average(total_words as f64)));
+        stats.insert(
+            "average_reading_minutes".to_string(),
+            json!(average(f64::from(total_minutes))),
+        );
+        stats.insert("longest_post".to_string(), json!(longest));
+        stats.insert("shortest_post".to_string(), json!(shortest));
+    }
+}
+
+/// Export every post (with body and tags) and every tag as one JSON document (admin only)
+pub async fn export_blog(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<BlogExport>, AppError> {
+    user.require_admin()?;
+
+    let posts = db::list_all_posts(&state.pool).await?;
+    let tags = db::list_tags(&state.pool).await?;
+
+    Ok(Json(BlogExport {
+        schema_version: EXPORT_SCHEMA_VERSION,
+        exported_at: Utc::now(),
+        tags,
+        posts: posts.into_iter().map(ExportedPost::from).collect(),
+    }))
+}
+
+/// Recreate posts and tags from an export, skipping slugs that already exist (admin only)
+pub async fn import_blog(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(export): Json<BlogExport>,
+) -> Result<Json<ImportReport>, AppError> {
+    user.require_admin()?;
+
+    let errors = import_errors(&export);
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+
+    let report = db::import_blog(&state.pool, &export, user.user_id).await?;
+
+    tracing::info!(
+        "Import by {}: {} posts, {} tags created, {} skipped",
+        user.username,
+        report.posts.len(),
+        report.tags.len(),
+        report.skipped.len()
+    );
+
+    Ok(Json(report))
+}
+
+/// Problems that would leave an import half-applied, found before touching the database
+fn import_errors(export: &BlogExport) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if export.schema_version != EXPORT_SCHEMA_VERSION {
+        errors.push(FieldError::new(
+            "schema_version",
+            format!(
+                "Unsupported export version {} (expected {})",
+                export.schema_version, EXPORT_SCHEMA_VERSION
+            ),
+        ));
+    }
+
+    // Imported tags go through the same normalization and checks as any other tag write
+    for (i, tag) in export.tags.iter().enumerate() {
+        let req = CreateTagRequest {
+            name: normalize_tag_name(&tag.name),
+            color: tag.color.clone(),
+        };
+        if let Err(e) = validate_tag_request(&req) {
+            let message = match e {
+                AppError::BadRequest(message) => message,
+                other => other.to_string(),
+            };
+            errors.push(FieldError::new(format!("tags[{}]", i), message));
+        }
+    }
+
+    let tag_names: HashSet<String> =
+        export.tags.iter().map(|t| normalize_tag_name(&t.name)).collect();
+    for (i, post) in export.posts.iter().enumerate() {
+        if !is_valid_slug(&post.slug) {
+            errors.push(FieldError::new(
+                format!("posts[{}].slug", i),
+                format!("Invalid slug '{}'", post.slug),
+            ));
+        }
+        let unknown = post.tags.iter().filter(|name| !tag_names.contains(&normalize_tag_name(name)));
+        for name in unknown {
+            errors.push(FieldError::new(
+                format!("posts[{}].tags", i),
+                format!("Tag '{}' is not in the export", name),
+            ));
+        }
+    }
+
+    errors
+}
+
+#[derive(Deserialize)]
+pub struct TimelineParams {
+    pub period: Option<String>,
+}
+
+/// Published-post counts per day, week or month over the last year
+pub async fn get_post_timeline(
     State(state): State<Arc<AppState>>,
     _user: AuthUser,
-    query: String,
-) -> Result<Json<Vec<Post>>, AppError> {
-    let posts = db::search_posts(&state.pool, &query).await?;
-    Ok(Json(posts))
+    Query(params): Query<TimelineParams>,
+) -> Result<Json<Vec<TimelineBucket>>, AppError> {
+    let period = StatsPeriod::parse(params.period.as_deref()).ok_or_else(|| {
+        AppError::BadRequest("period must be one of: day, week, month".to_string())
+    })?;
+    let buckets = db::get_post_timeline(&state.pool, period).await?;
+    Ok(Json(buckets))
+}
+
+/// Posts re-derived per transaction by `reindex`
+const REINDEX_BATCH_SIZE: i64 = 100;
+
+/// Time allowed for a whole reindex; it walks every post, so it outgrows the usual limit
+pub const REINDEX_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Rebuild the stored wiki-links of every post and drop cached renders after the
+/// markdown pipeline changes (admin only)
+pub async fn reindex(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<ReindexReport>, AppError> {
+    user.require_admin()?;
+
+    let report = db::reindex_posts(&state.pool, REINDEX_BATCH_SIZE).await?;
+    // Renders are cached per post version, which a pipeline change doesn't bump
+    state.render_cache.clear();
+
+    tracing::info!(
+        "Reindex by {}: {} posts in {} batches, {} updated",
+        user.username,
+        report.posts,
+        report.batches,
+        report.posts_updated
+    );
+    Ok(Json(report))
+}
+
+/// Wiki-link targets with no post yet, grouped by target with the posts linking there.
+/// Most-referenced targets come first: they're the pages most worth writing.
+pub async fn list_unresolved_links(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+) -> Result<Json<Vec<UnresolvedLink>>, AppError> {
+    let links = db::list_unresolved_links(&state.pool).await?;
+    Ok(Json(group_unresolved_links(links)))
+}
+
+/// Group `(target, post)` pairs by target; a post appears once per target however often it links
+fn group_unresolved_links(links: Vec<(String, LinkingPost)>) -> Vec<UnresolvedLink> {
+    let mut by_target: BTreeMap<String, UnresolvedLink> = BTreeMap::new();
+    for (target, post) in links {
+        let group = by_target
+            .entry(target)
+            .or_insert_with_key(|target| UnresolvedLink {
+                target: target.clone(),
+                reference_count: 0,
+                posts: Vec::new(),
+            });
+        group.reference_count += 1;
+        if !group.posts.contains(&post) {
+            group.posts.push(post);
+        }
+    }
+
+    let mut grouped: Vec<UnresolvedLink> = by_target.into_values().collect();
+    grouped.sort_by(|a, b| {
+        b.reference_count
+            .cmp(&a.reference_count)
+            .then_with(|| a.target.cmp(&b.target))
+    });
+    grouped
+}
+
+/// Search posts with the public search query: every live post, plus the drafts and
+/// scheduled posts the user may manage
+pub async fn search_posts(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<AdminPostSummary>>, AppError> {
+    let q = params.q.as_deref().unwrap_or_default();
+    if q.trim().is_empty() {
+        return Ok(Json(vec![]));
+    }
+
+    let hits = db::search_posts_with_snippets(&state.pool, q, params.tag_filter()).await?;
+    let summaries = hits
+        .into_iter()
+        .map(|(p, _)| p)
+        .filter(|p| p.is_live() || user.can_manage(p.author_id))
+        .map(admin_summary)
+        .collect();
+    Ok(Json(summaries))
 }
 
 // Helper functions
 
-fn is_valid_slug(slug: &str) -> bool {
+/// Publishing guard: when `required`, the body must contain a cover image
+fn cover_image_errors(body: &str, required: bool) -> Vec<FieldError> {
+    if required && extract_cover_image(body).is_none() {
+        vec![FieldError::new("cover_image", "A cover image is required to publish")]
+    } else {
+        vec![]
+    }
+}
+
+/// Why a post isn't fit to go live, if it isn't. Drafts may be incomplete;
+/// publishing needs a title and some body beyond front-matter.
+pub(crate) fn publish_blocker(title: &str, body: &str) -> Option<String> {
+    if title.trim().is_empty() {
+        return Some("Cannot publish a post without a title".to_string());
+    }
+    if body.trim().is_empty() {
+        return Some("Cannot publish a post with an empty body".to_string());
+    }
+
+    let text = split_frontmatter(body).map_or(body, |(_, rest)| rest).trim();
+    if text.is_empty() {
+        return Some("Cannot publish a post whose body is only front-matter".to_string());
+    }
+    let chars = text.chars().count();
+    if chars < MIN_PUBLISH_BODY_CHARS {
+        return Some(format!(
+            "Cannot publish a post with only {} characters of body; at least {} are required",
+            chars, MIN_PUBLISH_BODY_CHARS
+        ));
+    }
+
+    None
+}
+
+/// Reject tag ids that don't exist, before they can fail the insert transaction
+async fn ensure_tags_exist(state: &AppState, tag_ids: &[Uuid]) -> Result<(), AppError> {
+    let missing = db::find_missing_tag_ids(&state.pool, tag_ids).await?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<String> = missing.iter().map(Uuid::to_string).collect();
+    Err(AppError::BadRequest(format!("Unknown tag ids: {}", ids.join(", "))))
+}
+
+/// First of `base`, `base-2`, `base-3`, ... not present in `taken`
+fn dedupe_slug(base: &str, taken: &[String]) -> String {
+    if !taken.iter().any(|s| s == base) {
+        return base.to_string();
+    }
+
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("unbounded range always yields a free slug")
+}
+
+pub(crate) fn is_valid_slug(slug: &str) -> bool {
     slug.chars()
         .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
         && !slug.starts_with('-')
@@ -358,41 +1406,528 @@ fn is_valid_slug(slug: &str) -> bool {
         && !slug.contains("--")
 }
 
-fn is_valid_tag_name(name: &str) -> bool {
-    name.len() <= 100
-        && name
-            .chars()
-            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
-}
-
-fn is_valid_catppuccin_color(color: &str) -> bool {
-    matches!(
-        color,
-        "rosewater"
-            | "flamingo"
-            | "pink"
-            | "mauve"
-            | "red"
-            | "maroon"
-            | "peach"
-            | "yellow"
-            | "green"
-            | "teal"
-            | "sky"
-            | "sapphire"
-            | "blue"
-            | "lavender"
-            | "surface0"
-            | "surface1"
-            | "surface2"
-            | "overlay0"
-            | "overlay1"
-            | "overlay2"
-            | "subtext0"
-            | "subtext1"
-            | "text"
-            | "base"
-            | "mantle"
-            | "crust"
-    )
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{post, Role};
+    use chrono::Duration;
+
+    #[test]
+    fn test_admin_summary_counts() {
+        let tag = |name: &str| Tag {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            color: "mauve".to_string(),
+            created_at: Utc::now(),
+        };
+        let post = Post {
+            tags: vec![tag("rust"), tag("axum")],
+            ..post("counts", "# Counts\n\nFive words in this body.")
+        };
+
+        let summary = admin_summary(post.clone());
+        assert_eq!(summary.word_count, 7);
+        assert_eq!(summary.tag_count, 2);
+        assert_eq!(summary.tags.len(), 2);
+        assert!(summary.published);
+
+        // Drafts show up in admin listings and search, flagged as unpublished
+        let draft = Post { published: false, published_at: None, ..post };
+        assert!(!admin_summary(draft).published);
+    }
+
+    #[sqlx::test]
+    async fn test_repeated_autosaves_share_one_slot(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let author = db::create_user(&pool, "writer", "not-a-real-hash", Role::Author).await?;
+        let post = db::create_post(
+            &pool,
+            CreatePostRequest {
+                slug: "wip".to_string(),
+                title: "Work in progress".to_string(),
+                excerpt: "Live excerpt".to_string(),
+                body: "Live body".to_string(),
+                tags: vec![],
+                published: true,
+                trusted_html: false,
+                noindex: false,
+                publish_at: None,
+            },
+            author.id,
+        )
+        .await?;
+
+        for keystroke in ["L", "Li", "Live", "Live edit"] {
+            let slot = db::get_draft(&pool, post.id).await?;
+            let merged = merge_autosave(
+                slot.as_ref(),
+                &post,
+                AutosaveRequest {
+                    body: Some(keystroke.to_string()),
+                    ..Default::default()
+                },
+            );
+            db::upsert_draft(&pool, post.id, author.id, &merged).await?;
+        }
+
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM post_drafts WHERE post_id = $1")
+            .bind(post.id)
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(rows, 1);
+        let draft = db::get_draft(&pool, post.id).await?.expect("autosave slot");
+        assert_eq!(draft.body, "Live edit");
+        assert_eq!(draft.title, "Work in progress");
+        assert_eq!(draft.excerpt, "Live excerpt");
+
+        // The live post is untouched until a save promotes the slot
+        let live = db::get_post_by_id(&pool, post.id).await?.expect("post");
+        assert_eq!(live.body, "Live body");
+        assert_eq!(live.updated_at, post.updated_at);
+
+        // A promoting save keeps its own fields and takes the rest from the slot
+        let mut req = UpdatePostRequest {
+            title: Some("Finished".to_string()),
+            promote_autosave: true,
+            ..Default::default()
+        };
+        promote_autosave(&mut req, draft);
+        assert_eq!(req.title.as_deref(), Some("Finished"));
+        assert_eq!(req.body.as_deref(), Some("Live edit"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_collects_every_field_error() {
+        let req = |slug: &str, title: &str, published: bool| CreatePostRequest {
+            slug: slug.to_string(),
+            title: title.to_string(),
+            excerpt: String::new(),
+            body: "No front-matter and no images".to_string(),
+            tags: vec![],
+            published,
+            trusted_html: false,
+            noindex: false,
+            publish_at: None,
+        };
+        let fields = |errors: Vec<FieldError>| -> Vec<String> {
+            errors.into_iter().map(|e| e.field).collect()
+        };
+
+        assert!(create_field_errors(&req("fine-slug", "Fine", true), false, false).is_empty());
+        assert!(create_field_errors(&req("", "Derived From Title", false), true, true)
+            .iter()
+            .all(|e| e.field != "slug"));
+        assert_eq!(fields(create_field_errors(&req("", "!!!", false), false, false)), vec!["slug"]);
+        // An untitled post may be saved as a draft but not published
+        assert!(create_field_errors(&req("untitled", "", false), false, false).is_empty());
+        let errors = fields(create_field_errors(&req("untitled", "", true), false, false));
+        assert_eq!(errors, vec!["published"]);
+
+        // Bad slug, missing front-matter and missing cover are all reported at once
+        let errors = fields(create_field_errors(&req("Bad Slug", "Title", true), true, true));
+        assert_eq!(errors.first().map(String::as_str), Some("slug"));
+        assert!(errors.contains(&"frontmatter".to_string()));
+        assert!(errors.contains(&"cover_image".to_string()));
+    }
+
+    #[test]
+    fn test_content_stats_extend_counts() {
+        let sized = |slug: &str, words: usize, published: bool| Post {
+            published,
+            ..post(slug, &"word ".repeat(words))
+        };
+        let posts = vec![
+            sized("short", 100, true),
+            sized("long", 500, true),
+            sized("draft", 9000, false),
+        ];
+
+        let mut stats = json!({ "published": 2, "drafts": 1 });
+        add_content_stats(&mut stats, &posts);
+        assert_eq!(stats["published"], 2);
+        assert_eq!(stats["total_words"], 600);
+        assert_eq!(stats["average_words"], 300.0);
+        // 1 min + 3 min, matching calculate_reading_time
+        assert_eq!(stats["average_reading_minutes"], 2.0);
+        assert_eq!(stats["longest_post"], "long");
+        assert_eq!(stats["shortest_post"], "short");
+
+        let mut empty = json!({});
+        add_content_stats(&mut empty, &[]);
+        assert_eq!(empty["average_words"], 0.0);
+        assert!(empty["longest_post"].is_null());
+    }
+
+    #[test]
+    fn test_export_round_trips_and_validates() {
+        let rust = Tag {
+            id: Uuid::new_v4(),
+            name: "rust".to_string(),
+            color: "peach".to_string(),
+            created_at: Utc::now(),
+        };
+        let post = Post {
+            excerpt: "Excerpt".to_string(),
+            published: false,
+            published_at: None,
+            noindex: true,
+            tags: vec![rust.clone()],
+            ..post("exported", "Body")
+        };
+        let export = BlogExport {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            exported_at: Utc::now(),
+            tags: vec![rust],
+            posts: vec![ExportedPost::from(post)],
+        };
+
+        let json = serde_json::to_value(&export).unwrap();
+        let parsed: BlogExport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.posts[0].tags, vec!["rust"]);
+        assert!(parsed.posts[0].noindex);
+        assert!(import_errors(&parsed).is_empty());
+
+        let mut broken = parsed;
+        broken.schema_version = EXPORT_SCHEMA_VERSION + 1;
+        broken.tags.clear();
+        broken.posts[0].slug = "Not A Slug".to_string();
+        let fields: Vec<String> = import_errors(&broken).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["schema_version", "posts[0].slug", "posts[0].tags"]);
+
+        // Tags are matched and validated in normalized form
+        let mut cased = broken.clone();
+        cased.schema_version = EXPORT_SCHEMA_VERSION;
+        cased.posts[0].slug = "exported".to_string();
+        cased.tags = vec![Tag {
+            id: Uuid::new_v4(),
+            name: " Rust ".to_string(),
+            color: "peach".to_string(),
+            created_at: Utc::now(),
+        }];
+        assert!(import_errors(&cased).is_empty());
+
+        cased.tags[0].name = "rust lang".to_string();
+        cased.tags[0].color = "#ff0000".to_string();
+        let fields: Vec<String> = import_errors(&cased).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["tags[0]", "posts[0].tags"]);
+    }
+
+    #[test]
+    fn test_preview_links_skip_images_and_code() {
+        let markdown = "See [[Beta]] and [[Alpha|the first]], again [[Beta]].\n\n\
+            ![[shot.png]] ![[Gamma#Setup]]\n\n```\n[[Not A Link]]\n```\n";
+        assert_eq!(preview_links(markdown), vec!["Alpha", "Beta", "Gamma#Setup"]);
+    }
+
+    #[test]
+    fn test_unresolved_links_grouped_by_target() {
+        let post = |slug: &str| LinkingPost {
+            slug: slug.to_string(),
+            title: slug.to_uppercase(),
+        };
+        let links = vec![
+            ("later".to_string(), post("a")),
+            ("someday".to_string(), post("a")),
+            ("someday".to_string(), post("a")),
+            ("someday".to_string(), post("b")),
+        ];
+
+        let grouped = group_unresolved_links(links);
+        let summary: Vec<(&str, usize, usize)> = grouped
+            .iter()
+            .map(|g| (g.target.as_str(), g.reference_count, g.posts.len()))
+            .collect();
+        assert_eq!(summary, vec![("someday", 3, 2), ("later", 1, 1)]);
+    }
+
+    #[test]
+    fn test_frontmatter_fills_blank_fields() {
+        let body = "---\ntitle: From Notes\ntags: [Rust, 'bad tag', rust]\n\
+                    excerpt: Short\n---\nThe body #wasm and #rust.";
+        let mut req = CreatePostRequest {
+            slug: String::new(),
+            title: String::new(),
+            excerpt: String::new(),
+            body: body.to_string(),
+            tags: vec![],
+            published: false,
+            trusted_html: false,
+            noindex: false,
+            publish_at: None,
+        };
+        assert_eq!(apply_frontmatter(&mut req), vec!["rust"]);
+        assert_eq!(req.title, "From Notes");
+        assert_eq!(req.excerpt, "Short");
+
+        // Fields the request sets win, and explicit tags suppress front-matter ones
+        req.title = "Given".to_string();
+        req.tags = vec![Uuid::new_v4()];
+        assert!(apply_frontmatter(&mut req).is_empty());
+        assert_eq!(req.title, "Given");
+
+        let frontmatter = parse_frontmatter(body).0.unwrap();
+        assert_eq!(preview_tags(body, &frontmatter), vec!["rust", "wasm"]);
+        assert_eq!(preview_tags("#b and #a", &Frontmatter::default()), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_timeline_period_allowlist() {
+        assert_eq!(StatsPeriod::parse(None), Some(StatsPeriod::Month));
+        assert_eq!(StatsPeriod::parse(Some("week")), Some(StatsPeriod::Week));
+        assert_eq!(StatsPeriod::parse(Some("day")).map(StatsPeriod::as_str), Some("day"));
+        assert_eq!(StatsPeriod::parse(Some("year")), None);
+        assert_eq!(StatsPeriod::parse(Some("month'); DROP TABLE posts; --")), None);
+    }
+
+    #[test]
+    fn test_idempotency_key_header() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("idempotency-key", value.parse().unwrap());
+            headers
+        };
+        assert_eq!(idempotency_key(&HeaderMap::new()).unwrap(), None);
+        assert_eq!(idempotency_key(&headers(" retry-1 ")).unwrap().as_deref(), Some("retry-1"));
+        assert!(idempotency_key(&headers(" ")).is_err());
+        assert!(idempotency_key(&headers(&"k".repeat(256))).is_err());
+    }
+
+    #[test]
+    fn test_replayed_create_matches_original_response() {
+        let post = Post {
+            excerpt: "Created a single time".to_string(),
+            ..post("once", "# Once\n\nBody")
+        };
+        let original = serde_json::to_value(&post).unwrap();
+
+        // The retry is answered from the stored response, byte for byte
+        let replayed = replay_post(original.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&replayed).unwrap(), original);
+        assert!(replay_post(json!({ "not": "a post" })).is_err());
+
+        // Replays point at the same resource as the original 201
+        let response = created_post(replayed).into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::LOCATION], "/api/posts/once");
+    }
+
+    #[test]
+    fn test_blank_excerpt_on_update_is_generated() {
+        let existing = post("notes", "");
+        let body = "# Notes\n\nSome thoughts about writing parsers by hand.";
+
+        let req = UpdatePostRequest {
+            excerpt: Some("   ".to_string()),
+            body: Some(body.to_string()),
+            ..Default::default()
+        };
+        let excerpt = excerpt_for_update(&req, &existing, 10).unwrap();
+        assert!(excerpt.starts_with("Some thoughts"), "{}", excerpt);
+
+        // Empty body: an empty excerpt, not a panic
+        let req = UpdatePostRequest {
+            excerpt: Some(String::new()),
+            body: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(excerpt_for_update(&req, &existing, 10).as_deref(), Some(""));
+
+        // A real excerpt is left alone
+        let req = UpdatePostRequest {
+            excerpt: Some("Hand-written".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(excerpt_for_update(&req, &existing, 10), None);
+    }
+
+    #[test]
+    fn test_publish_blocked_for_incomplete_posts() {
+        let body = "A finished thought, long enough to stand on its own.";
+        assert_eq!(publish_blocker("Title", body), None);
+
+        let reason = |title, body| publish_blocker(title, body).unwrap();
+        assert!(reason("  ", body).contains("title"));
+        assert!(reason("Title", "\n  \n").contains("empty body"));
+        assert!(reason("Title", "---\ntitle: Title\n---\n\n").contains("front-matter"));
+        assert!(reason("Title", "---\ntitle: Title\n---\nTODO").contains("only 4 characters"));
+    }
+
+    #[test]
+    fn test_publish_without_cover_fails_when_required() {
+        let body = "# Title\n\nJust text, no images.";
+        let errors = cover_image_errors(body, true);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "cover_image");
+
+        // Guard off, or a cover present, lets the post through
+        assert!(cover_image_errors(body, false).is_empty());
+        assert!(cover_image_errors("![[cover.png]]\n\nText", true).is_empty());
+    }
+
+    #[test]
+    fn test_nav_preview_places_draft_at_intended_date() {
+        let now = Utc::now();
+        let scheduled = now + Duration::days(3);
+        let draft = |publish_at| Post {
+            published: false,
+            published_at: None,
+            created_at: now - Duration::days(10),
+            updated_at: now,
+            publish_at,
+            ..post("draft", "")
+        };
+
+        // Scheduled drafts are ordered by their publish date, not their creation date
+        let projected = as_if_published(draft(Some(scheduled)), now);
+        assert_eq!(projected.published_at, Some(scheduled));
+
+        // Unscheduled drafts sit at "now", after every existing post
+        assert_eq!(as_if_published(draft(None), now).published_at, Some(now));
+    }
+
+    #[test]
+    fn test_dedupe_slug_appends_counter() {
+        let taken = |slugs: &[&str]| slugs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(dedupe_slug("hello-world", &taken(&[])), "hello-world");
+        assert_eq!(dedupe_slug("hello-world", &taken(&["hello-world"])), "hello-world-2");
+        assert_eq!(
+            dedupe_slug("hello-world", &taken(&["hello-world", "hello-world-2", "hello-world-4"])),
+            "hello-world-3"
+        );
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+    }
+
+    #[sqlx::test]
+    async fn test_idempotent_create_makes_one_post(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let role = Role::Author;
+        let author = db::create_user(&pool, "writer", "not-a-real-hash", role).await?;
+        let state = Arc::new(AppState::new(pool.clone(), "test-secret".to_string()));
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", "retry-me".parse().unwrap());
+        let req = CreatePostRequest {
+            slug: String::new(),
+            title: "Retried Post".to_string(),
+            excerpt: String::new(),
+            body: "Sent twice over a flaky connection.".to_string(),
+            tags: vec![],
+            published: false,
+            trusted_html: false,
+            noindex: false,
+            publish_at: None,
+        };
+
+        let mut responses = Vec::new();
+        for _ in 0..2 {
+            let user = AuthUser {
+                user_id: author.id,
+                username: author.username.clone(),
+                role,
+            };
+            let created = create_post(
+                State(state.clone()),
+                user,
+                headers.clone(),
+                Json(req.clone()),
+            )
+            .await
+            .unwrap()
+            .into_response();
+            assert_eq!(created.status(), StatusCode::CREATED);
+            let location = created.headers()[header::LOCATION].clone();
+            let body = shuttle_axum::axum::body::to_bytes(created.into_body(), usize::MAX).await?;
+            responses.push((location, body));
+        }
+        assert_eq!(responses[0], responses[1]);
+
+        // The same key with a different body is refused rather than answered with the first post
+        let user = AuthUser {
+            user_id: author.id,
+            username: author.username.clone(),
+            role,
+        };
+        let other = CreatePostRequest {
+            title: "Something Else".to_string(),
+            ..req
+        };
+        let refused = create_post(State(state), user, headers, Json(other)).await.err();
+        assert!(matches!(refused, Some(AppError::Unprocessable(_))));
+
+        let posts = db::list_all_posts(&pool).await?;
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].slug, "retried-post");
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_authors_cannot_see_each_others_drafts(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let owner = db::create_user(&pool, "owner", "not-a-real-hash", Role::Author).await?;
+        let other = db::create_user(&pool, "other", "not-a-real-hash", Role::Author).await?;
+        let state = Arc::new(AppState::new(pool.clone(), "test-secret".to_string()));
+        let req = CreatePostRequest {
+            slug: "secret-plans".to_string(),
+            title: "Secret plans".to_string(),
+            excerpt: String::new(),
+            body: "Unannounced haskell rewrite".to_string(),
+            tags: vec![],
+            published: false,
+            trusted_html: false,
+            noindex: false,
+            publish_at: None,
+        };
+        let draft = db::create_post(&pool, req, owner.id).await?;
+        let as_user = |user: &crate::models::User| AuthUser {
+            user_id: user.id,
+            username: user.username.clone(),
+            role: Role::Author,
+        };
+        let search = || SearchParams {
+            q: Some("haskell".to_string()),
+            tag: None,
+        };
+
+        let id = Path(draft.id.to_string());
+        let refused = get_post_by_id(State(state.clone()), as_user(&other), id).await.err();
+        assert!(matches!(refused, Some(AppError::Forbidden(_))));
+        let hits = search_posts(State(state.clone()), as_user(&other), Query(search())).await?;
+        assert!(hits.0.is_empty());
+
+        let id = Path(draft.id.to_string());
+        let fetched = get_post_by_id(State(state.clone()), as_user(&owner), id).await?;
+        assert_eq!(fetched.0.id, draft.id);
+        let hits = search_posts(State(state), as_user(&owner), Query(search())).await?;
+        assert_eq!(hits.0.len(), 1);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_rejected_post_creates_no_tags(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let author = db::create_user(&pool, "writer", "not-a-real-hash", Role::Author).await?;
+        let state = AppState::new(pool.clone(), "test-secret".to_string());
+        let user = AuthUser {
+            user_id: author.id,
+            username: author.username.clone(),
+            role: Role::Author,
+        };
+        let req = |slug: &str| CreatePostRequest {
+            slug: slug.to_string(),
+            title: String::new(),
+            excerpt: String::new(),
+            body: "---\ntitle: Tagged Note\ntags: [fresh-tag]\n---\nBody".to_string(),
+            tags: vec![],
+            published: false,
+            trusted_html: false,
+            noindex: false,
+            publish_at: None,
+        };
+
+        let rejected = insert_post(&state, &user, req("Bad Slug")).await.err();
+        assert!(matches!(rejected, Some(AppError::Validation(_))));
+        assert!(db::get_tag_by_name(&pool, "fresh-tag").await?.is_none());
+
+        let post = insert_post(&state, &user, req("tagged-note")).await?;
+        let names: Vec<&str> = post.tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["fresh-tag"]);
+        Ok(())
+    }
 }
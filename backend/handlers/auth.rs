@@ -1,28 +1,41 @@
 use shuttle_axum::axum::{
     extract::{FromRef, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
+use std::net::IpAddr;
+use chrono::Utc;
 use std::sync::Arc;
 use serde_json::json;
 use tokio::time::{sleep, Duration};
 
 use crate::{
-    auth::{generate_jwt, verify_password},
+    auth::{
+        access_token_ttl, generate_jwt, generate_refresh_token, hash_password, hash_token,
+        refresh_token_ttl, verify_password, AuthUser,
+    },
     db,
     error::AppError,
-    models::{LoginRequest, LoginResponse, UserInfo},
+    models::{
+        ChangePasswordRequest, LoginRequest, LoginResponse, RefreshRequest, RefreshResponse,
+        UserInfo,
+    },
     state::AppState,
+    visitor::ClientIp,
 };
 
 /// Real login endpoint (backed by Postgres users table).
 /// - Verifies username/password with Argon2
-/// - Issues a JWT on success
+/// - Issues a short-lived JWT plus a refresh token backed by a session row
 pub async fn login(
     State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
     Json(payload): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, AppError> {
+    let ip = login_client(ip)?;
+    check_login_allowed(&state, ip)?;
+
     let username = payload.username.trim();
     let password = payload.password;
 
@@ -32,27 +45,43 @@ pub async fn login(
         ));
     }
 
-    // Fetch user
-    let user = db::get_user_by_username(&state.pool, username)
-        .await?
-        .ok_or_else(|| AppError::Unauthorized("Invalid credentials".into()))?;
-
-    // Verify password
-    let verified = verify_password(&password, &user.password_hash)
-        .map_err(|_| AppError::Unauthorized("Invalid credentials".into()))?;
+    // Fetch user and verify password
+    let user = match db::get_user_by_username(&state.pool, username).await? {
+        Some(user) if verify_password(&password, &user.password_hash).unwrap_or(false) => user,
+        _ => {
+            state.login_limiter.record_failure(ip);
+            return Err(AppError::Unauthorized("Invalid credentials".into()));
+        }
+    };
 
-    if !verified {
-        return Err(AppError::Unauthorized("Invalid credentials".into()));
-    }
+    state.login_limiter.record_success(ip);
 
     // Issue JWT
-    let token = generate_jwt(user.id, &user.username, &state.jwt_secret)?;
+    let token = generate_jwt(
+        user.id,
+        &user.username,
+        user.role,
+        &state.jwt_secret,
+        access_token_ttl(),
+    )?;
+
+    // Persist a session for the refresh token
+    let refresh_token = generate_refresh_token();
+    db::create_session(
+        &state.pool,
+        user.id,
+        &hash_token(&refresh_token),
+        Utc::now() + refresh_token_ttl(),
+    )
+    .await?;
 
     let res = LoginResponse {
         token,
+        refresh_token,
         user: UserInfo {
             id: user.id,
             username: user.username,
+            role: user.role,
         },
     };
 
@@ -61,14 +90,122 @@ pub async fn login(
     Ok(Json(res))
 }
 
+/// Exchange a valid refresh token for a new access token
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<RefreshResponse>, AppError> {
+    let session = db::get_active_session(&state.pool, &hash_token(&payload.refresh_token))
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid refresh token".into()))?;
+
+    let user = db::get_user_by_id(&state.pool, session.user_id)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid refresh token".into()))?;
+
+    // Re-read the role so demotions take effect on the next refresh
+    let token = generate_jwt(
+        user.id,
+        &user.username,
+        user.role,
+        &state.jwt_secret,
+        access_token_ttl(),
+    )?;
+
+    Ok(Json(RefreshResponse { token }))
+}
+
+/// Log out by deleting the refresh token's session.
+/// Outstanding access tokens remain valid until they expire.
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<StatusCode, AppError> {
+    db::delete_session(&state.pool, &hash_token(&payload.refresh_token)).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Minimum length accepted for a new password
+pub(crate) const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Change the logged-in user's password.
+/// - Requires the current password
+/// - Stores an Argon2 hash of the new one
+/// - Revokes every refresh session, so other devices must sign in again
+pub async fn change_password(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    if payload.new_password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "new password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    let account = db::get_user_by_id(&state.pool, user.user_id)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid credentials".into()))?;
+
+    let verified = verify_password(&payload.current_password, &account.password_hash)
+        .map_err(|_| AppError::Unauthorized("Invalid credentials".into()))?;
+
+    if !verified {
+        return Err(AppError::Unauthorized("Invalid credentials".into()));
+    }
+
+    let password_hash = hash_password(&payload.new_password)?;
+    let revoked = db::update_user_password(&state.pool, account.id, &password_hash).await?;
+
+    tracing::info!(
+        "user '{}' changed their password; {} sessions revoked",
+        account.username,
+        revoked
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The address logins are throttled by. Unknown clients are refused rather than
+/// pooled under one shared key, where a few bad attempts would lock everyone out.
+/// The server runs with connect info, so this only fails if that's misconfigured.
+fn login_client(ip: Option<IpAddr>) -> Result<IpAddr, AppError> {
+    ip.ok_or_else(|| AppError::Internal("Client address unavailable for login".to_string()))
+}
+
+/// Reject a login attempt while `ip` is locked out
+fn check_login_allowed(state: &AppState, ip: IpAddr) -> Result<(), AppError> {
+    state.login_limiter.check(ip).map_err(|retry_after| {
+        AppError::TooManyRequests(format!(
+            "Too many failed login attempts. Try again in {} seconds.",
+            retry_after.as_secs().max(1)
+        ))
+    })
+}
+
 /// Decoy login endpoint (always fails).
 /// - Lives under the "admin" path to attract scanners
 /// - Always returns 401 with a small delay
-pub async fn decoy_login(Json(payload): Json<LoginRequest>) -> impl IntoResponse {
+/// - Shares the real login's rate limiter so lockouts look identical
+pub async fn decoy_login(
+    State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    Json(payload): Json<LoginRequest>,
+) -> Response {
+    let ip = match login_client(ip) {
+        Ok(ip) => ip,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = check_login_allowed(&state, ip) {
+        return e.into_response();
+    }
+
     // Add a small randomized delay to make enumeration harder
     let delay_ms = 300 + (payload.username.len() as u64 % 400);
     sleep(Duration::from_millis(delay_ms)).await;
 
+    state.login_limiter.record_failure(ip);
     tracing::warn!("decoy login attempt for user '{}'", payload.username);
 
     (
@@ -78,4 +215,5 @@ pub async fn decoy_login(Json(payload): Json<LoginRequest>) -> impl IntoResponse
             "message": "The username or password you entered is incorrect."
         })),
     )
+        .into_response()
 }
@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+use shuttle_axum::axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// Weak entity tag for a resource version, hashed from the parts that identify it.
+/// Weak because it names the version rather than the bytes: gzip, brotli and identity
+/// responses from the compression layer all share it.
+pub fn etag(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+    format!("W/\"{}\"", hex)
+}
+
+/// Whether the client's `If-None-Match` already names this version (weak comparison)
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = etag.trim_start_matches("W/");
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == opaque)
+        })
+}
+
+/// 304 with no body, repeating the tag
+pub fn not_modified(etag: &str) -> Response {
+    (StatusCode::NOT_MODIFIED, [(header::ETAG, etag.to_string())]).into_response()
+}
+
+/// A normal response carrying the tag
+pub fn with_etag(etag: &str, body: impl IntoResponse) -> Response {
+    ([(header::ETAG, etag.to_string())], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_none_match() {
+        let tag = etag(&["post-id", "2026-10-16T12:00:00Z"]);
+        assert_ne!(tag, etag(&["post-id", "2026-10-16T12:00:01Z"]));
+        // Part boundaries matter
+        assert_ne!(etag(&["ab", "c"]), etag(&["a", "bc"]));
+
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, value.parse().unwrap());
+            headers
+        };
+        assert!(tag.starts_with("W/\""));
+        assert!(if_none_match(&headers(&tag), &tag));
+        assert!(if_none_match(&headers(&format!("\"other\", {}", tag)), &tag));
+        // A client (or proxy) that dropped the weak marker still matches
+        assert!(if_none_match(&headers(tag.trim_start_matches("W/")), &tag));
+        assert!(if_none_match(&headers("*"), &tag));
+        assert!(!if_none_match(&headers("\"other\""), &tag));
+        assert!(!if_none_match(&HeaderMap::new(), &tag));
+
+        let res = not_modified(&tag);
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()[header::ETAG], tag.as_str());
+    }
+}
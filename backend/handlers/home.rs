@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use shuttle_axum::axum::{
+    extract::{Query, State},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::{
+    db,
+    error::AppError,
+    handlers::tags::TagStats,
+    models::PostSummary,
+    state::AppState,
+};
+
+/// Posts with this tag make up the homepage's featured section
+pub const FEATURED_TAG: &str = "featured";
+
+/// Largest number of items any one homepage section may ask for
+const MAX_SECTION_SIZE: u32 = 20;
+
+/// Per-section caps for the homepage; each defaults when omitted
+#[derive(Debug, Default, Deserialize)]
+pub struct HomeParams {
+    pub featured: Option<u32>,
+    pub recent: Option<u32>,
+    pub tags: Option<u32>,
+}
+
+impl HomeParams {
+    fn cap(value: Option<u32>, default: u32) -> usize {
+        value.unwrap_or(default).clamp(1, MAX_SECTION_SIZE) as usize
+    }
+
+    fn featured(&self) -> usize {
+        Self::cap(self.featured, 3)
+    }
+
+    fn recent(&self) -> usize {
+        Self::cap(self.recent, 10)
+    }
+
+    fn tags(&self) -> usize {
+        Self::cap(self.tags, 10)
+    }
+}
+
+/// Everything the homepage needs in one payload
+#[derive(Serialize)]
+pub struct HomeResponse {
+    pub featured: Vec<PostSummary>,
+    pub recent: Vec<PostSummary>,
+    pub top_tags: Vec<TagStats>,
+    pub post_count: usize,
+}
+
+/// Homepage composition: featured and recent posts, top tags and the post count
+pub async fn get_home(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HomeParams>,
+) -> Result<Json<HomeResponse>, AppError> {
+    let (featured, _) =
+        db::get_posts_by_tag(&state.pool, FEATURED_TAG, params.featured() as i64, 0).await?;
+    let recent = db::list_recent_posts_excluding(&state.pool, &[], params.recent() as i64).await?;
+    let (_, post_count) = db::published_posts_version(&state.pool).await?;
+    let tag_stats = db::get_tag_stats(&state.pool).await?;
+
+    Ok(Json(compose_home(featured, recent, tag_stats, post_count as usize, &params)))
+}
+
+/// Assemble the sections; posts arrive already capped, tags are capped here
+fn compose_home(
+    featured: Vec<PostSummary>,
+    recent: Vec<PostSummary>,
+    tag_stats: Vec<TagStats>,
+    post_count: usize,
+    params: &HomeParams,
+) -> HomeResponse {
+    let top_tags = tag_stats
+        .into_iter()
+        .filter(|stats| stats.post_count > 0)
+        .take(params.tags())
+        .collect();
+
+    HomeResponse {
+        featured,
+        recent,
+        top_tags,
+        post_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Tag;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn summary(slug: &str) -> PostSummary {
+        PostSummary {
+            id: Uuid::new_v4(),
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            excerpt: String::new(),
+            published_at: Utc::now(),
+            reading_time: "1 min read".to_string(),
+            author: Default::default(),
+            tags: vec![],
+        }
+    }
+
+    fn stats(name: &str, post_count: usize) -> TagStats {
+        TagStats {
+            tag: Tag {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                color: "blue".to_string(),
+                created_at: Utc::now(),
+            },
+            post_count,
+        }
+    }
+
+    #[test]
+    fn test_home_contains_every_section() {
+        let recent = (0..5).map(|i| summary(&format!("post-{}", i))).collect();
+        let params = HomeParams {
+            tags: Some(1),
+            ..Default::default()
+        };
+        let home = compose_home(
+            vec![summary("pinned")],
+            recent,
+            vec![stats("rust", 4), stats("haskell", 2), stats("empty", 0)],
+            15,
+            &params,
+        );
+
+        let json = serde_json::to_value(&home).unwrap();
+        for section in ["featured", "recent", "top_tags", "post_count"] {
+            assert!(json.get(section).is_some(), "missing {}", section);
+        }
+        assert_eq!(home.featured.len(), 1);
+        assert_eq!(home.recent.len(), 5);
+        assert_eq!(home.recent[0].slug, "post-0");
+        assert_eq!(home.top_tags.len(), 1);
+        assert_eq!(home.post_count, 15);
+    }
+}
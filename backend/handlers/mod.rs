@@ -1,4 +1,8 @@
 pub mod admin;
 pub mod auth;
+pub mod etag;
+pub mod home;
 pub mod posts;
 pub mod tags;
+pub mod users;
+pub mod vault;
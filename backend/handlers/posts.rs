@@ -1,46 +1,153 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use shuttle_axum::axum::{
-    extract::{Path, State},
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use std::net::IpAddr;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
     db,
     error::AppError,
-    markdown::{extract_links, render_obsidian_markdown, strip_first_heading},
-    models::{Post, PostSummary},
+    handlers::etag::{etag, if_none_match, not_modified, with_etag},
+    markdown::{
+        build_toc, calculate_reading_time, extract_cover_image, extract_excerpt, extract_headings,
+        extract_links, render_with_deadline, sanitize_html, strip_title_heading, Heading,
+        RenderOptions,
+    },
+    models::{
+        AuthorInfo, Page, PaginationParams, Post, PostChange, PostMeta, PostSummary, SearchHit,
+        SitemapEntry, Tag,
+    },
     state::AppState,
+    visitor::{hash_ip, ClientIp},
 };
 
-/// List all published posts
-pub async fn list_posts(State(state): State<Arc<AppState>>) -> Result<Json<Vec<PostSummary>>, AppError> {
+/// List all published posts.
+/// Tagged with the newest change, the post count and the tags embedded in each post,
+/// so unchanged lists answer 304.
+pub async fn list_posts(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let (last_updated, count) = db::published_posts_version(&state.pool).await?;
+    let tags_version = db::tags_version(&state.pool).await?;
+    let tag = etag(&[
+        &last_updated.map(|at| at.to_rfc3339()).unwrap_or_default(),
+        &count.to_string(),
+        &tags_version,
+    ]);
+    if if_none_match(&headers, &tag) {
+        return Ok(not_modified(&tag));
+    }
+
     let posts = db::list_published_posts(&state.pool).await?;
-    Ok(Json(posts))
+    Ok(with_etag(&tag, Json(posts)))
+}
+
+/// Query parameters for the post detail endpoint
+#[derive(Deserialize)]
+pub struct PostParams {
+    /// Deepest heading level included in the TOC
+    pub max_depth: Option<u8>,
+    /// Re-render instead of serving cached HTML (debugging)
+    #[serde(default)]
+    pub nocache: bool,
 }
 
 /// Get a single published post by slug
 pub async fn get_post(
     State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    OriginalUri(uri): OriginalUri,
     Path(slug): Path<String>,
-) -> Result<Json<PostResponse>, AppError> {
-    let post = db::get_post_by_slug(&state.pool, &slug)
-        .await?
-        .ok_or_else(|| AppError::NotFound(format!("Post '{}' not found", slug)))?;
+    Query(params): Query<PostParams>,
+) -> Result<Response, AppError> {
+    let post = match db::get_post_by_slug(&state.pool, &slug).await? {
+        Some(post) => post,
+        None => {
+            let existing = db::get_post_by_slug_any(&state.pool, &slug).await?;
+            // Old slugs of renamed posts redirect permanently to the current one
+            if existing.is_none() {
+                if let Some(new_slug) = db::find_slug_redirect(&state.pool, &slug).await? {
+                    let location = redirect_location(uri.path(), uri.query(), &new_slug);
+                    return Ok((StatusCode::PERMANENT_REDIRECT, [(header::LOCATION, location)])
+                        .into_response());
+                }
+            }
+            return Err(missing_post_error(&slug, existing.as_ref()));
+        }
+    };
+
+    // Count the view off the read path; repeat views from one client count once per window
+    if state.view_dedup.record(&view_key(post.id, ip, &state.ip_salt)) {
+        let pool = state.pool.clone();
+        let post_id = post.id;
+        tokio::spawn(async move {
+            if let Err(e) = db::increment_view_count(&pool, post_id).await {
+                tracing::warn!("Failed to count view of post {}: {}", post_id, e);
+            }
+        });
+    }
+
+    // The client's copy is current: skip rendering entirely.
+    // Related and adjacent posts come from the live list, so its version is part of the tag.
+    let (last_updated, count) = db::published_posts_version(&state.pool).await?;
+    let tags_version = db::tags_version(&state.pool).await?;
+    let tag = etag(&[
+        &post.id.to_string(),
+        &post.updated_at.to_rfc3339(),
+        &params.max_depth.map(|d| d.to_string()).unwrap_or_default(),
+        &last_updated.map(|at| at.to_rfc3339()).unwrap_or_default(),
+        &count.to_string(),
+        &tags_version,
+    ]);
+    if if_none_match(&headers, &tag) {
+        return Ok(not_modified(&tag));
+    }
 
     // Strip the first heading from body for rendering
-    let body = strip_first_heading(&post.body);
+    let body = strip_title_heading(&post.body, &post.title);
     tracing::info!("Original body starts with: {:?}", &post.body.chars().take(50).collect::<String>());
     tracing::info!("Stripped body starts with: {:?}", &body.chars().take(50).collect::<String>());
 
-    // Render the markdown content to HTML
-    let html = render_obsidian_markdown(&body);
+    // Render the markdown content to HTML.
+    // Trusted HTML needs both the post's opt-in and an admin author.
+    let author_role = db::get_author_role(&state.pool, post.author_id).await?;
+    let opts = RenderOptions::for_author(author_role, post.trusted_html)
+        .with_url_schemes(&state.url_schemes)
+        .with_max_callout_depth(state.max_callout_depth);
+    let profile = opts.profile;
+    let cached = if params.nocache {
+        None
+    } else {
+        state.render_cache.get(post.id, post.updated_at, profile)
+    };
+    let html = match cached {
+        Some(html) => html,
+        None => {
+            let html = render_with_deadline(body.clone(), opts, state.render_timeout).await?;
+            state.render_cache.insert(post.id, post.updated_at, profile, html.clone());
+            html
+        }
+    };
+
+    // Build the table of contents (H2–H4 unless a depth is requested)
+    let toc = match params.max_depth {
+        Some(max_depth) => build_toc(&body, max_depth),
+        None => extract_headings(&body),
+    };
 
     // Extract wiki-links for potential backlinks
     let links = extract_links(&post.body);
 
-    // Get related posts by tags
-    let related = db::get_related_posts(&state.pool, post.id, 5).await?;
+    // Get related posts by tags, topped up with recent posts
+    let related = get_related_posts(&state, post.id).await?;
 
     // Get adjacent posts (previous and next by date)
     let adjacent = get_adjacent_posts(&state.pool, &post).await?;
@@ -52,16 +159,163 @@ pub async fn get_post(
         excerpt: post.excerpt,
         body,
         html,
+        toc,
         published_at: post.published_at,
         created_at: post.created_at,
         updated_at: post.updated_at,
+        author: post.author,
         tags: post.tags,
         links,
         related,
         adjacent,
     };
 
-    Ok(Json(response))
+    Ok(with_etag(&tag, Json(response)))
+}
+
+/// Dedup key for a client's view of a post; clients without a known IP share one key
+fn view_key(post_id: Uuid, ip: Option<IpAddr>, salt: &str) -> String {
+    let client = ip.map(|ip| hash_ip(&ip, salt)).unwrap_or_default();
+    format!("{}:{}", post_id, client)
+}
+
+/// Query parameters for the popular posts listing
+#[derive(Deserialize)]
+pub struct PopularParams {
+    pub limit: Option<u32>,
+}
+
+impl PopularParams {
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 50;
+
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT).clamp(1, Self::MAX_LIMIT) as i64
+    }
+}
+
+/// Most-read published posts
+pub async fn list_popular_posts(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PopularParams>,
+) -> Result<Json<Vec<PostSummary>>, AppError> {
+    let posts = db::list_popular_posts(&state.pool, params.limit()).await?;
+    Ok(Json(posts))
+}
+
+/// A trashed post answers 410 so clients and crawlers drop it; anything else is 404
+fn missing_post_error(slug: &str, existing: Option<&Post>) -> AppError {
+    match existing {
+        Some(post) if post.deleted_at.is_some() => {
+            AppError::Gone(format!("Post '{}' has been deleted", slug))
+        }
+        _ => AppError::NotFound(format!("Post '{}' not found", slug)),
+    }
+}
+
+/// `path` with its last segment replaced by `new_slug`, keeping the query string
+fn redirect_location(path: &str, query: Option<&str>, new_slug: &str) -> String {
+    let parent = path.rsplit_once('/').map_or("", |(parent, _)| parent);
+    match query {
+        Some(query) => format!("{}/{}?{}", parent, new_slug, query),
+        None => format!("{}/{}", parent, new_slug),
+    }
+}
+
+/// Fallback social preview image for posts without one of their own
+const DEFAULT_OG_IMAGE: &str = "/og-default.png";
+
+/// Get social preview metadata for a published post
+pub async fn get_post_meta(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<PostMeta>, AppError> {
+    let post = db::get_post_by_slug(&state.pool, &slug)
+        .await?
+        .filter(|p| p.is_live())
+        .ok_or_else(|| AppError::NotFound(format!("Post '{}' not found", slug)))?;
+
+    Ok(Json(build_post_meta(post, state.frontend_url.as_deref())))
+}
+
+/// Build social metadata; site-relative URLs are made absolute against the frontend URL
+fn build_post_meta(post: Post, frontend_url: Option<&str>) -> PostMeta {
+    let base = frontend_url.unwrap_or("").trim_end_matches('/');
+    let absolute = |url: &str| {
+        if url.starts_with('/') {
+            format!("{}{}", base, url)
+        } else {
+            url.to_string()
+        }
+    };
+
+    let source = if post.excerpt.trim().is_empty() {
+        strip_title_heading(&post.body, &post.title)
+    } else {
+        post.excerpt.clone()
+    };
+    let image = extract_cover_image(&post.body).unwrap_or_else(|| DEFAULT_OG_IMAGE.to_string());
+
+    PostMeta {
+        robots: if post.noindex { "noindex" } else { "index, follow" }.to_string(),
+        description: extract_excerpt(&source, 200),
+        canonical_url: absolute(&format!("/blogs/{}", post.slug)),
+        image: absolute(&image),
+        title: post.title,
+        author: post.author,
+        published_at: post.published_at,
+        tags: post.tags.into_iter().map(|t| t.name).collect(),
+    }
+}
+
+/// Sitemap of live posts; posts flagged `noindex` are left out
+pub async fn get_sitemap(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, AppError> {
+    let entries = db::list_sitemap_entries(&state.pool).await?;
+    let xml = build_sitemap(state.frontend_url.as_deref().unwrap_or(""), &entries);
+    Ok(([(header::CONTENT_TYPE, "application/xml")], xml))
+}
+
+fn build_sitemap(base: &str, entries: &[SitemapEntry]) -> String {
+    let base = base.trim_end_matches('/');
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for entry in entries.iter().filter(|e| !e.noindex) {
+        xml.push_str(&format!(
+            "  <url><loc>{}/blogs/{}</loc><lastmod>{}</lastmod></url>\n",
+            escape_xml(base),
+            escape_xml(&entry.slug),
+            entry.updated_at.format("%Y-%m-%d")
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Get published posts linking to a post, paginated
+pub async fn get_backlinks(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<Page<PostSummary>>, AppError> {
+    db::get_post_by_slug(&state.pool, &slug)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Post '{}' not found", slug)))?;
+
+    let (limit, offset) = params.limit_offset();
+    let (posts, total) = db::get_backlinks(&state.pool, &slug, limit, offset).await?;
+
+    Ok(Json(Page::new(posts, total, &params)))
 }
 
 /// Response structure for a single post with additional data
@@ -73,9 +327,11 @@ pub struct PostResponse {
     pub excerpt: String,
     pub body: String,
     pub html: String,
+    pub toc: Vec<Heading>,
     pub published_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub author: AuthorInfo,
     pub tags: Vec<crate::models::Tag>,
     pub links: Vec<String>,
     pub related: Vec<PostSummary>,
@@ -88,49 +344,549 @@ pub struct AdjacentPosts {
     pub next: Option<PostSummary>,
 }
 
+/// Related posts shown on a post page
+const RELATED_POSTS_LIMIT: usize = 5;
+
+/// Posts sharing tags with `post_id`; when too few share the configured number, posts
+/// sharing any tag come next, then the most recent other posts fill the remaining slots
+/// so the section is never empty
+async fn get_related_posts(state: &AppState, post_id: Uuid) -> Result<Vec<PostSummary>, AppError> {
+    let limit = RELATED_POSTS_LIMIT as i64;
+    let mut related =
+        db::get_related_posts(&state.pool, post_id, limit, state.related_min_common_tags).await?;
+    if related.len() < RELATED_POSTS_LIMIT && state.related_min_common_tags > 1 {
+        let loose = db::get_related_posts(&state.pool, post_id, limit, 1).await?;
+        related = fill_related(related, loose, post_id, RELATED_POSTS_LIMIT);
+    }
+    if related.len() >= RELATED_POSTS_LIMIT {
+        return Ok(related);
+    }
+
+    let mut exclude: Vec<Uuid> = related.iter().map(|p| p.id).collect();
+    exclude.push(post_id);
+    let recent = db::list_recent_posts_excluding(&state.pool, &exclude, limit).await?;
+    Ok(fill_related(related, recent, post_id, RELATED_POSTS_LIMIT))
+}
+
+/// Append `more` posts not already present (nor the current post) up to `limit`
+fn fill_related(
+    mut related: Vec<PostSummary>,
+    more: Vec<PostSummary>,
+    current: Uuid,
+    limit: usize,
+) -> Vec<PostSummary> {
+    for post in more {
+        if related.len() >= limit {
+            break;
+        }
+        if post.id != current && related.iter().all(|r| r.id != post.id) {
+            related.push(post);
+        }
+    }
+    related.truncate(limit);
+    related
+}
+
 /// Get adjacent posts (previous and next by publication date)
 async fn get_adjacent_posts(
     pool: &sqlx::PgPool,
     current_post: &Post,
 ) -> Result<AdjacentPosts, AppError> {
-    // For now, return None for both - can be implemented later
-    Ok(AdjacentPosts { previous: None, next: None })
+    let (previous, next) = db::get_adjacent_posts(pool, current_post).await?;
+    Ok(AdjacentPosts { previous, next })
+}
+
+/// Query parameters for public search (`?q=&tag=`)
+#[derive(Deserialize)]
+pub struct SearchParams {
+    pub q: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl SearchParams {
+    /// Tag to restrict results to; blank values mean no filter
+    pub(crate) fn tag_filter(&self) -> Option<&str> {
+        self.tag.as_deref().map(str::trim).filter(|t| !t.is_empty())
+    }
 }
 
-/// Search published posts
+/// Search live posts, with a highlighted snippet of the matching text
 pub async fn search_posts(
-    State(state): State<AppState>,
-    query: String,
-) -> Result<Json<Vec<PostSummary>>, AppError> {
-    if query.trim().is_empty() {
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SearchHit>>, AppError> {
+    let q = params.q.as_deref().unwrap_or_default();
+    if q.trim().is_empty() {
         return Ok(Json(vec![]));
     }
 
-    let posts = db::search_posts(&state.pool, &query)
-        .await?
+    let hits = db::search_posts_with_snippets(&state.pool, q, params.tag_filter()).await?;
+    Ok(Json(search_hits(hits)))
+}
+
+/// Query parameters for incremental sync
+#[derive(Deserialize)]
+pub struct ChangedSinceParams {
+    pub ts: DateTime<Utc>,
+    /// Id of the last change already seen at exactly `ts`; later changes sharing
+    /// that instant are still returned
+    pub after: Option<Uuid>,
+}
+
+/// Most changes returned per sync request; mirrors continue from the last
+/// change's `changed_at` and `id`
+const MAX_CHANGES: i64 = 500;
+
+/// Posts changed after the `(ts, after)` cursor, oldest first, with removed posts
+/// marked `deleted`. Drafts and scheduled posts never appear until they go live.
+pub async fn list_changed_since(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ChangedSinceParams>,
+) -> Result<Json<Vec<PostChange>>, AppError> {
+    let posts =
+        db::list_posts_changed_since(&state.pool, params.ts, params.after, MAX_CHANGES).await?;
+    let purged =
+        db::list_tombstones_since(&state.pool, params.ts, params.after, MAX_CHANGES).await?;
+    let cursor = (params.ts, params.after);
+    Ok(Json(changes_since(posts, purged, cursor, MAX_CHANGES as usize)))
+}
+
+/// Up to `limit` changes past `cursor`, ordered by when they happened, then by id.
+/// Posts that aren't live only reveal their id and slug.
+fn changes_since(
+    posts: Vec<Post>,
+    purged: Vec<PostChange>,
+    cursor: (DateTime<Utc>, Option<Uuid>),
+    limit: usize,
+) -> Vec<PostChange> {
+    let (since, after) = cursor;
+    let past_cursor = |c: &PostChange| {
+        c.changed_at > since || (c.changed_at == since && after.is_some_and(|id| c.id > id))
+    };
+    let mut changes: Vec<PostChange> = posts
         .into_iter()
-        .filter(|p| p.published)
-        .map(|p| PostSummary {
-            id: p.id,
-            slug: p.slug,
-            title: p.title,
-            excerpt: p.excerpt,
-            published_at: p.published_at.unwrap_or(p.created_at),
-            reading_time: crate::markdown::calculate_reading_time(&p.body),
-            tags: p.tags,
+        .map(|p| {
+            let live = p.is_live();
+            PostChange {
+                id: p.id,
+                slug: p.slug.clone(),
+                changed_at: p.changed_at(),
+                deleted: !live,
+                post: live.then(|| summary_of(p)),
+            }
         })
+        .chain(purged)
+        .filter(past_cursor)
         .collect();
+    changes.sort_by_key(|c| (c.changed_at, c.id));
+    changes.truncate(limit);
+    changes
+}
 
-    Ok(Json(posts))
+fn summary_of(p: Post) -> PostSummary {
+    PostSummary {
+        id: p.id,
+        slug: p.slug,
+        title: p.title,
+        excerpt: p.excerpt,
+        published_at: p.published_at.unwrap_or(p.created_at),
+        reading_time: calculate_reading_time(&p.body),
+        author: p.author,
+        tags: p.tags,
+    }
 }
 
-/// Get posts by tag
+/// Drop posts that aren't live and sanitize the snippets the database highlighted
+fn search_hits(hits: Vec<(Post, Option<String>)>) -> Vec<SearchHit> {
+    hits.into_iter()
+        .filter(|(p, _)| p.is_live())
+        .map(|(p, snippet)| SearchHit {
+            post: summary_of(p),
+            snippet: snippet.map(|s| sanitize_html(&s, &RenderOptions::default())),
+        })
+        .collect()
+}
+
+/// Get published posts with a tag, paginated.
+/// An unknown tag is a 404; a tag with no published posts is an empty page.
 pub async fn get_posts_by_tag(
-    State(state): State<AppState>,
+    State(state): State<Arc<AppState>>,
     Path(tag_name): Path<String>,
-) -> Result<Json<Vec<PostSummary>>, AppError> {
-    let posts = db::get_posts_by_tag(&state.pool, &tag_name).await?;
-    Ok(Json(posts))
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<Page<PostSummary>>, AppError> {
+    require_tag(&state.pool, &tag_name).await?;
+
+    let (limit, offset) = params.limit_offset();
+    let (posts, total) = db::get_posts_by_tag(&state.pool, &tag_name, limit, offset).await?;
+
+    Ok(Json(Page::new(posts, total, &params)))
 }
 
+/// Most recent posts included in a tag's feed
+const TAG_FEED_SIZE: i64 = 20;
+
+/// RSS feed of a tag's most recent published posts
+pub async fn get_tag_feed(
+    State(state): State<Arc<AppState>>,
+    Path(tag_name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let tag = require_tag(&state.pool, &tag_name).await?;
+    let (posts, _) = db::get_posts_by_tag(&state.pool, &tag.name, TAG_FEED_SIZE, 0).await?;
+
+    let xml = build_tag_feed(state.frontend_url.as_deref().unwrap_or(""), &tag.name, &posts);
+    Ok(([(header::CONTENT_TYPE, "application/rss+xml")], xml))
+}
+
+async fn require_tag(pool: &sqlx::PgPool, name: &str) -> Result<Tag, AppError> {
+    db::get_tag_by_name(pool, name)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Tag '{}' not found", name)))
+}
+
+fn build_tag_feed(base: &str, tag: &str, posts: &[PostSummary]) -> String {
+    let base = base.trim_end_matches('/');
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str(&format!(
+        "  <title>Posts tagged {}</title>\n  <link>{}/tags/{}</link>\n  <description>Latest posts tagged {}</description>\n",
+        escape_xml(tag),
+        escape_xml(base),
+        escape_xml(tag),
+        escape_xml(tag)
+    ));
+    for post in posts {
+        let link = format!("{}/blogs/{}", base, post.slug);
+        xml.push_str(&format!(
+            "  <item>\n    <title>{}</title>\n    <link>{}</link>\n    <guid>{}</guid>\n    <pubDate>{}</pubDate>\n    <description>{}</description>\n  </item>\n",
+            escape_xml(&post.title),
+            escape_xml(&link),
+            escape_xml(&link),
+            post.published_at.to_rfc2822(),
+            escape_xml(&post.excerpt)
+        ));
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(excerpt: &str, body: &str) -> Post {
+        Post {
+            id: Uuid::new_v4(),
+            slug: "hello-world".to_string(),
+            title: "Hello World".to_string(),
+            excerpt: excerpt.to_string(),
+            body: body.to_string(),
+            published: true,
+            published_at: Some(Utc::now()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            author_id: Uuid::new_v4(),
+            trusted_html: false,
+            noindex: false,
+            publish_at: None,
+            deleted_at: None,
+            author: AuthorInfo {
+                id: Uuid::new_v4(),
+                username: "admin".to_string(),
+            },
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_post_meta_image_description_and_urls() {
+        let meta = build_post_meta(
+            post("", "# Hello World\n\nIntro text.\n\n![[Hero Shot.png]]"),
+            Some("https://blog.example/"),
+        );
+        assert_eq!(meta.canonical_url, "https://blog.example/blogs/hello-world");
+        assert_eq!(meta.image, "https://blog.example/api/assets/hero-shot-png");
+        assert_eq!(meta.description, "Intro text.");
+
+        let long = "word ".repeat(100);
+        let meta = build_post_meta(post(&long, "No images"), None);
+        assert_eq!(meta.image, DEFAULT_OG_IMAGE);
+        assert!(meta.description.chars().count() <= 203);
+        assert_eq!(meta.robots, "index, follow");
+    }
+
+    #[test]
+    fn test_noindex_posts_are_left_out_of_sitemap() {
+        let entry = |slug: &str, noindex| SitemapEntry {
+            slug: slug.to_string(),
+            updated_at: Utc::now(),
+            noindex,
+        };
+        let xml = build_sitemap(
+            "https://blog.example/",
+            &[entry("indexed", false), entry("hidden", true)],
+        );
+        assert!(xml.contains("<loc>https://blog.example/blogs/indexed</loc>"));
+        assert!(!xml.contains("hidden"));
 
+        let mut hidden = post("Excerpt", "Body");
+        hidden.noindex = true;
+        assert!(hidden.is_live());
+        assert_eq!(build_post_meta(hidden, None).robots, "noindex");
+    }
+
+    #[sqlx::test]
+    async fn test_noindex_post_stays_in_list_and_feed(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        use crate::models::{CreatePostRequest, CreateTagRequest, Role};
+        use shuttle_axum::axum::body::to_bytes;
+
+        let author = db::create_user(&pool, "writer", "not-a-real-hash", Role::Admin).await?.id;
+        let rust = CreateTagRequest {
+            name: "rust".to_string(),
+            color: "peach".to_string(),
+        };
+        let rust = db::create_tag(&pool, rust).await?.id;
+        let req = CreatePostRequest {
+            slug: "unlisted-notes".to_string(),
+            title: "Unlisted notes".to_string(),
+            excerpt: String::new(),
+            body: "# Unlisted notes\n\nReadable, just not indexed.".to_string(),
+            tags: vec![rust],
+            published: true,
+            trusted_html: false,
+            noindex: true,
+            publish_at: None,
+        };
+        db::create_post(&pool, req, author).await?;
+        let state = Arc::new(AppState::new(pool.clone(), "test-secret".to_string()));
+
+        let list = list_posts(State(state.clone()), HeaderMap::new()).await?;
+        let list = to_bytes(list.into_body(), usize::MAX).await?;
+        let list: Vec<PostSummary> = serde_json::from_slice(&list)?;
+        assert!(list.iter().any(|p| p.slug == "unlisted-notes"));
+
+        let feed = get_tag_feed(State(state.clone()), Path("rust".to_string())).await?;
+        let feed = to_bytes(feed.into_response().into_body(), usize::MAX).await?;
+        assert!(String::from_utf8(feed.to_vec())?.contains("/blogs/unlisted-notes"));
+
+        let sitemap = get_sitemap(State(state)).await?;
+        let sitemap = to_bytes(sitemap.into_response().into_body(), usize::MAX).await?;
+        assert!(!String::from_utf8(sitemap.to_vec())?.contains("unlisted-notes"));
+        Ok(())
+    }
+    #[test]
+    fn test_tag_feed_lists_posts_with_escaped_text() {
+        let summary = PostSummary {
+            id: Uuid::new_v4(),
+            slug: "types-and-you".to_string(),
+            title: "Types & You".to_string(),
+            excerpt: "Why <T> matters".to_string(),
+            published_at: Utc::now(),
+            reading_time: "1 min read".to_string(),
+            author: Default::default(),
+            tags: vec![],
+        };
+        let xml = build_tag_feed("https://blog.example/", "rust", &[summary]);
+        assert!(xml.contains("<title>Posts tagged rust</title>"));
+        assert!(xml.contains("<link>https://blog.example/blogs/types-and-you</link>"));
+        assert!(xml.contains("<title>Types &amp; You</title>"));
+        assert!(xml.contains("Why &lt;T&gt; matters"));
+        assert_eq!(xml.matches("<item>").count(), 1);
+
+        let empty = build_tag_feed("https://blog.example", "rust", &[]);
+        assert!(empty.contains("<channel>") && !empty.contains("<item>"));
+    }
+
+    #[test]
+    fn test_search_tag_filter_ignores_blank() {
+        let params = |tag: Option<&str>| SearchParams {
+            q: Some("async".to_string()),
+            tag: tag.map(str::to_string),
+        };
+        assert_eq!(params(Some(" rust ")).tag_filter(), Some("rust"));
+        assert_eq!(params(Some("  ")).tag_filter(), None);
+        assert_eq!(params(None).tag_filter(), None);
+    }
+
+    #[test]
+    fn test_search_hits_skip_unpublished_and_sanitize_snippets() {
+        let mut draft = post("Excerpt", "Body");
+        draft.published = false;
+        let hits = search_hits(vec![
+            (post("Excerpt", "Body"), Some("<mark>match</mark><script>x</script>".to_string())),
+            (draft, None),
+        ]);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].snippet.as_deref(), Some("<mark>match</mark>"));
+    }
+
+    #[test]
+    fn test_views_dedup_per_client_and_post() {
+        use crate::visitor::HitWindow;
+        use std::time::Duration;
+
+        let views = HitWindow::new(Duration::from_secs(3600), 1);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(views.record(&view_key(first, Some(ip), "salt")));
+        assert!(!views.record(&view_key(first, Some(ip), "salt")));
+        assert!(views.record(&view_key(second, Some(ip), "salt")));
+        assert!(!view_key(first, Some(ip), "salt").contains("203.0.113.7"));
+
+        let clamp = |limit| PopularParams { limit }.limit();
+        assert_eq!(clamp(None), 10);
+        assert_eq!(clamp(Some(0)), 1);
+        assert_eq!(clamp(Some(500)), 50);
+    }
+
+    #[test]
+    fn test_related_posts_fall_back_to_recent() {
+        let summary = |slug: &str| PostSummary {
+            id: Uuid::new_v4(),
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            excerpt: String::new(),
+            published_at: Utc::now(),
+            reading_time: "1 min read".to_string(),
+            author: Default::default(),
+            tags: vec![],
+        };
+        let current = summary("current");
+        let tagged = summary("tagged");
+        let recent = vec![
+            current.clone(),
+            tagged.clone(),
+            summary("newest"),
+            summary("newer"),
+            summary("new"),
+            summary("old"),
+        ];
+
+        let related = fill_related(vec![tagged], recent, current.id, 4);
+        let slugs: Vec<&str> = related.iter().map(|p| p.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["tagged", "newest", "newer", "new"]);
+
+        // Unique tags: the section is filled entirely from recent posts
+        let related = fill_related(vec![], vec![summary("a"), summary("b")], current.id, 4);
+        assert_eq!(related.len(), 2);
+    }
+
+    #[test]
+    fn test_changed_since_reports_newer_changes_only() {
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let mut old = post("Old", "Body");
+        old.slug = "old".to_string();
+        old.updated_at = since - chrono::Duration::hours(1);
+        let mut edited = post("Edited", "Body");
+        edited.slug = "edited".to_string();
+        let mut trashed = post("Trashed", "Body");
+        trashed.slug = "trashed".to_string();
+        trashed.updated_at = since - chrono::Duration::days(1);
+        trashed.deleted_at = Some(Utc::now());
+
+        let purged = PostChange {
+            id: Uuid::new_v4(),
+            slug: "purged".to_string(),
+            changed_at: Utc::now() + chrono::Duration::seconds(1),
+            deleted: true,
+            post: None,
+        };
+
+        let changes = changes_since(vec![edited, old, trashed], vec![purged], (since, None), 10);
+        let slugs: Vec<&str> = changes.iter().map(|c| c.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["edited", "trashed", "purged"]);
+        assert!(!changes[0].deleted && changes[0].post.is_some());
+        assert!(changes[1].deleted && changes[1].post.is_none());
+        assert!(changes[2].deleted);
+
+        // The limit cuts after the oldest changes
+        let first = changes_since(vec![], changes.clone(), (since, None), 2);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].slug, "trashed");
+    }
+
+    #[test]
+    fn test_changed_since_resumes_within_a_shared_timestamp() {
+        let at = Utc::now() - chrono::Duration::hours(1);
+        let mut ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        ids.sort();
+        let change = |id: Uuid| PostChange {
+            id,
+            slug: id.to_string(),
+            changed_at: at,
+            deleted: true,
+            post: None,
+        };
+        let all: Vec<PostChange> = ids.iter().copied().map(change).collect();
+
+        // A page that ended on the first of three changes at `at` picks up the other two
+        let rest = changes_since(vec![], all.clone(), (at, Some(ids[0])), 10);
+        let rest: Vec<Uuid> = rest.iter().map(|c| c.id).collect();
+        assert_eq!(rest, vec![ids[1], ids[2]]);
+
+        // Without an id, `ts` alone is exclusive
+        assert!(changes_since(vec![], all, (at, None), 10).is_empty());
+    }
+
+    #[test]
+    fn test_renamed_slug_redirect_location() {
+        assert_eq!(
+            redirect_location("/api/posts/old-name", None, "new-name"),
+            "/api/posts/new-name"
+        );
+        assert_eq!(
+            redirect_location("/api/v1/posts/old-name", Some("max_depth=2"), "new-name"),
+            "/api/v1/posts/new-name?max_depth=2"
+        );
+    }
+
+    #[test]
+    fn test_trashed_post_is_gone_unknown_slug_is_not_found() {
+        use shuttle_axum::axum::http::StatusCode;
+
+        let mut trashed = post("Excerpt", "Body");
+        trashed.deleted_at = Some(Utc::now());
+        let gone = missing_post_error("hello-world", Some(&trashed));
+        assert!(matches!(gone, AppError::Gone(_)));
+        assert_eq!(gone.into_response().status(), StatusCode::GONE);
+
+        let missing = missing_post_error("nope", None);
+        assert_eq!(missing.into_response().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[sqlx::test]
+    async fn test_related_posts_loosen_before_recent(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        use crate::models::{CreatePostRequest, CreateTagRequest, Role};
+
+        let author = db::create_user(&pool, "writer", "not-a-real-hash", Role::Admin).await?.id;
+        let tag = |name: &str| CreateTagRequest {
+            name: name.to_string(),
+            color: "blue".to_string(),
+        };
+        let a = db::create_tag(&pool, tag("a")).await?.id;
+        let b = db::create_tag(&pool, tag("b")).await?.id;
+        let now = Utc::now();
+        let req = |slug: &str, tags: Vec<Uuid>, hours_ago: i64| CreatePostRequest {
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            excerpt: String::new(),
+            body: format!("# {}\n\nBody text for {}.", slug, slug),
+            tags,
+            published: true,
+            trusted_html: false,
+            noindex: false,
+            publish_at: Some(now - chrono::Duration::hours(hours_ago)),
+        };
+        let current = db::create_post(&pool, req("current", vec![a, b], 4), author).await?;
+        db::create_post(&pool, req("both", vec![a, b], 3), author).await?;
+        db::create_post(&pool, req("one", vec![a], 2), author).await?;
+        db::create_post(&pool, req("none", vec![], 1), author).await?;
+
+        // Only "both" meets the threshold; "one" shares a tag, so it beats the newer "none"
+        let mut state = AppState::new(pool.clone(), "test-secret".to_string());
+        state.related_min_common_tags = 2;
+        let related = get_related_posts(&state, current.id).await?;
+        let slugs: Vec<&str> = related.iter().map(|p| p.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["both", "one", "none"]);
+        Ok(())
+    }
+}
@@ -1,9 +1,16 @@
-use shuttle_axum::axum::extract::{Path, State};
+use serde::Deserialize;
+use shuttle_axum::axum::extract::{Path, Query, State};
 use shuttle_axum::axum::Json;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::{auth::AuthUser, db, error::AppError, models::{CreateTagRequest, Tag}, state::AppState};
+use crate::{
+    auth::AuthUser,
+    db,
+    error::AppError,
+    models::{CatppuccinColor, CreateTagRequest, Tag},
+    state::AppState,
+};
 
 /// List all tags
 pub async fn list_tags(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Tag>>, AppError> {
@@ -25,14 +32,142 @@ pub struct TagStats {
     pub post_count: usize,
 }
 
-/// Create a new tag (admin only)
-pub async fn create_tag(
-    _auth: AuthUser,
+/// Query parameters for tag autocomplete
+#[derive(Deserialize)]
+pub struct TagSearchParams {
+    #[serde(default)]
+    pub q: String,
+    pub limit: Option<u32>,
+}
+
+impl TagSearchParams {
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 50;
+
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT).clamp(1, Self::MAX_LIMIT) as i64
+    }
+}
+
+/// Tag picker autocomplete: tags containing `q`, prefix matches and busy tags first
+pub async fn search_tags(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TagSearchParams>,
+) -> Result<Json<Vec<TagStats>>, AppError> {
+    let query = normalize_tag_name(&params.q);
+    let tags = db::search_tags(&state.pool, &query, params.limit()).await?;
+    Ok(Json(tags))
+}
+
+/// Get all tags arranged by their `/`-separated segments
+pub async fn get_tag_tree(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<CreateTagRequest>,
-) -> Result<Json<Tag>, AppError> {
-    let tag = db::create_tag(&state.pool, req).await?;
-    Ok(Json(tag))
+) -> Result<Json<Vec<TagNode>>, AppError> {
+    let tags = db::list_tags(&state.pool).await?;
+    Ok(Json(build_tag_tree(tags)))
+}
+
+/// One segment of the nested tag hierarchy
+#[derive(Debug, serde::Serialize)]
+pub struct TagNode {
+    pub name: String, // this segment, e.g. "rust"
+    pub path: String, // full tag name, e.g. "programming/rust"
+    pub tag: Option<Tag>, // None for parents that only exist through their children
+    pub children: Vec<TagNode>,
+}
+
+/// Arrange tags into a tree; children are ordered by name
+pub fn build_tag_tree(tags: Vec<Tag>) -> Vec<TagNode> {
+    let mut roots: Vec<TagNode> = Vec::new();
+
+    for tag in tags {
+        let segments: Vec<&str> = tag.name.split('/').filter(|s| !s.is_empty()).collect();
+        if segments.is_empty() {
+            continue;
+        }
+
+        let mut level = &mut roots;
+        for depth in 0..segments.len() {
+            let path = segments[..=depth].join("/");
+            let index = match level.iter().position(|n| n.path == path) {
+                Some(index) => index,
+                None => {
+                    level.push(TagNode {
+                        name: segments[depth].to_string(),
+                        path,
+                        tag: None,
+                        children: Vec::new(),
+                    });
+                    level.len() - 1
+                }
+            };
+
+            if depth == segments.len() - 1 {
+                level[index].tag = Some(tag.clone());
+            }
+            level = &mut level[index].children;
+        }
+    }
+
+    sort_tree(&mut roots);
+    roots
+}
+
+fn sort_tree(nodes: &mut [TagNode]) {
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    for node in nodes {
+        sort_tree(&mut node.children);
+    }
+}
+
+/// Color given to tags first seen in imported or front-matter content
+pub(crate) const IMPORTED_TAG_COLOR: &str = "mauve";
+
+/// Id of the tag called `name`, creating it if this is its first use
+pub(crate) async fn ensure_tag(state: &AppState, name: &str) -> Result<Uuid, AppError> {
+    if let Some(tag) = db::get_tag_by_name(&state.pool, name).await? {
+        return Ok(tag.id);
+    }
+
+    let req = CreateTagRequest {
+        name: name.to_string(),
+        color: IMPORTED_TAG_COLOR.to_string(),
+    };
+    Ok(db::create_tag(&state.pool, req).await?.id)
+}
+
+/// Canonical form of a submitted tag name, so "Rust", " rust " and "rust" are one tag
+pub(crate) fn normalize_tag_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Validate a tag create/update request; shared by every tag write path
+pub(crate) fn validate_tag_request(req: &CreateTagRequest) -> Result<(), AppError> {
+    if !is_valid_tag_name(&req.name) {
+        return Err(AppError::BadRequest(
+            "Invalid tag name. Use lowercase letters, numbers, and hyphens, with '/' between nested segments.".to_string(),
+        ));
+    }
+
+    // Colors outside the Catppuccin palette break the frontend theme
+    if CatppuccinColor::from_name(&req.color).is_none() {
+        return Err(AppError::BadRequest(
+            "Invalid color. Must be a valid Catppuccin color name.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Tag names are lowercase segments joined by `/` for nesting (`programming/rust`)
+pub(crate) fn is_valid_tag_name(name: &str) -> bool {
+    name.len() <= 100
+        && name.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        })
 }
 
 /// Delete a tag (admin only)
@@ -44,5 +179,78 @@ pub async fn delete_tag(
     let id = Uuid::parse_str(&tag_id)
         .map_err(|_| AppError::BadRequest("Invalid tag ID".to_string()))?;
     db::delete_tag(&state.pool, id).await?;
+    state.render_cache.clear();
     Ok(Json(()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::extract_tags;
+    use chrono::Utc;
+
+    fn tag(name: &str) -> Tag {
+        Tag {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            color: "blue".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_tag_search_limit_is_clamped() {
+        let limit = |limit| {
+            TagSearchParams {
+                q: String::new(),
+                limit,
+            }
+            .limit()
+        };
+        assert_eq!(limit(None), 10);
+        assert_eq!(limit(Some(0)), 1);
+        assert_eq!(limit(Some(500)), 50);
+    }
+
+    #[test]
+    fn test_tag_request_validation() {
+        let req = |name: &str, color: &str| CreateTagRequest {
+            name: name.to_string(),
+            color: color.to_string(),
+        };
+        assert!(validate_tag_request(&req("programming/rust", "blue")).is_ok());
+        assert!(validate_tag_request(&req("rust", "#ff0000")).is_err());
+        assert!(validate_tag_request(&req("programming/", "blue")).is_err());
+        assert!(validate_tag_request(&req("/rust", "blue")).is_err());
+        assert!(validate_tag_request(&req("a//b", "blue")).is_err());
+
+        // Case and surrounding whitespace are normalized away before validation
+        let name = normalize_tag_name("  Programming/Rust ");
+        assert_eq!(name, "programming/rust");
+        assert!(validate_tag_request(&req(&name, "blue")).is_ok());
+    }
+
+    #[test]
+    fn test_nested_tag_appears_under_parent() {
+        let names = extract_tags("Learning #programming/rust this week");
+        assert_eq!(names, vec!["programming/rust".to_string()]);
+
+        let tree = build_tag_tree(vec![tag("rust-free"), tag(&names[0]), tag("programming")]);
+        assert_eq!(tree.len(), 2);
+
+        let programming = &tree[0];
+        assert_eq!(programming.name, "programming");
+        assert!(programming.tag.is_some());
+        assert_eq!(programming.children.len(), 1);
+        assert_eq!(programming.children[0].name, "rust");
+        assert_eq!(programming.children[0].path, "programming/rust");
+    }
+
+    #[test]
+    fn test_missing_parent_is_placeholder() {
+        let tree = build_tag_tree(vec![tag("lang/go")]);
+        assert_eq!(tree[0].path, "lang");
+        assert!(tree[0].tag.is_none());
+        assert!(tree[0].children[0].tag.is_some());
+    }
+}
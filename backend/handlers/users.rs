@@ -0,0 +1,82 @@
+use shuttle_axum::axum::{extract::State, http::StatusCode, Json};
+use std::sync::Arc;
+
+use crate::{
+    auth::{hash_password, AuthUser},
+    db,
+    error::AppError,
+    handlers::auth::MIN_PASSWORD_LENGTH,
+    models::{CreateUserRequest, UserInfo},
+    state::AppState,
+};
+
+/// Create an additional user (admin only).
+/// - Usernames are 3–32 characters of letters, digits, `_` or `-`
+/// - Passwords are stored as Argon2 hashes
+pub async fn create_user(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateUserRequest>,
+) -> Result<(StatusCode, Json<UserInfo>), AppError> {
+    user.require_admin()?;
+
+    let username = req.username.trim();
+    if !is_valid_username(username) {
+        return Err(AppError::BadRequest(
+            "Username must be 3-32 characters of letters, digits, '_' or '-'".to_string(),
+        ));
+    }
+
+    if req.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    if db::get_user_by_username(&state.pool, username).await?.is_some() {
+        return Err(AppError::Conflict(format!(
+            "A user named '{}' already exists",
+            username
+        )));
+    }
+
+    let password_hash = hash_password(&req.password)?;
+    let created = db::create_user(&state.pool, username, &password_hash, req.role).await?;
+
+    tracing::info!(
+        "user '{}' ({}) created by {}",
+        created.username,
+        created.role.as_str(),
+        user.username
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(UserInfo {
+            id: created.id,
+            username: created.username,
+            role: created.role,
+        }),
+    ))
+}
+
+fn is_valid_username(username: &str) -> bool {
+    (3..=32).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_username_validation() {
+        assert!(is_valid_username("co-author_2"));
+        assert!(!is_valid_username("ab"));
+        assert!(!is_valid_username("has space"));
+        assert!(!is_valid_username(&"a".repeat(33)));
+    }
+}
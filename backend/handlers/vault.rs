@@ -0,0 +1,316 @@
+use serde::Deserialize;
+use shuttle_axum::axum::{
+    extract::{Multipart, Query, State},
+    Json,
+};
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    db,
+    error::AppError,
+    handlers::{
+        admin::is_valid_slug,
+        tags::{ensure_tag, is_valid_tag_name},
+    },
+    markdown::{build_toc, extract_excerpt, extract_tags, slugify, MAX_MARKDOWN_BYTES},
+    models::{CreatePostRequest, UpdatePostRequest, VaultFileResult, VaultFileStatus},
+    state::AppState,
+};
+
+/// Largest vault zip accepted by the import route
+pub const MAX_VAULT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Most markdown files imported from one vault
+const MAX_VAULT_FILES: usize = 1000;
+
+/// Total markdown inflated from one vault. Entries are read into memory before any
+/// post is written, so this (not the zip size) bounds the import's memory use.
+const MAX_VAULT_INFLATED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Time allowed for a whole vault import; several queries run per file
+pub const VAULT_IMPORT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+#[derive(Deserialize)]
+pub struct VaultImportParams {
+    /// Replace posts whose slug already exists instead of skipping them
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// A markdown file from the vault, ready to become a post
+#[derive(Debug)]
+struct VaultPost {
+    slug: String,
+    title: String,
+    body: String,
+    excerpt: String,
+    tags: Vec<String>,
+}
+
+/// Import a zipped Obsidian vault (multipart field `file`) as draft posts, one per `.md` file
+pub async fn import_obsidian(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(params): Query<VaultImportParams>,
+    mut multipart: Multipart,
+) -> Result<Json<Vec<VaultFileResult>>, AppError> {
+    let mut archive = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Invalid multipart body: {}", e)))?
+    {
+        if field.name() == Some("file") {
+            let bytes = field
+                .bytes()
+                .await
+                .map_err(|e| AppError::BadRequest(format!("Failed to read upload: {}", e)))?;
+            archive = Some(bytes);
+        }
+    }
+    let archive = archive
+        .ok_or_else(|| AppError::BadRequest("Missing 'file' field with the vault zip".to_string()))?;
+
+    let files = read_vault(&archive)?;
+    let mut results = Vec::with_capacity(files.len());
+    for (path, content) in files {
+        let post = match content.and_then(|content| vault_post(&path, content)) {
+            Ok(post) => post,
+            Err(error) => {
+                results.push(VaultFileResult::failed(path, None, error));
+                continue;
+            }
+        };
+
+        let slug = post.slug.clone();
+        let result = match import_vault_post(&state, &user, post, params.overwrite).await {
+            Ok(status) => VaultFileResult {
+                file: path,
+                slug: Some(slug),
+                status,
+                error: None,
+            },
+            Err(e) => VaultFileResult::failed(path, Some(slug), e.to_string()),
+        };
+        results.push(result);
+    }
+
+    tracing::info!(
+        "Vault import by {}: {} files, {} failed",
+        user.username,
+        results.len(),
+        results
+            .iter()
+            .filter(|r| r.status == VaultFileStatus::Failed)
+            .count()
+    );
+
+    Ok(Json(results))
+}
+
+/// Create (or, with `overwrite`, replace) the post for one vault file
+async fn import_vault_post(
+    state: &AppState,
+    user: &AuthUser,
+    post: VaultPost,
+    overwrite: bool,
+) -> Result<VaultFileStatus, AppError> {
+    let existing = db::get_post_by_slug_any(&state.pool, &post.slug).await?;
+    if existing.is_some() && !overwrite {
+        return Ok(VaultFileStatus::Skipped);
+    }
+
+    let mut tag_ids = Vec::with_capacity(post.tags.len());
+    for name in &post.tags {
+        tag_ids.push(ensure_tag(state, name).await?);
+    }
+
+    match existing {
+        Some(existing) => {
+            if !user.can_manage(existing.author_id) {
+                return Err(AppError::Forbidden(
+                    "You don't have permission to update this post".to_string(),
+                ));
+            }
+            let req = UpdatePostRequest {
+                title: Some(post.title),
+                excerpt: Some(post.excerpt),
+                body: Some(post.body),
+                tags: Some(tag_ids),
+                ..Default::default()
+            };
+            db::update_post(&state.pool, existing.id, req).await?;
+            state.render_cache.invalidate(existing.id);
+            Ok(VaultFileStatus::Updated)
+        }
+        None => {
+            let req = CreatePostRequest {
+                slug: post.slug,
+                title: post.title,
+                excerpt: post.excerpt,
+                body: post.body,
+                tags: tag_ids,
+                published: false,
+                trusted_html: false,
+                noindex: false,
+                publish_at: None,
+            };
+            db::create_post(&state.pool, req, user.user_id).await?;
+            Ok(VaultFileStatus::Created)
+        }
+    }
+}
+
+/// Markdown files in the zip, by path. Files that can't be read are kept as errors
+/// so they show up in the report; Obsidian's own folders and other files are ignored.
+fn read_vault(bytes: &[u8]) -> Result<Vec<(String, Result<String, String>)>, AppError> {
+    read_vault_within(bytes, MAX_VAULT_FILES, MAX_VAULT_INFLATED_BYTES)
+}
+
+/// `read_vault` with explicit limits; a vault over either is rejected outright
+fn read_vault_within(
+    bytes: &[u8],
+    max_files: usize,
+    max_inflated: usize,
+) -> Result<Vec<(String, Result<String, String>)>, AppError> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| AppError::BadRequest(format!("Not a valid zip archive: {}", e)))?;
+
+    let mut files = Vec::new();
+    let mut inflated = 0;
+    for i in 0..zip.len() {
+        let file = zip
+            .by_index(i)
+            .map_err(|e| AppError::BadRequest(format!("Corrupt zip entry: {}", e)))?;
+        let path = file.name().to_string();
+        let hidden = path.split('/').any(|part| part.starts_with('.'));
+        if file.is_dir() || hidden || !path.to_lowercase().ends_with(".md") {
+            continue;
+        }
+        if files.len() == max_files {
+            return Err(AppError::BadRequest(format!(
+                "Vault has more than {} markdown files",
+                max_files
+            )));
+        }
+
+        // Read one byte past the limit: oversized entries are caught without inflating them fully
+        let mut raw = Vec::new();
+        let read = file.take(MAX_MARKDOWN_BYTES as u64 + 1).read_to_end(&mut raw);
+        inflated += raw.len();
+        if inflated > max_inflated {
+            return Err(AppError::BadRequest(format!(
+                "Vault unpacks to more than {} MB of markdown",
+                max_inflated / (1024 * 1024)
+            )));
+        }
+        let content = match read {
+            Err(e) => Err(format!("Failed to read file: {}", e)),
+            Ok(_) if raw.len() > MAX_MARKDOWN_BYTES => {
+                Err(format!("File is larger than {} KB", MAX_MARKDOWN_BYTES / 1024))
+            }
+            Ok(_) => String::from_utf8(raw).map_err(|_| "File is not valid UTF-8".to_string()),
+        };
+        files.push((path, content));
+    }
+
+    Ok(files)
+}
+
+/// Post fields for a vault file: slug from the file name, title from the first H1
+/// (falling back to the file name), tags and excerpt from the body
+fn vault_post(path: &str, body: String) -> Result<VaultPost, String> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let stem = &file_name[..file_name.len() - ".md".len()];
+
+    let slug = slugify(stem);
+    if slug.is_empty() || !is_valid_slug(&slug) {
+        return Err(format!("Could not derive a slug from '{}'", file_name));
+    }
+
+    let title = build_toc(&body, 1)
+        .into_iter()
+        .next()
+        .map(|heading| heading.text)
+        .filter(|text| !text.trim().is_empty())
+        .unwrap_or_else(|| stem.to_string());
+
+    // Obsidian tags may be mixed case; only names valid here are attached
+    let mut tags: Vec<String> = extract_tags(&body)
+        .into_iter()
+        .map(|tag| tag.to_lowercase())
+        .filter(|tag| is_valid_tag_name(tag))
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    Ok(VaultPost {
+        slug,
+        title,
+        excerpt: extract_excerpt(&body, 200),
+        tags,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn vault(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_vault_reads_markdown_files_only() {
+        let bytes = vault(&[
+            ("notes/Rust Ownership.md", b"# Ownership\n\nBorrowing rules."),
+            (".obsidian/workspace.md", b"ignored"),
+            ("attachments/diagram.png", b"\x89PNG"),
+            ("broken.md", b"\xff\xfe"),
+        ]);
+
+        let files = read_vault(&bytes).unwrap();
+        let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["notes/Rust Ownership.md", "broken.md"]);
+        assert!(files[0].1.is_ok());
+        assert!(files[1].1.is_err());
+        assert!(read_vault(b"not a zip").is_err());
+    }
+
+    #[test]
+    fn test_vault_limits_file_count_and_inflated_size() {
+        let bytes = vault(&[("a.md", b"aaaa"), ("b.md", b"bbbb"), ("c.png", b"png")]);
+        assert!(read_vault_within(&bytes, 2, 1024).is_ok());
+        assert!(read_vault_within(&bytes, 1, 1024).is_err());
+        assert!(read_vault_within(&bytes, 2, 7).is_err());
+    }
+
+    #[test]
+    fn test_vault_post_fields() {
+        let post = vault_post(
+            "notes/Rust Ownership.md",
+            "# Who Owns What\n\nBorrowing in #Rust and #rust/borrowck.".to_string(),
+        )
+        .unwrap();
+        assert_eq!(post.slug, "rust-ownership");
+        assert_eq!(post.title, "Who Owns What");
+        assert_eq!(post.tags, vec!["rust", "rust/borrowck"]);
+        assert!(post.excerpt.contains("Borrowing"));
+
+        // Without an H1 the file name is the title
+        let untitled = vault_post("Daily Note.md", "Just text".to_string()).unwrap();
+        assert_eq!(untitled.title, "Daily Note");
+
+        assert!(vault_post("!!!.md", "Body".to_string()).is_err());
+    }
+}
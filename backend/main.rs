@@ -1,16 +1,21 @@
 use shuttle_axum::axum::{
-    extract::{Query, State},
-    http::{HeaderValue, Method},
+    body::Body,
+    extract::{DefaultBodyLimit, State},
+    http::{header, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Uri},
+    middleware::{self, Next},
     routing::{delete, get, post, put},
     Json, Router,
 };
-use serde::Deserialize;
-use shuttle_axum::{ShuttleAxum, AxumService};
 use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use tower::ServiceBuilder;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowHeaders, Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
+use tracing::Span;
 
 // Bring our modules into scope
 mod auth;
@@ -19,7 +24,11 @@ mod error;
 mod handlers;
 mod markdown;
 mod models;
+mod ratelimit;
+mod render_cache;
 mod state;
+mod visitor;
+mod webhook;
 
 // Shared application state handle
 type SharedState = Arc<state::AppState>;
@@ -29,6 +38,11 @@ async fn health() -> &'static str {
     "ok"
 }
 
+/// Unmatched routes get the same JSON error body as any other 404
+async fn not_found(uri: Uri) -> error::AppError {
+    error::AppError::NotFound(format!("No route for {}", uri.path()))
+}
+
 // Optional: simple probe endpoint to sanity check DB connectivity
 async fn db_probe(
     State(state): State<SharedState>,
@@ -37,43 +51,280 @@ async fn db_probe(
     Ok(Json(serde_json::json!({ "db": row.0 })))
 }
 
-// Public search handler (?q=) mapping DB rows to summaries
-#[derive(Deserialize)]
-struct SearchParams {
-    q: Option<String>,
+// How often the scheduler checks for posts whose publish_at has passed
+const SCHEDULER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Background task promoting scheduled drafts once their publish_at passes
+async fn publish_scheduler(state: SharedState) {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+    loop {
+        interval.tick().await;
+        match db::publish_scheduled_posts(&state.pool).await {
+            Ok(slugs) => {
+                for slug in slugs {
+                    tracing::info!("Scheduled post published: {}", slug);
+                    match db::get_post_by_slug_any(&state.pool, &slug).await {
+                        Ok(Some(post)) => state.notify(webhook::WebhookEvent::Published, &post),
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("Could not load {} for its webhook: {}", slug, e),
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Scheduled publishing failed: {}", e),
+        }
+    }
 }
 
-async fn public_search(
-    State(state): State<SharedState>,
-    Query(params): Query<SearchParams>,
-) -> Result<Json<Vec<models::PostSummary>>, error::AppError> {
-    let q = params.q.unwrap_or_default();
-    if q.trim().is_empty() {
-        return Ok(Json(vec![]));
-    }
-
-    let posts = db::search_posts(&state.pool, &q).await?;
-    let summaries: Vec<models::PostSummary> = posts
-        .into_iter()
-        .filter(|p| p.published)
-        .map(|p| models::PostSummary {
-            id: p.id,
-            slug: p.slug,
-            title: p.title,
-            excerpt: p.excerpt,
-            published_at: p.published_at.unwrap_or(p.created_at),
-            reading_time: markdown::calculate_reading_time(&p.body),
-            tags: p.tags,
-        })
-        .collect();
+// Build the API router.
+// - Public routes under /api, admin routes under /api/sayyidati
+// - /api/v1 mirrors both so clients can pin a version ahead of a future /api/v2
+fn build_router(app_state: SharedState) -> Router {
+    let public_api = Router::new()
+        .route("/health", get(health))
+        .route("/db-probe", get(db_probe))
+        // Homepage
+        .route("/home", get(handlers::home::get_home))
+        // Posts
+        .route("/posts", get(handlers::posts::list_posts))
+        .route("/posts/popular", get(handlers::posts::list_popular_posts))
+        .route("/posts/changed-since", get(handlers::posts::list_changed_since))
+        .route("/posts/{slug}", get(handlers::posts::get_post))
+        .route("/posts/{slug}/backlinks", get(handlers::posts::get_backlinks))
+        .route("/posts/{slug}/meta", get(handlers::posts::get_post_meta))
+        .route("/sitemap.xml", get(handlers::posts::get_sitemap))
+        // Tags
+        .route("/tags", get(handlers::tags::list_tags).post(handlers::admin::create_tag))
+        .route("/tags/stats", get(handlers::tags::get_tag_stats))
+        .route("/tags/search", get(handlers::tags::search_tags))
+        .route("/tags/tree", get(handlers::tags::get_tag_tree))
+        .route("/tags/{name}/posts", get(handlers::posts::get_posts_by_tag))
+        .route("/tags/{name}/feed.xml", get(handlers::posts::get_tag_feed))
+        .route("/tags/{tag_id}", delete(handlers::tags::delete_tag))
+        // Search
+        .route("/search", get(handlers::posts::search_posts))
+        .with_state(app_state.clone())
+        .layer(middleware::from_fn(public_cache_control))
+        .layer(request_timeout(REQUEST_TIMEOUT));
 
-    Ok(Json(summaries))
+    let auth_api = Router::new()
+        .route("/auth/login", post(handlers::auth::login))
+        .route("/auth/refresh", post(handlers::auth::refresh))
+        .route("/auth/logout", post(handlers::auth::logout))
+        // Decoy
+        .route("/admin/login", post(handlers::auth::decoy_login))
+        .with_state(app_state.clone())
+        .layer(middleware::map_response(no_store))
+        .layer(request_timeout(REQUEST_TIMEOUT));
+    let public_api = public_api.merge(auth_api);
+
+    let admin_api = Router::new()
+        // Posts (admin)
+        .route(
+            "/posts",
+            post(handlers::admin::create_post).get(handlers::admin::list_all_posts),
+        )
+        .route("/posts/validate", post(handlers::admin::validate_post))
+        .route(
+            "/posts/{slug}",
+            put(handlers::admin::update_post).delete(handlers::admin::delete_post),
+        )
+        .route(
+            "/posts/by-id/{id}",
+            get(handlers::admin::get_post_by_id)
+                .put(handlers::admin::update_post_by_id)
+                .delete(handlers::admin::delete_post_by_id),
+        )
+        .route(
+            "/posts/{slug}/draft",
+            get(handlers::admin::get_draft).put(handlers::admin::save_draft),
+        )
+        .route("/posts/{slug}/autosave", put(handlers::admin::autosave_post))
+        .route("/posts/{slug}/nav-preview", get(handlers::admin::nav_preview))
+        .route("/posts/{slug}/publish", post(handlers::admin::publish_post))
+        .route("/posts/{slug}/restore", post(handlers::admin::restore_post))
+        .route(
+            "/posts/{slug}/unpublish",
+            post(handlers::admin::unpublish_post),
+        )
+        .route(
+            "/posts/{slug}/render-timing",
+            get(handlers::admin::render_timing),
+        )
+        .route("/stats", get(handlers::admin::get_post_stats))
+        .route("/stats/timeline", get(handlers::admin::get_post_timeline))
+        // Backup and migration
+        .route("/export", get(handlers::admin::export_blog))
+        .route("/import", post(handlers::admin::import_blog))
+        .route("/search", get(handlers::admin::search_posts))
+        .route("/links/unresolved", get(handlers::admin::list_unresolved_links))
+        // Demo content (admin only)
+        .route(
+            "/seed",
+            post(handlers::admin::seed_demo_content).delete(handlers::admin::clear_demo_content),
+        )
+        // Markdown preview
+        .route("/preview", post(handlers::admin::preview_markdown))
+        .route("/lint", post(handlers::admin::lint_markdown))
+        .route("/outline", post(handlers::admin::outline_markdown))
+        // Account
+        .route("/account/password", post(handlers::auth::change_password))
+        // Users (admin only)
+        .route("/users", post(handlers::users::create_user))
+        // Tags (admin)
+        .route("/tags", post(handlers::admin::create_tag))
+        .route(
+            "/tags/{id}",
+            put(handlers::admin::update_tag).delete(handlers::admin::delete_tag),
+        )
+        .with_state(app_state.clone())
+        .layer(middleware::map_response(no_store))
+        .layer(request_timeout(REQUEST_TIMEOUT));
+
+    // Vault imports write a post per file, so they get a bigger body and more time
+    let vault_api = Router::new()
+        .route(
+            "/import/obsidian",
+            post(handlers::vault::import_obsidian)
+                .layer(DefaultBodyLimit::max(handlers::vault::MAX_VAULT_BYTES)),
+        )
+        .with_state(app_state.clone())
+        .layer(middleware::map_response(no_store))
+        .layer(request_timeout(handlers::vault::VAULT_IMPORT_TIMEOUT));
+    // A reindex walks every post in batches, well past the usual request timeout
+    let reindex_api = Router::new()
+        .route("/reindex", post(handlers::admin::reindex))
+        .with_state(app_state.clone())
+        .layer(middleware::map_response(no_store))
+        .layer(request_timeout(handlers::admin::REINDEX_TIMEOUT));
+    let admin_api = admin_api.merge(vault_api).merge(reindex_api);
+
+    Router::new()
+        .nest("/api", public_api.clone())
+        .nest("/api/sayyidati", admin_api.clone())
+        .nest("/api/v1", public_api)
+        .nest("/api/v1/sayyidati", admin_api)
+        .fallback(not_found)
+        .layer(DefaultBodyLimit::max(MAX_REQUEST_BODY_BYTES))
+        // gzip/brotli per Accept-Encoding. ETags name the post version, not the bytes,
+        // so they're computed before this layer and hold for every encoding.
+        .layer(CompressionLayer::new())
+}
+
+/// Largest request body any handler will buffer; room for a maximal
+/// markdown document plus its JSON envelope
+const MAX_REQUEST_BODY_BYTES: usize = 2 * markdown::MAX_MARKDOWN_BYTES;
+
+/// Longest a request may run before it's answered with a 503
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Give up on stuck requests with a 503 instead of letting clients hang.
+/// Applied per router so slow-by-design routes can be given longer.
+fn request_timeout(timeout: std::time::Duration) -> TimeoutLayer {
+    TimeoutLayer::with_status_code(StatusCode::SERVICE_UNAVAILABLE, timeout)
+}
+
+/// How long a query waits for a free pooled connection before failing with
+/// `PoolTimedOut`; well under `REQUEST_TIMEOUT` so the busy error reaches the client
+const POOL_ACQUIRE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// `Cache-Control` for public reads; short, so edits reach readers quickly
+const PUBLIC_CACHE_CONTROL: &str = "public, max-age=60";
+/// `Cache-Control` for admin, auth and anything that failed or changed state
+const NO_STORE: &str = "no-store";
+
+/// Cache successful public GETs briefly; never cache writes or errors.
+/// Handlers that set their own `Cache-Control` keep it.
+async fn public_cache_control(req: Request<Body>, next: Next) -> Response<Body> {
+    let method = req.method().clone();
+    let mut res = next.run(req).await;
+    let value = public_cache_policy(&method, res.status());
+    res.headers_mut()
+        .entry(header::CACHE_CONTROL)
+        .or_insert(HeaderValue::from_static(value));
+    res
+}
+
+fn public_cache_policy(method: &Method, status: StatusCode) -> &'static str {
+    let read = *method == Method::GET || *method == Method::HEAD;
+    if read && (status.is_success() || status == StatusCode::NOT_MODIFIED) {
+        PUBLIC_CACHE_CONTROL
+    } else {
+        NO_STORE
+    }
+}
+
+/// Private responses (tokens, drafts, admin data) must not be stored anywhere
+async fn no_store(mut res: Response<Body>) -> Response<Body> {
+    res.headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static(NO_STORE));
+    res
+}
+
+/// Header carrying the per-request id, generated unless the client sent one
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Log every request with its id, method, path, status and latency,
+/// and echo the id back in `X-Request-Id`
+fn with_request_logging(router: Router) -> Router {
+    let request_id = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    router.layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::new(request_id.clone(), MakeRequestUuid))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(request_span)
+                    .on_response(log_response),
+            )
+            .layer(PropagateRequestIdLayer::new(request_id)),
+    )
+}
+
+// Handler log lines emitted inside this span carry the request id
+fn request_span<B>(req: &Request<B>) -> Span {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-");
+
+    tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.uri().path(),
+    )
+}
+
+fn log_response<B>(res: &Response<B>, latency: std::time::Duration, _span: &Span) {
+    tracing::info!(
+        status = res.status().as_u16(),
+        elapsed_ms = latency.as_millis() as u64,
+        "request completed"
+    );
+}
+
+/// The router, served with each connection's peer address available as
+/// `ConnectInfo` so `ClientIp` can fall back to it
+struct BlogService(Router);
+
+#[shuttle_runtime::async_trait]
+impl shuttle_runtime::Service for BlogService {
+    async fn bind(self, addr: SocketAddr) -> Result<(), shuttle_runtime::Error> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        shuttle_axum::axum::serve(
+            listener,
+            self.0.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await?;
+        Ok(())
+    }
 }
 
 #[shuttle_runtime::main]
 async fn axum(
     #[shuttle_runtime::Secrets] secrets: shuttle_runtime::SecretStore,
-) -> ShuttleAxum {
+) -> Result<BlogService, shuttle_runtime::Error> {
 
     // Get configuration from Shuttle secrets
     let database_url = secrets
@@ -81,15 +332,112 @@ async fn axum(
         .expect("DATABASE_URL secret not set");
     let jwt_secret = secrets
         .get("JWT_SECRET")
-        .unwrap_or_else(|| "development-secret-change-me".to_string());
+        .unwrap_or_else(|| auth::DEV_JWT_SECRET.to_string());
+    // Anything but APP_ENV=development is treated as production
+    let development = secrets
+        .get("APP_ENV")
+        .is_some_and(|env| env.eq_ignore_ascii_case("development"));
+    auth::ensure_secure_jwt_secret(&jwt_secret, development)?;
+    // Secret rotation: keep accepting tokens signed with the old secret for a grace period
+    let previous_jwt_secret = match secrets.get("JWT_PREVIOUS_SECRET") {
+        Some(secret) => {
+            let grace_minutes = match secrets.get("JWT_ROTATION_GRACE_MINUTES") {
+                Some(v) => v.parse::<i64>().map_err(|_| {
+                    anyhow::anyhow!("JWT_ROTATION_GRACE_MINUTES must be a whole number of minutes")
+                })?,
+                None => 60,
+            };
+            Some(auth::PreviousSecret {
+                secret,
+                valid_until: chrono::Utc::now() + chrono::Duration::minutes(grace_minutes),
+            })
+        }
+        None => None,
+    };
     let admin_password = secrets
         .get("ADMIN_PASSWORD")
         .unwrap_or_else(|| "admin123".to_string());
     let cors_origins = secrets.get("CORS_ORIGINS");
+    // Credentialed requests (cookies) need an explicit CORS_ORIGINS list
+    let cors_allow_credentials = secrets
+        .get("CORS_ALLOW_CREDENTIALS")
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    // Public site origin, used for canonical URLs in post metadata
+    let frontend_url = secrets.get("FRONTEND_URL");
+    let ip_hash_salt = secrets.get("IP_HASH_SALT");
+    let strict_frontmatter = secrets
+        .get("STRICT_FRONTMATTER")
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    let require_cover_image = secrets
+        .get("REQUIRE_COVER_IMAGE")
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    let excerpt_min_body_chars = match secrets.get("EXCERPT_MIN_BODY_CHARS") {
+        Some(v) => v.parse::<usize>().map_err(|_| {
+            anyhow::anyhow!("EXCERPT_MIN_BODY_CHARS must be a whole number of characters")
+        })?,
+        None => markdown::DEFAULT_EXCERPT_MIN_BODY_CHARS,
+    };
+    let render_timeout = match secrets.get("RENDER_TIMEOUT_MS") {
+        Some(v) => std::time::Duration::from_millis(v.parse::<u64>().map_err(|_| {
+            anyhow::anyhow!("RENDER_TIMEOUT_MS must be a whole number of milliseconds")
+        })?),
+        None => markdown::DEFAULT_RENDER_TIMEOUT,
+    };
+    let max_callout_depth = match secrets.get("MAX_CALLOUT_DEPTH") {
+        Some(v) => v
+            .parse::<usize>()
+            .map_err(|_| anyhow::anyhow!("MAX_CALLOUT_DEPTH must be a whole number of levels"))?,
+        None => markdown::DEFAULT_MAX_CALLOUT_DEPTH,
+    };
+    let related_min_common_tags = match secrets.get("RELATED_MIN_COMMON_TAGS") {
+        Some(v) => v
+            .parse::<i64>()
+            .ok()
+            .filter(|n| *n >= 1)
+            .ok_or_else(|| anyhow::anyhow!("RELATED_MIN_COMMON_TAGS must be a whole number of at least 1"))?,
+        None => 1,
+    };
+    // Proxies whose X-Forwarded-For entries are trusted when identifying clients
+    let trusted_proxies = match secrets.get("TRUSTED_PROXIES") {
+        Some(v) => v
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| anyhow::anyhow!("TRUSTED_PROXIES must be a whole number of proxies"))?,
+        None => visitor::DEFAULT_TRUSTED_PROXIES,
+    };
+
+    // Optional webhook, by default fired only on publish
+    let webhook = match secrets.get("WEBHOOK_URL").filter(|url| !url.trim().is_empty()) {
+        Some(url) => {
+            let events = webhook::parse_events(secrets.get("WEBHOOK_EVENTS").as_deref())?;
+            Some(Arc::new(webhook::Webhook::new(url, events)?))
+        }
+        None => None,
+    };
+
+    let url_schemes = secrets
+        .get("ALLOWED_URL_SCHEMES")
+        .map(|v| markdown::parse_url_schemes(&v));
+
+    let pool_config = parse_pool_config(
+        secrets.get("DB_MAX_CONNECTIONS").as_deref(),
+        secrets.get("DB_MIN_CONNECTIONS").as_deref(),
+        secrets.get("DB_IDLE_TIMEOUT_SECS").as_deref(),
+    )?;
+    tracing::info!(
+        "Database pool: max {} connections, min {}, idle timeout {:?}, acquire timeout {:?}",
+        pool_config.max_connections,
+        pool_config.min_connections,
+        pool_config.idle_timeout,
+        POOL_ACQUIRE_TIMEOUT
+    );
 
     // Database connection
     let pool = PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(pool_config.max_connections)
+        .min_connections(pool_config.min_connections)
+        .idle_timeout(pool_config.idle_timeout)
+        .acquire_timeout(POOL_ACQUIRE_TIMEOUT)
         .connect(&database_url)
         .await
         .expect("Failed to connect to database");
@@ -104,17 +452,119 @@ async fn axum(
         .map_err(|e| anyhow::anyhow!("Migration run error: {}", e))?;
     println!("✅ Migrations completed");
 
-    // Initialize admin user
+    // Initialize admin user (ADMIN_PASSWORD only applies on first creation)
     println!("Creating admin user...");
     db::init_admin_user(&pool, &admin_password)
         .await
         .expect("Failed to initialize admin user");
-    println!("✅ Admin user created");
+    println!("✅ Admin user ready");
+
+    let mut app_state = state::AppState::new(pool, jwt_secret);
+    app_state.previous_jwt_secret = previous_jwt_secret;
+    app_state.frontend_url = frontend_url;
+    // Without a configured salt, a per-boot random one is used (hashes won't survive restarts)
+    if let Some(salt) = ip_hash_salt {
+        app_state.ip_salt = salt;
+    }
+    app_state.strict_frontmatter = strict_frontmatter;
+    app_state.require_cover_image = require_cover_image;
+    app_state.excerpt_min_body_chars = excerpt_min_body_chars;
+    app_state.render_timeout = render_timeout;
+    app_state.max_callout_depth = max_callout_depth;
+    app_state.related_min_common_tags = related_min_common_tags;
+    app_state.webhook = webhook;
+    app_state.trusted_proxies = trusted_proxies;
+    if let Some(schemes) = url_schemes {
+        app_state.url_schemes = schemes;
+    }
+    let app_state = Arc::new(app_state);
+
+    // Publish scheduled posts in the background
+    tokio::spawn(publish_scheduler(app_state.clone()));
+
+    let cors = build_cors(cors_origins.as_deref(), cors_allow_credentials).inspect_err(|e| {
+        tracing::error!("Invalid CORS configuration: {}", e);
+    })?;
 
-    let app_state = Arc::new(state::AppState::new(pool, jwt_secret));
+    let app = with_request_logging(build_router(app_state).layer(cors));
 
-    // CORS
-    let mut cors = CorsLayer::new()
+    Ok(BlogService(app))
+}
+
+/// Pool sizing; the defaults suit Shuttle's shared Postgres
+#[derive(Debug, PartialEq)]
+struct PoolConfig {
+    max_connections: u32,
+    min_connections: u32,
+    /// `None` keeps idle connections open indefinitely
+    idle_timeout: Option<std::time::Duration>,
+}
+
+const DEFAULT_DB_MAX_CONNECTIONS: u32 = 5;
+const DEFAULT_DB_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Read `DB_MAX_CONNECTIONS`, `DB_MIN_CONNECTIONS` and `DB_IDLE_TIMEOUT_SECS`
+/// (0 disables the idle timeout), rejecting values the pool can't honour
+fn parse_pool_config(
+    max: Option<&str>,
+    min: Option<&str>,
+    idle_secs: Option<&str>,
+) -> anyhow::Result<PoolConfig> {
+    let max_connections = match max {
+        Some(v) => v
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|n| *n >= 1)
+            .ok_or_else(|| {
+                anyhow::anyhow!("DB_MAX_CONNECTIONS must be a whole number of at least 1")
+            })?,
+        None => DEFAULT_DB_MAX_CONNECTIONS,
+    };
+    let min_connections = match min {
+        Some(v) => v
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("DB_MIN_CONNECTIONS must be a whole number"))?,
+        None => 0,
+    };
+    if min_connections > max_connections {
+        anyhow::bail!(
+            "DB_MIN_CONNECTIONS ({}) can't exceed DB_MAX_CONNECTIONS ({})",
+            min_connections,
+            max_connections
+        );
+    }
+    let idle_timeout = match idle_secs {
+        Some(v) => match v.trim().parse::<u64>() {
+            Ok(0) => None,
+            Ok(secs) => Some(std::time::Duration::from_secs(secs)),
+            Err(_) => anyhow::bail!("DB_IDLE_TIMEOUT_SECS must be a whole number of seconds"),
+        },
+        None => Some(DEFAULT_DB_IDLE_TIMEOUT),
+    };
+
+    Ok(PoolConfig {
+        max_connections,
+        min_connections,
+        idle_timeout,
+    })
+}
+
+/// How long browsers may cache a CORS preflight
+const CORS_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// CORS for the API.
+/// - Origins come from the comma-separated `origins` list; without one, any origin is allowed (dev)
+/// - Credentials can't be combined with a wildcard origin, so they require an explicit list
+fn build_cors(origins: Option<&str>, allow_credentials: bool) -> anyhow::Result<CorsLayer> {
+    let allowed: Vec<HeaderValue> = origins
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|o| o.trim().parse().ok())
+        .collect();
+
+    let cors = CorsLayer::new()
         .allow_methods([
             Method::GET,
             Method::POST,
@@ -122,74 +572,235 @@ async fn axum(
             Method::DELETE,
             Method::OPTIONS,
         ])
-        .allow_headers(Any);
-
-    // Prefer explicit allowlist via CORS_ORIGINS; else in dev allow any
-    if let Some(list) = cors_origins {
-        let allowed: Vec<HeaderValue> = list
-            .split(',')
-            .filter_map(|o| o.trim().parse().ok())
-            .collect();
-        if !allowed.is_empty() {
-            cors = cors.allow_origin(allowed);
-        } else {
-            cors = cors.allow_origin(Any);
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)])
+        .max_age(CORS_MAX_AGE);
+
+    if allow_credentials {
+        if allowed.is_empty() {
+            anyhow::bail!(
+                "CORS_ALLOW_CREDENTIALS=true needs an explicit CORS_ORIGINS list; \
+                 credentials can't be allowed for any origin"
+            );
         }
-    } else {
+        // A wildcard header list is also off-limits with credentials, so echo the request's
+        return Ok(cors
+            .allow_origin(allowed)
+            .allow_headers(AllowHeaders::mirror_request())
+            .allow_credentials(true));
+    }
+
+    let cors = cors.allow_headers(Any);
+    Ok(if allowed.is_empty() {
         // Development convenience; prefer to configure origins explicitly in production
-        cors = cors.allow_origin(Any);
+        cors.allow_origin(Any)
+    } else {
+        cors.allow_origin(allowed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shuttle_axum::axum::{
+        body::{to_bytes, Body},
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    // Routes that don't touch the database work against a lazy, never-connected pool.
+    // Routes that do fail fast with 503 once the short acquire timeout passes.
+    fn test_state() -> SharedState {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_millis(200))
+            .connect_lazy("postgres://localhost/blog_test")
+            .expect("valid database url");
+        Arc::new(state::AppState::new(pool, "test-secret".to_string()))
     }
 
-    // Routers
-    let public_api = Router::new()
-        .route("/health", get(health))
-        .route("/db-probe", get(db_probe))
-        // Posts
-        .route("/posts", get(handlers::posts::list_posts))
-        .route("/posts/{slug}", get(handlers::posts::get_post))
-        // Tags
-        .route("/tags", get(handlers::tags::list_tags).post(handlers::tags::create_tag))
-        .route("/tags/stats", get(handlers::tags::get_tag_stats))
-        .route("/tags/{tag_id}", delete(handlers::tags::delete_tag))
-        // Search
-        .route("/search", get(public_search))
-        // Auth
-        .route("/auth/login", post(handlers::auth::login))
-        // Decoy
-        .route("/admin/login", post(handlers::auth::decoy_login))
-        .with_state(app_state.clone());
+    #[tokio::test]
+    async fn test_cors_preflight_max_age_and_credentials() {
+        assert!(build_cors(None, true).is_err());
+        assert!(build_cors(Some(" , "), true).is_err());
 
-    let admin_api = Router::new()
-        // Posts (admin)
-        .route(
-            "/posts",
-            post(handlers::admin::create_post).get(handlers::admin::list_all_posts),
-        )
-        .route(
-            "/posts/{slug}",
-            put(handlers::admin::update_post).delete(handlers::admin::delete_post),
-        )
-        .route("/posts/{slug}/publish", post(handlers::admin::publish_post))
-        .route(
-            "/posts/{slug}/unpublish",
-            post(handlers::admin::unpublish_post),
-        )
-        .route("/stats", get(handlers::admin::get_post_stats))
-        // Markdown preview
-        .route("/preview", post(handlers::admin::preview_markdown))
-        // Tags (admin)
-        .route("/tags", post(handlers::admin::create_tag))
-        .route(
-            "/tags/{id}",
-            put(handlers::admin::update_tag).delete(handlers::admin::delete_tag),
+        let cors = build_cors(Some("https://blog.example"), true).unwrap();
+        let res = build_router(test_state())
+            .layer(cors)
+            .oneshot(
+                Request::options("/api/posts")
+                    .header("origin", "https://blog.example")
+                    .header("access-control-request-method", "GET")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.headers()["access-control-max-age"], "3600");
+        assert_eq!(res.headers()["access-control-allow-credentials"], "true");
+        assert_eq!(res.headers()["access-control-allow-origin"], "https://blog.example");
+    }
+
+    #[tokio::test]
+    async fn test_cache_control_per_route() {
+        let app = build_router(test_state());
+
+        let res = app
+            .clone()
+            .oneshot(Request::get("/api/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[header::CACHE_CONTROL], PUBLIC_CACHE_CONTROL);
+
+        // Rejected before any database work, but still never cacheable
+        let res = app
+            .oneshot(
+                Request::post("/api/auth/login")
+                    .header("content-type", "application/json")
+                    .body(Body::from("{}"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[header::CACHE_CONTROL], NO_STORE);
+
+        // The posts list is a public GET like health; errors and writes are not cached
+        assert_eq!(public_cache_policy(&Method::GET, StatusCode::OK), PUBLIC_CACHE_CONTROL);
+        assert_eq!(public_cache_policy(&Method::GET, StatusCode::NOT_MODIFIED), PUBLIC_CACHE_CONTROL);
+        assert_eq!(public_cache_policy(&Method::GET, StatusCode::INTERNAL_SERVER_ERROR), NO_STORE);
+        assert_eq!(public_cache_policy(&Method::POST, StatusCode::CREATED), NO_STORE);
+    }
+
+    #[tokio::test]
+    async fn test_post_by_id_rejects_malformed_uuid() {
+        let token = auth::generate_jwt(
+            uuid::Uuid::new_v4(),
+            "admin",
+            models::Role::Admin,
+            "test-secret",
+            chrono::Duration::minutes(5),
         )
-        .with_state(app_state.clone());
+        .unwrap();
+
+        for method in [Method::GET, Method::PUT, Method::DELETE] {
+            let res = build_router(test_state())
+                .oneshot(
+                    Request::builder()
+                        .method(method.clone())
+                        .uri("/api/sayyidati/posts/by-id/not-a-uuid")
+                        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from("{}"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST, "{}", method);
+        }
+    }
+
+    #[test]
+    fn test_pool_config_defaults_and_validation() {
+        assert_eq!(
+            parse_pool_config(None, None, None).unwrap(),
+            PoolConfig {
+                max_connections: 5,
+                min_connections: 0,
+                idle_timeout: Some(DEFAULT_DB_IDLE_TIMEOUT),
+            }
+        );
+        assert_eq!(
+            parse_pool_config(Some("20"), Some("2"), Some("0")).unwrap(),
+            PoolConfig {
+                max_connections: 20,
+                min_connections: 2,
+                idle_timeout: None,
+            }
+        );
+        assert!(parse_pool_config(Some("0"), None, None).is_err());
+        assert!(parse_pool_config(Some("ten"), None, None).is_err());
+        assert!(parse_pool_config(Some("4"), Some("8"), None).is_err());
+        assert!(parse_pool_config(None, None, Some("-1")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_responses_compressed_on_request() {
+        let app = build_router(test_state());
+
+        let res = app
+            .clone()
+            .oneshot(
+                Request::get("/api/nope")
+                    .header(header::ACCEPT_ENCODING, "gzip")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(res.headers()[header::VARY], "accept-encoding");
+
+        let res = app
+            .oneshot(Request::get("/api/nope").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+    }
 
-    let app = Router::new()
-        .nest("/api", public_api)
-        .nest("/api/sayyidati", admin_api)
-        .layer(cors)
-        .layer(TraceLayer::new_for_http());
+    #[tokio::test]
+    async fn test_versioned_alias_routes() {
+        for path in ["/api/health", "/api/v1/health"] {
+            let res = build_router(test_state())
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::OK, "{}", path);
+        }
+
+        // The post list reaches its handler (and the unreachable database) on both bases
+        for path in ["/api/posts", "/api/v1/posts"] {
+            let res = build_router(test_state())
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_routes_return_json_404() {
+        for path in ["/api/nope", "/api/sayyidati/nope", "/api/v1/postz"] {
+            let res = build_router(test_state())
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{}", path);
 
-    Ok(AxumService::from(app))
+            let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["status"], 404, "{}", path);
+            assert!(json["error"].is_string(), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_echoed() {
+        let app = with_request_logging(build_router(test_state()));
+
+        let res = app
+            .clone()
+            .oneshot(Request::get("/api/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(res.headers().contains_key(REQUEST_ID_HEADER));
+
+        let res = app
+            .oneshot(
+                Request::get("/api/health")
+                    .header(REQUEST_ID_HEADER, "client-supplied")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[REQUEST_ID_HEADER], "client-supplied");
+    }
 }
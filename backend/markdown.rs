@@ -1,7 +1,80 @@
 use ammonia::{Builder, UrlRelative};
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd, html};
 use regex::Regex;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::error::AppError;
+use crate::models::{FieldError, LintKind, LintWarning, Role};
+
+/// Syntax definitions for server-side highlighting, loaded once
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+/// Compile a pattern for one of the statics below; each is built once, on first use
+fn compile_regex(pattern: &str) -> Regex {
+    #[cfg(test)]
+    REGEX_COMPILES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    Regex::new(pattern).expect("valid regex")
+}
+
+/// How many regexes have been compiled, so tests can check rendering reuses them
+#[cfg(test)]
+static REGEX_COMPILES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// `[[Page]]` / `[[Page|Display]]`
+static WIKI_LINK_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"\[\[([^\]|]+)(?:\|([^\]]+))?\]\]"));
+/// Inline `#tag` preceded by whitespace; nested tags use `/` between segments.
+/// Inline code is matched too, so it can be copied through untouched.
+static OBSIDIAN_TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    compile_regex(r"(?P<code>`[^`\n]*`)|(?:^|\s)#(?P<tag>[a-zA-Z][a-zA-Z0-9_-]*(?:/[a-zA-Z0-9_-]+)*)")
+});
+/// Any `#tag`, for stripping from plain text
+static TAG_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"#([a-zA-Z][a-zA-Z0-9_-]*(?:/[a-zA-Z0-9_-]+)*)"));
+/// `^block-id` at the end of a line
+static BLOCK_ID_RE: LazyLock<Regex> = LazyLock::new(|| compile_regex(r"(?m)\^([a-zA-Z0-9-]+)$"));
+/// `![[Image]]` / `![[Page]]` embeds
+static EMBED_RE: LazyLock<Regex> = LazyLock::new(|| compile_regex(r"!\[\[([^\]]+)\]\]"));
+/// `[!type]` opening a rendered blockquote: type, fold marker, then the rest of the title line
+static CALLOUT_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"^\s*<p>\[!([^\]]+)\]([+-]?)[ \t]*(.*?)(\n|</p>)"));
+/// `==highlight==`
+static HIGHLIGHT_RE: LazyLock<Regex> = LazyLock::new(|| compile_regex(r"==(.*?)=="));
+/// `==highlight==` in rendered HTML, with `<code>` elements matched so they can be skipped
+static HTML_HIGHLIGHT_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"(?P<code><code[^>]*>[\s\S]*?</code>)|==(?P<text>.*?)=="));
+/// Rendered mermaid code blocks
+static MERMAID_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r#"<pre><code class="language-mermaid">([\s\S]*?)</code></pre>"#));
+/// pulldown-cmark's inline alignment on table cells
+static TABLE_ALIGN_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r#"<(th|td) style="text-align: (left|center|right)">"#));
+/// Markdown and Obsidian image syntax, timed separately from prose
+static READING_IMAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"!\[[^\]]*\]\([^)]*\)|!\[\[([^\]|]+)(?:\|[^\]]*)?\]\]"));
+/// Wiki-links and embeds, with the `!` captured
+static LINK_REF_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"(!?)\[\[([^\]|]+)(?:\|[^\]]*)?\]\]"));
+/// Callout marker lines in raw markdown
+static LINT_CALLOUT_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"^\s*(?:>\s*)+\[!([^\]]+)\]"));
+/// ATX heading lines
+static ATX_HEADING_RE: LazyLock<Regex> = LazyLock::new(|| compile_regex(r"^(#{1,6})\s+\S"));
+/// Image syntax with the URL or embed name captured
+static COVER_IMAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"!\[[^\]]*\]\(([^)\s]+)[^)]*\)|!\[\[([^\]|]+)(?:\|[^\]]*)?\]\]"));
+/// Syntax highlighting classes emitted by syntect
+static HIGHLIGHT_CLASS_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"\bhl-[a-zA-Z0-9_-]+"));
+
+/// Class prefix for highlighted tokens; the frontend maps these to Catppuccin colors
+const HIGHLIGHT_CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
 
 /// Obsidian-style callout types with Catppuccin colors
 #[derive(Debug, Clone)]
@@ -13,98 +86,264 @@ pub struct CalloutType {
 
 impl CalloutType {
     pub fn from_str(s: &str) -> Self {
+        Self::lookup(s).unwrap_or(Self {
+            name: "note",
+            icon: "", // nf-oct-pin
+            color: "surface2",
+        })
+    }
+
+    /// The callout for a recognized type name (or alias), None for unknown types
+    pub fn lookup(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
-            "note" | "info" => Self {
+            "note" | "info" => Some(Self {
                 name: "note",
                 icon: "", // nf-fa-info_circle
                 color: "blue",
-            },
-            "tip" | "hint" | "important" => Self {
+            }),
+            "tip" | "hint" => Some(Self {
                 name: "tip",
                 icon: "", // nf-fa-lightbulb_o
                 color: "teal",
-            },
-            "warning" | "caution" | "attention" => Self {
+            }),
+            // GitHub alert: [!IMPORTANT]
+            "important" => Some(Self {
+                name: "important",
+                icon: "", // nf-fa-exclamation_circle
+                color: "mauve",
+            }),
+            "warning" | "attention" => Some(Self {
                 name: "warning",
                 icon: "", // nf-fa-exclamation_triangle
                 color: "yellow",
-            },
-            "danger" | "error" => Self {
+            }),
+            // GitHub alert: [!CAUTION]
+            "caution" => Some(Self {
+                name: "caution",
+                icon: "", // nf-fa-ban
+                color: "red",
+            }),
+            "danger" | "error" => Some(Self {
                 name: "danger",
                 icon: "", // nf-fa-fire
                 color: "red",
-            },
-            "success" | "check" | "done" => Self {
+            }),
+            "success" | "check" | "done" => Some(Self {
                 name: "success",
                 icon: "", // nf-fa-check_circle
                 color: "green",
-            },
-            "question" | "help" | "faq" => Self {
+            }),
+            "question" | "help" | "faq" => Some(Self {
                 name: "question",
                 icon: "", // nf-fa-question_circle
                 color: "mauve",
-            },
-            "example" => Self {
+            }),
+            "example" => Some(Self {
                 name: "example",
                 icon: "", // nf-fa-file_text_o
                 color: "lavender",
-            },
-            "quote" | "cite" => Self {
+            }),
+            "quote" | "cite" => Some(Self {
                 name: "quote",
                 icon: "", // nf-fa-quote_left
                 color: "flamingo",
-            },
-            "bug" => Self {
+            }),
+            "bug" => Some(Self {
                 name: "bug",
                 icon: "", // nf-fa-bug
                 color: "maroon",
-            },
-            "abstract" | "summary" | "tldr" => Self {
+            }),
+            "abstract" | "summary" | "tldr" => Some(Self {
                 name: "abstract",
                 icon: "", // nf-fa-file_text
                 color: "sky",
-            },
-            "code" | "snippet" => Self {
+            }),
+            "code" | "snippet" => Some(Self {
                 name: "code",
                 icon: "", // nf-fa-code
                 color: "peach",
-            },
-            "todo" | "task" => Self {
+            }),
+            "todo" | "task" => Some(Self {
                 name: "todo",
                 icon: "", // nf-fa-check_square_o
                 color: "sapphire",
-            },
-            _ => Self {
-                name: "note",
-                icon: "", // nf-oct-pin
-                color: "surface2",
-            },
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Which sanitizer allowlist a render goes through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SanitizerProfile {
+    /// Obsidian elements only; raw HTML beyond the basics is stripped
+    #[default]
+    Safe,
+    /// Additionally allows embeds such as iframes and media elements
+    Trusted,
+}
+
+/// URL schemes allowed in links and images unless configured otherwise
+pub const DEFAULT_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+/// Schemes that can run script; never allowed, whatever the configuration says
+const BLOCKED_URL_SCHEMES: [&str; 3] = ["javascript", "vbscript", "data"];
+
+/// Parse a comma-separated scheme list, dropping blanks and blocked schemes
+pub fn parse_url_schemes(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|scheme| scheme.trim().trim_end_matches(':').to_ascii_lowercase())
+        .filter(|scheme| !scheme.is_empty() && !BLOCKED_URL_SCHEMES.contains(&scheme.as_str()))
+        .collect()
+}
+
+/// Options controlling how a post is rendered
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub profile: SanitizerProfile,
+    /// Schemes kept in `href`/`src`; anything else is stripped (relative URLs always pass)
+    pub url_schemes: Vec<String>,
+    /// Blockquotes nested deeper than this are left as plain blockquotes
+    pub max_callout_depth: usize,
+}
+
+/// Callout nesting processed unless configured otherwise
+pub const DEFAULT_MAX_CALLOUT_DEPTH: usize = 8;
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            profile: SanitizerProfile::default(),
+            url_schemes: DEFAULT_URL_SCHEMES.map(String::from).to_vec(),
+            max_callout_depth: DEFAULT_MAX_CALLOUT_DEPTH,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Pick the render options for a post based on who wrote it.
+    /// Only admins can opt a post into the trusted profile.
+    pub fn for_author(role: Role, trusted_html: bool) -> Self {
+        let profile = match role {
+            Role::Admin if trusted_html => SanitizerProfile::Trusted,
+            _ => SanitizerProfile::Safe,
+        };
+        Self {
+            profile,
+            ..Self::default()
         }
     }
+
+    /// Use the configured link schemes instead of the defaults
+    pub fn with_url_schemes(mut self, schemes: &[String]) -> Self {
+        self.url_schemes = schemes.to_vec();
+        self
+    }
+
+    /// Use the configured callout nesting limit instead of the default
+    pub fn with_max_callout_depth(mut self, depth: usize) -> Self {
+        self.max_callout_depth = depth;
+        self
+    }
+}
+
+/// Largest markdown document accepted for rendering
+pub const MAX_MARKDOWN_BYTES: usize = 512 * 1024;
+
+/// Reject markdown over `MAX_MARKDOWN_BYTES`, naming the limit for the editor to show
+pub fn check_markdown_size(markdown: &str) -> Result<(), AppError> {
+    if markdown.len() > MAX_MARKDOWN_BYTES {
+        return Err(AppError::BadRequest(format!(
+            "Markdown is too large ({} KB); the limit is {} KB",
+            markdown.len().div_ceil(1024),
+            MAX_MARKDOWN_BYTES / 1024
+        )));
+    }
+    Ok(())
+}
+
+/// How long a request waits for a render by default
+pub const DEFAULT_RENDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Render on the blocking thread pool, giving up after `deadline`.
+/// A render can't be interrupted, so one that times out still finishes in the
+/// background; the request just stops waiting and returns 503.
+pub async fn render_with_deadline(
+    content: String,
+    opts: RenderOptions,
+    deadline: Duration,
+) -> Result<String, AppError> {
+    run_with_deadline(deadline, move || render_obsidian_markdown_with(&content, &opts)).await
+}
+
+async fn run_with_deadline<F>(deadline: Duration, render: F) -> Result<String, AppError>
+where
+    F: FnOnce() -> String + Send + 'static,
+{
+    match tokio::time::timeout(deadline, tokio::task::spawn_blocking(render)).await {
+        Ok(Ok(html)) => Ok(html),
+        Ok(Err(e)) => Err(AppError::Internal(format!("Render task failed: {}", e))),
+        Err(_) => Err(AppError::ServiceUnavailable(format!(
+            "Rendering did not finish within {} ms",
+            deadline.as_millis()
+        ))),
+    }
 }
 
 /// Process Obsidian-style markdown into HTML
 pub fn render_obsidian_markdown(content: &str) -> String {
+    render_obsidian_markdown_with(content, &RenderOptions::default())
+}
+
+/// Process Obsidian-style markdown into HTML with explicit render options
+pub fn render_obsidian_markdown_with(content: &str, opts: &RenderOptions) -> String {
+    render_with_timings(content, opts).0
+}
+
+/// Wall-clock durations of each rendering phase, in pipeline order
+pub type RenderTimings = Vec<(&'static str, Duration)>;
+
+/// Measures consecutive phases of the rendering pipeline
+struct PhaseClock {
+    last: Instant,
+    phases: RenderTimings,
+}
+
+impl PhaseClock {
+    fn start() -> Self {
+        Self {
+            last: Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    fn lap(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, now.duration_since(self.last)));
+        self.last = now;
+    }
+}
+
+/// Render markdown, also reporting how long each pipeline phase took
+pub fn render_with_timings(content: &str, opts: &RenderOptions) -> (String, RenderTimings) {
+    let mut clock = PhaseClock::start();
+
     // Pre-process Obsidian-specific syntax
     let processed = preprocess_obsidian_syntax(content);
+    clock.lap("preprocess");
 
-    // Configure parser options
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_FOOTNOTES);
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TASKLISTS);
-    options.insert(Options::ENABLE_SMART_PUNCTUATION);
-    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    let mut parsed: Vec<Event> = Parser::new_ext(&processed, parser_options()).collect();
 
-    let parser = Parser::new_ext(&processed, options);
+    // Give every heading an anchor so TOC entries can link to it
+    assign_heading_ids(&mut parsed);
 
     // Transform events for syntax highlighting and custom rendering
     let mut in_code_block = false;
     let mut code_lang = String::new();
+    let mut code_buffer = String::new();
     let mut events = Vec::new();
 
-    for event in parser {
+    for event in parsed {
         match event {
             Event::Start(Tag::CodeBlock(kind)) => {
                 in_code_block = true;
@@ -116,7 +355,7 @@ pub fn render_obsidian_markdown(content: &str) -> String {
                         r#"<div class="code-block" data-lang="{}">
                             <div class="code-header">
                                 <span class="code-lang">{}</span>
-                                <button class="code-copy" onclick="copyCode(this)" aria-label="Copy code">
+                                <button class="code-copy" aria-label="Copy code">
                                     <span class="copy-icon"></span>
                                 </button>
                             </div>
@@ -129,11 +368,13 @@ pub fn render_obsidian_markdown(content: &str) -> String {
             }
             Event::End(TagEnd::CodeBlock) => {
                 in_code_block = false;
-                code_lang.clear();
+                events.push(Event::Html(highlight_code(&code_buffer, &code_lang).into()));
                 events.push(Event::Html("</code></pre></div>".into()));
+                code_buffer.clear();
+                code_lang.clear();
             }
             Event::Text(text) if in_code_block => {
-                events.push(Event::Html(escape_html(&text).into()));
+                code_buffer.push_str(&text);
             }
             Event::Code(code) => {
                 events.push(Event::Html(
@@ -146,61 +387,431 @@ pub fn render_obsidian_markdown(content: &str) -> String {
 
     let mut html_output = String::new();
     html::push_html(&mut html_output, events.into_iter());
+    clock.lap("parse");
 
     // Post-process for callouts and other Obsidian features
-    let html_output = postprocess_callouts(&html_output);
+    let html_output = postprocess_callouts(&html_output, opts.max_callout_depth);
+    clock.lap("callouts");
     let html_output = postprocess_highlights(&html_output);
+    clock.lap("highlights");
     let html_output = postprocess_mermaid_diagrams(&html_output);
+    clock.lap("mermaid");
+    let html_output = postprocess_table_alignment(&html_output);
+    clock.lap("tables");
 
     // Sanitize HTML while preserving our custom elements
-    sanitize_html(&html_output)
+    let html_output = sanitize_html(&html_output, opts);
+    clock.lap("sanitize");
+
+    (html_output, clock.phases)
 }
 
-/// Pre-process Obsidian-specific syntax before parsing
-fn preprocess_obsidian_syntax(content: &str) -> String {
-    let mut processed = content.to_string();
+/// Parser options shared by rendering and structural helpers
+fn parser_options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_SMART_PUNCTUATION);
+    options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    options
+}
 
-    // Process wiki-links [[Page]] or [[Page|Display Text]]
-    let wiki_link_re = Regex::new(r"\[\[([^\]|]+)(?:\|([^\]]+))?\]\]").unwrap();
-    processed = wiki_link_re
-        .replace_all(&processed, |caps: &regex::Captures| {
-            let link = &caps[1];
-            let display = caps.get(2).map(|m| m.as_str()).unwrap_or(link);
-            let slug = slugify(link);
-            format!(
-                r#"<a href="/blogs/{}" class="wiki-link" data-page="{}"><span class="link-icon"></span> {}</a>"#,
-                slug, link, display
-            )
-        })
-        .to_string();
+/// A heading entry in a post's table of contents
+#[derive(Debug, Clone, Serialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+}
 
-    // Process tags #tag
-    let tag_re = Regex::new(r"(?:^|\s)#([a-zA-Z][a-zA-Z0-9_-]*)").unwrap();
-    processed = tag_re
-        .replace_all(&processed, |caps: &regex::Captures| {
-            let tag = &caps[1];
-            format!(
-                r#" <span class="obsidian-tag" data-tag="{}"><span class="tag-icon"></span>{}</span>"#,
-                tag, tag
-            )
-        })
-        .to_string();
+/// A heading with the deeper headings under it
+#[derive(Debug, Clone, Serialize)]
+pub struct OutlineNode {
+    #[serde(flatten)]
+    pub heading: Heading,
+    pub children: Vec<OutlineNode>,
+}
 
-    // Process block IDs ^block-id
-    let block_id_re = Regex::new(r"\^([a-zA-Z0-9-]+)$").unwrap();
-    processed = block_id_re
-        .replace_all(&processed, |caps: &regex::Captures| {
-            let block_id = &caps[1];
-            format!(
-                r#"<span class="block-ref" id="block-{}" data-block-id="{}"></span>"#,
-                block_id, block_id
-            )
+/// Nest a flat TOC: each heading owns the deeper headings that follow it
+pub fn outline_tree(headings: Vec<Heading>) -> Vec<OutlineNode> {
+    fn attach(stack: &mut [OutlineNode], roots: &mut Vec<OutlineNode>, node: OutlineNode) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<OutlineNode> = Vec::new();
+    for heading in headings {
+        while stack.last().is_some_and(|open| open.heading.level >= heading.level) {
+            let done = stack.pop().expect("checked above");
+            attach(&mut stack, &mut roots, done);
+        }
+        stack.push(OutlineNode {
+            heading,
+            children: Vec::new(),
+        });
+    }
+    while let Some(done) = stack.pop() {
+        attach(&mut stack, &mut roots, done);
+    }
+    roots
+}
+
+/// Structural counts for the editor's outline panel
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DocumentCounts {
+    /// Prose words; code blocks are left out
+    pub words: usize,
+    pub code_blocks: usize,
+    /// Markdown images and embedded image assets
+    pub images: usize,
+    /// Markdown links and wiki-links
+    pub links: usize,
+}
+
+/// Count words, code blocks, images and links without rendering
+pub fn document_counts(content: &str) -> DocumentCounts {
+    let refs = link_refs(content);
+    let mut counts = DocumentCounts {
+        images: refs.iter().filter(|r| r.is_image()).count(),
+        links: refs.iter().filter(|r| !r.embed).count(),
+        ..DocumentCounts::default()
+    };
+
+    let mut prose = String::new();
+    let mut in_code_block = false;
+    for event in Parser::new_ext(content, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                counts.code_blocks += 1;
+                in_code_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::Image { .. }) => counts.images += 1,
+            Event::Start(Tag::Link { .. }) => counts.links += 1,
+            Event::Text(text) | Event::Code(text) if !in_code_block => prose.push_str(&text),
+            Event::SoftBreak
+            | Event::HardBreak
+            | Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell) => {
+                prose.push(' ')
+            }
+            _ => {}
+        }
+    }
+    counts.words = count_words(&prose);
+    counts
+}
+
+/// Build a table of contents from headings up to `max_depth` (1 = h1 only).
+/// Deeper headings are left out of the TOC but still get anchors when rendered.
+pub fn build_toc(content: &str, max_depth: u8) -> Vec<Heading> {
+    let processed = preprocess_obsidian_syntax(content);
+    let mut parsed: Vec<Event> = Parser::new_ext(&processed, parser_options()).collect();
+    assign_heading_ids(&mut parsed);
+
+    let mut toc = Vec::new();
+    for (i, event) in parsed.iter().enumerate() {
+        if let Event::Start(Tag::Heading { level, id: Some(id), .. }) = event {
+            let level = *level as u8;
+            if level <= max_depth {
+                toc.push(Heading {
+                    level,
+                    text: heading_text(&parsed[i + 1..]),
+                    slug: id.to_string(),
+                });
+            }
+        }
+    }
+    toc
+}
+
+/// Extract the H2–H4 headings used for a post's sidebar TOC.
+/// Slugs match the ids injected by `render_obsidian_markdown`.
+pub fn extract_headings(content: &str) -> Vec<Heading> {
+    build_toc(content, 4)
+        .into_iter()
+        .filter(|h| h.level >= 2)
+        .collect()
+}
+
+/// Heading id used when the text has nothing to slugify (emoji, punctuation, ...)
+const FALLBACK_HEADING_ID: &str = "section";
+
+/// Fill in slugified ids for headings without an explicit `{#id}`.
+/// Repeated heading texts get unique ids by appending -2, -3, ...
+/// Ids depend only on the content, so the same markdown always renders the same anchors.
+fn assign_heading_ids(events: &mut [Event]) {
+    // Explicit ids anywhere in the document win, even over an earlier auto id
+    let mut used: HashSet<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => Some(id.to_string()),
+            _ => None,
         })
-        .to_string();
+        .collect();
+
+    for i in 0..events.len() {
+        match &events[i] {
+            Event::Start(Tag::Heading { id: None, .. }) => {
+                let mut base = slugify(&heading_text(&events[i + 1..]));
+                if base.is_empty() {
+                    base = FALLBACK_HEADING_ID.to_string();
+                }
+
+                let mut slug = base.clone();
+                let mut n = 2;
+                while used.contains(&slug) {
+                    slug = format!("{}-{}", base, n);
+                    n += 1;
+                }
+                used.insert(slug.clone());
+
+                if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+                    *id = Some(slug.into());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collect the plain text of a heading from the events following its start tag
+fn heading_text(events: &[Event]) -> String {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::End(TagEnd::Heading(_)) => break,
+            Event::Text(t) | Event::Code(t) => text.push_str(t),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+/// Highlight a code block into class-annotated spans.
+/// Unknown languages (and mermaid, which is rendered client-side) fall back to escaped text.
+fn highlight_code(code: &str, lang: &str) -> String {
+    if lang.is_empty() || lang == "mermaid" {
+        return escape_html(code);
+    }
+
+    let Some(syntax) = SYNTAX_SET.find_syntax_by_token(lang) else {
+        return escape_html(code);
+    };
+
+    let mut generator =
+        ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, HIGHLIGHT_CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        if generator.parse_html_for_line_which_includes_newline(line).is_err() {
+            return escape_html(code);
+        }
+    }
+    generator.finalize()
+}
+
+/// Split a leading `---` delimited YAML front-matter block from the body.
+/// Returns `(yaml, body)`, or None when the content has no front-matter.
+pub fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The post fields an Obsidian note's front-matter can supply
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Frontmatter {
+    pub title: Option<String>,
+    /// `tags: [a, b]` or `tags: a, b`; a leading `#` is dropped
+    pub tags: Vec<String>,
+    /// `excerpt:`, or `description:` as many Obsidian themes call it
+    pub excerpt: Option<String>,
+}
+
+/// Strip and parse a leading YAML front-matter block.
+/// Returns the parsed block (None when there is none) and the body without it.
+/// A `---` block that isn't a YAML mapping is left in place: it's content, not metadata.
+pub fn parse_frontmatter(content: &str) -> (Option<Frontmatter>, String) {
+    let Some((yaml, body)) = split_frontmatter(content) else {
+        return (None, content.to_string());
+    };
+
+    let mapping = match serde_yaml::from_str::<serde_yaml::Value>(yaml) {
+        Ok(serde_yaml::Value::Mapping(mapping)) => mapping,
+        Ok(serde_yaml::Value::Null) => serde_yaml::Mapping::new(),
+        _ => return (None, content.to_string()),
+    };
+
+    let text = |key: &str| {
+        mapping
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let tags = match mapping.get("tags") {
+        Some(serde_yaml::Value::Sequence(tags)) => {
+            tags.iter().filter_map(|t| t.as_str()).map(str::to_string).collect()
+        }
+        Some(serde_yaml::Value::String(tags)) => tags.split(',').map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+
+    let frontmatter = Frontmatter {
+        title: text("title"),
+        tags: tags
+            .iter()
+            .map(|t| t.trim().trim_start_matches('#').to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        excerpt: text("excerpt").or_else(|| text("description")),
+    };
+    (Some(frontmatter), body.to_string())
+}
+
+/// The body without its front-matter, which is metadata and never rendered or counted
+fn strip_frontmatter(content: &str) -> std::borrow::Cow<'_, str> {
+    match parse_frontmatter(content) {
+        (Some(_), body) => body.into(),
+        (None, _) => content.into(),
+    }
+}
+
+/// Front-matter keys required in strict mode
+pub const REQUIRED_FRONTMATTER: [&str; 3] = ["title", "tags", "date"];
+
+/// Validate front-matter strictly: every required key present with the right type.
+/// Returns one error per missing or invalid field (empty when valid).
+pub fn validate_frontmatter_strict(content: &str) -> Vec<FieldError> {
+    let Some((yaml, _)) = split_frontmatter(content) else {
+        return vec![FieldError::new("frontmatter", "front-matter block is required")];
+    };
+
+    let mapping = match serde_yaml::from_str::<serde_yaml::Value>(yaml) {
+        Ok(serde_yaml::Value::Mapping(mapping)) => mapping,
+        Ok(_) => return vec![FieldError::new("frontmatter", "front-matter must be a mapping")],
+        Err(e) => return vec![FieldError::new("frontmatter", format!("invalid YAML: {}", e))],
+    };
+
+    let mut errors = Vec::new();
+    for key in REQUIRED_FRONTMATTER {
+        let Some(value) = mapping.get(key) else {
+            errors.push(FieldError::new(key, "is required"));
+            continue;
+        };
+
+        let valid = match key {
+            "title" => value.as_str().is_some_and(|t| !t.trim().is_empty()),
+            "tags" => value
+                .as_sequence()
+                .is_some_and(|tags| tags.iter().all(|t| t.is_string())),
+            "date" => value.as_str().is_some_and(|d| {
+                chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_ok()
+                    || chrono::DateTime::parse_from_rfc3339(d).is_ok()
+            }),
+            _ => true,
+        };
+
+        if !valid {
+            let expected = match key {
+                "title" => "must be a non-empty string",
+                "tags" => "must be a list of strings",
+                _ => "must be a YYYY-MM-DD or RFC 3339 date",
+            };
+            errors.push(FieldError::new(key, expected));
+        }
+    }
+    errors
+}
+
+/// `$$display$$` and `$inline$` math; inline code is matched first so it is left alone.
+/// A closing `$` followed by a digit is treated as currency, not math.
+static MATH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    compile_regex(
+        r"(?P<code>`[^`\n]*`)|\$\$(?P<display>[\s\S]+?)\$\$|\$(?P<inline>[^\s$](?:[^$\n]*[^\s$])?)\$(?P<after>\d?)",
+    )
+});
+
+/// Turn math into placeholders carrying the TeX in `data-math` for the client to typeset.
+/// Runs before any other preprocessing and before parsing, so markdown emphasis,
+/// list and callout handling never see the TeX itself.
+fn preprocess_math(content: &str) -> String {
+    replace_outside_fences(content, &MATH_RE, |caps| {
+        if caps.name("code").is_some() || caps.name("after").is_some_and(|m| !m.is_empty()) {
+            return caps[0].to_string();
+        }
+
+        if let Some(display) = caps.name("display") {
+            // Continuation lines of math inside a blockquote carry the `>` marker
+            let tex = display
+                .as_str()
+                .lines()
+                .map(|line| line.trim_start().strip_prefix('>').map_or(line, str::trim_start))
+                .collect::<Vec<_>>()
+                .join("\n");
+            return format!(
+                r#"<div class="math math-display" data-math="{}"></div>"#,
+                escape_math(tex.trim())
+            );
+        }
+
+        format!(
+            r#"<span class="math math-inline" data-math="{}"></span>"#,
+            escape_math(&caps["inline"])
+        )
+    })
+}
+
+/// Escape TeX for an attribute, also hiding characters the Obsidian regexes react to
+fn escape_math(tex: &str) -> String {
+    escape_html(tex)
+        .replace('#', "&#35;")
+        .replace('[', "&#91;")
+        .replace(']', "&#93;")
+        .replace('^', "&#94;")
+        .replace('=', "&#61;")
+}
+
+/// The part of a page an embed points at
+#[derive(Debug, PartialEq, Eq)]
+enum EmbedTarget<'a> {
+    Section(&'a str),
+    Block(&'a str),
+}
+
+/// Split `Page#Heading`, `Page#^block` or `Page^block` into the page and its target
+fn split_embed_target(resource: &str) -> (&str, Option<EmbedTarget<'_>>) {
+    let Some(at) = resource.find(['#', '^']) else {
+        return (resource, None);
+    };
+    let page = resource[..at].trim();
+    let target = match resource[at..].trim_start_matches('#') {
+        fragment if fragment.starts_with('^') => EmbedTarget::Block(fragment[1..].trim()),
+        heading => EmbedTarget::Section(heading.trim()),
+    };
+    (page, Some(target))
+}
+
+/// Pre-process Obsidian-specific syntax before parsing
+fn preprocess_obsidian_syntax(content: &str) -> String {
+    let mut processed = preprocess_math(&strip_frontmatter(content));
 
-    // Process embedded content ![[Image]] or ![[Page]]
-    let embed_re = Regex::new(r"!\[\[([^\]]+)\]\]").unwrap();
-    processed = embed_re
+    // Process embedded content ![[Image]] or ![[Page]], before wiki-links claim the [[...]]
+    // Page embeds keep a `#heading` or `^block` target for later transclusion
+    processed = EMBED_RE
         .replace_all(&processed, |caps: &regex::Captures| {
             let resource = &caps[1];
             if is_image(resource) {
@@ -210,60 +821,192 @@ fn preprocess_obsidian_syntax(content: &str) -> String {
                     resource
                 )
             } else {
+                let (page, target) = split_embed_target(resource);
+                let target_attr = match target {
+                    Some(EmbedTarget::Section(heading)) => format!(r#" data-section="{}""#, heading),
+                    Some(EmbedTarget::Block(id)) => format!(r#" data-block="{}""#, id),
+                    None => String::new(),
+                };
                 format!(
-                    r#"<div class="obsidian-embed" data-page="{}"><span class="embed-icon"></span> {}</div>"#,
-                    resource, resource
+                    r#"<div class="obsidian-embed" data-page="{}"{}><span class="embed-icon"></span> {}</div>"#,
+                    // `#` is escaped so the label can't be mistaken for a tag
+                    page,
+                    target_attr,
+                    resource.replace('#', "&#35;")
                 )
             }
         })
         .to_string();
 
+    // Process wiki-links [[Page]] or [[Page|Display Text]]
+    processed = WIKI_LINK_RE
+        .replace_all(&processed, |caps: &regex::Captures| {
+            let link = &caps[1];
+            let display = caps.get(2).map(|m| m.as_str()).unwrap_or(link);
+            let slug = slugify(link);
+            format!(
+                r#"<a href="/blogs/{}" class="wiki-link" data-page="{}"><span class="link-icon"></span> {}</a>"#,
+                slug, link, display
+            )
+        })
+        .to_string();
+
+    // Process tags #tag, leaving code (CSS colors, URL fragments) alone
+    // Nested tags use `/` between segments (#programming/rust)
+    processed = replace_outside_fences(&processed, &OBSIDIAN_TAG_RE, |caps| {
+        let Some(tag) = caps.name("tag") else {
+            return caps[0].to_string();
+        };
+        let tag = tag.as_str();
+        format!(
+            r#" <span class="obsidian-tag" data-tag="{}"><span class="tag-icon"></span>{}</span>"#,
+            tag, tag
+        )
+    });
+
+    // Process block IDs ^block-id at the end of any line
+    processed = replace_outside_fences(&processed, &BLOCK_ID_RE, |caps| {
+        let block_id = &caps[1];
+        format!(
+            r#"<span class="block-ref" id="block-{}" data-block-id="{}"></span>"#,
+            block_id, block_id
+        )
+    });
+
     processed
 }
 
-/// Process callouts in the HTML output
-fn postprocess_callouts(html: &str) -> String {
-    // Match blockquotes that start with [!type]
-    let callout_re =
-        Regex::new(r#"<blockquote>\s*<p>\[!([^\]]+)\](?:\s+(.+?))?</p>([\s\S]*?)</blockquote>"#)
-            .unwrap();
+const BLOCKQUOTE_OPEN: &str = "<blockquote>";
+const BLOCKQUOTE_CLOSE: &str = "</blockquote>";
 
-    callout_re
-        .replace_all(html, |caps: &regex::Captures| {
-            let callout_type = CalloutType::from_str(&caps[1]);
-            let title = caps.get(2).map(|m| m.as_str()).unwrap_or(&caps[1]);
-            let content = &caps[3];
+/// Process callouts in the HTML output.
+/// Blockquotes are matched by nesting depth so bodies may hold several paragraphs,
+/// lists or further (callout) blockquotes; past `max_depth` they are left as they are.
+fn postprocess_callouts(html: &str, max_depth: usize) -> String {
+    callouts_at_depth(html, 0, max_depth)
+}
 
-            format!(
-                r#"<div class="callout callout-{}" data-callout-type="{}">
+fn callouts_at_depth(html: &str, depth: usize, max_depth: usize) -> String {
+    if depth >= max_depth {
+        if html.contains(BLOCKQUOTE_OPEN) {
+            tracing::warn!(
+                "Blockquotes nested deeper than {} levels rendered without callout processing",
+                max_depth
+            );
+        }
+        return html.to_string();
+    }
+
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(BLOCKQUOTE_OPEN) {
+        output.push_str(&rest[..start]);
+        let body_start = start + BLOCKQUOTE_OPEN.len();
+        let Some(body_len) = blockquote_body_len(&rest[body_start..]) else {
+            // Unbalanced markup: leave the remainder as it is
+            output.push_str(&rest[start..]);
+            return output;
+        };
+
+        let body = callouts_at_depth(&rest[body_start..body_start + body_len], depth + 1, max_depth);
+        match render_callout(&body) {
+            Some(callout) => output.push_str(&callout),
+            None => {
+                output.push_str(BLOCKQUOTE_OPEN);
+                output.push_str(&body);
+                output.push_str(BLOCKQUOTE_CLOSE);
+            }
+        }
+        rest = &rest[body_start + body_len + BLOCKQUOTE_CLOSE.len()..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Upper-case the first character
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Length of a blockquote's body, up to the `</blockquote>` that closes it
+fn blockquote_body_len(html: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut pos = 0;
+    loop {
+        let close = pos + html[pos..].find(BLOCKQUOTE_CLOSE)?;
+        match html[pos..].find("<blockquote").map(|i| pos + i) {
+            Some(open) if open < close => {
+                depth += 1;
+                pos = open + "<blockquote".len();
+            }
+            _ if depth == 0 => return Some(close),
+            _ => {
+                depth -= 1;
+                pos = close + BLOCKQUOTE_CLOSE.len();
+            }
+        }
+    }
+}
+
+/// Render a blockquote body as a callout if it opens with `[!type]`.
+/// `[!type]-` starts collapsed, `[!type]+` starts expanded.
+fn render_callout(body: &str) -> Option<String> {
+    let caps = CALLOUT_RE.captures(body)?;
+    let callout_type = CalloutType::from_str(&caps[1]);
+    let collapsed = &caps[2] == "-";
+    // Untitled callouts (including GitHub's `[!NOTE]` alerts) are titled by their type
+    let title = match caps[3].trim() {
+        "" => capitalize(&caps[1].to_lowercase()),
+        title => title.to_string(),
+    };
+
+    // Text after the title line continues the first paragraph
+    let after = &body[caps.get(0).map_or(0, |m| m.end())..];
+    let content = if &caps[4] == "\n" {
+        format!("<p>{}", after)
+    } else {
+        after.to_string()
+    };
+
+    Some(format!(
+                r#"<div class="callout callout-{}{}" data-callout-type="{}">
                 <div class="callout-header">
                     <span class="callout-icon">{}</span>
                     <span class="callout-title">{}</span>
-                    <button class="callout-fold" onclick="toggleCallout(this)" aria-label="Toggle callout">
+                    <button class="callout-fold" aria-label="Toggle callout">
                         <span class="fold-icon"></span>
                     </button>
                 </div>
                 <div class="callout-content">{}</div>
             </div>"#,
-                callout_type.color, callout_type.name, callout_type.icon, title, content
-            )
-        })
-        .to_string()
+        callout_type.color,
+        if collapsed { " is-collapsed" } else { "" },
+        callout_type.name,
+        callout_type.icon,
+        title,
+        content
+    ))
 }
 
-/// Process highlighting syntax ==text==
+/// Process highlighting syntax ==text==, except inside inline or fenced code
 fn postprocess_highlights(html: &str) -> String {
-    let highlight_re = Regex::new(r"==(.*?)==").unwrap();
-    highlight_re
-        .replace_all(html, r#"<mark class="obsidian-highlight">$1</mark>"#)
+    HTML_HIGHLIGHT_RE
+        .replace_all(html, |caps: &regex::Captures| match caps.name("text") {
+            Some(text) => format!(r#"<mark class="obsidian-highlight">{}</mark>"#, text.as_str()),
+            None => caps[0].to_string(),
+        })
         .to_string()
 }
 
 /// Process Mermaid diagrams
 fn postprocess_mermaid_diagrams(html: &str) -> String {
-    let mermaid_re =
-        Regex::new(r#"<pre><code class="language-mermaid">([\s\S]*?)</code></pre>"#).unwrap();
-    mermaid_re
+    MERMAID_RE
         .replace_all(html, |caps: &regex::Captures| {
             let diagram = &caps[1];
             format!(
@@ -277,10 +1020,27 @@ fn postprocess_mermaid_diagrams(html: &str) -> String {
         .to_string()
 }
 
+/// Elements pulldown-cmark emits for GFM tables
+const TABLE_TAGS: [&str; 6] = ["table", "thead", "tbody", "tr", "th", "td"];
+
+/// Convert pulldown-cmark's inline alignment styles on table cells into classes,
+/// since the sanitizer strips `style` attributes
+fn postprocess_table_alignment(html: &str) -> String {
+    TABLE_ALIGN_RE
+        .replace_all(html, r#"<$1 class="text-$2">"#)
+        .to_string()
+}
+
 /// Calculate reading time from content
 pub fn calculate_reading_time(content: &str) -> String {
-    let word_count = content.split_whitespace().count();
-    let reading_time = (word_count as f64 / 200.0).ceil() as u32; // 200 words per minute
+    calculate_reading_time_with(content, DEFAULT_WORDS_PER_MINUTE)
+}
+
+/// Reading time at `words_per_minute` for prose.
+/// - Fenced code is skimmed faster, at CODE_WORDS_PER_MINUTE
+/// - Each image adds SECONDS_PER_IMAGE
+pub fn calculate_reading_time_with(content: &str, words_per_minute: u32) -> String {
+    let reading_time = reading_minutes(content, words_per_minute);
 
     if reading_time <= 1 {
         "1 min read".to_string()
@@ -289,34 +1049,94 @@ pub fn calculate_reading_time(content: &str) -> String {
     }
 }
 
-/// Extract plain text excerpt from markdown
-pub fn extract_excerpt(content: &str, max_length: usize) -> String {
-    // Remove Obsidian-specific syntax first
-    let mut plain = content.to_string();
+/// Whole minutes behind `calculate_reading_time_with`, never less than 1
+pub fn reading_minutes(content: &str, words_per_minute: u32) -> u32 {
+    let (prose, code) = split_fenced_code(&strip_frontmatter(content));
 
-    // Remove wiki links
-    let wiki_link_re = Regex::new(r"\[\[([^\]|]+)(?:\|([^\]]+))?\]\]").unwrap();
-    plain = wiki_link_re.replace_all(&plain, "$2").to_string();
+    // Images are timed separately, so their syntax doesn't count as words
+    let images = READING_IMAGE_RE
+        .captures_iter(&prose)
+        .filter(|caps| caps.get(1).is_none_or(|resource| is_image(resource.as_str())))
+        .count();
+    let prose = READING_IMAGE_RE.replace_all(&prose, " ");
 
-    // Remove tags
-    let tag_re = Regex::new(r"#[a-zA-Z][a-zA-Z0-9_-]*").unwrap();
-    plain = tag_re.replace_all(&plain, "").to_string();
+    let prose_minutes = count_words(&prose) as f64 / f64::from(words_per_minute.max(1));
+    let code_minutes = count_words(&code) as f64 / CODE_WORDS_PER_MINUTE;
+    let image_minutes = images as f64 * SECONDS_PER_IMAGE / 60.0;
 
-    // Remove highlights
-    let highlight_re = Regex::new(r"==(.*?)==").unwrap();
-    plain = highlight_re.replace_all(&plain, "$1").to_string();
+    ((prose_minutes + code_minutes + image_minutes).ceil() as u32).max(1)
+}
 
-    // Parse as markdown to get plain text
-    let parser = Parser::new(&plain);
-    let mut plain_text = String::new();
+/// Split markdown into (prose, fenced code) by toggling on ``` / ~~~ fences
+fn split_fenced_code(content: &str) -> (String, String) {
+    let mut prose = String::new();
+    let mut code = String::new();
+    let mut in_fence = false;
 
-    for event in parser {
-        match event {
-            Event::Text(text) => plain_text.push_str(&text),
-            Event::SoftBreak | Event::HardBreak => plain_text.push(' '),
-            _ => {}
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+
+        let target = if in_fence { &mut code } else { &mut prose };
+        target.push_str(line);
+        target.push('\n');
+    }
+
+    (prose, code)
+}
+
+/// Apply a regex replacement to everything except fenced code blocks,
+/// which are copied through verbatim (fence lines included)
+fn replace_outside_fences(
+    content: &str,
+    re: &Regex,
+    replacement: impl Fn(&regex::Captures) -> String,
+) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut prose = String::new();
+    let mut in_fence = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+
+        if in_fence || is_fence {
+            output.push_str(&re.replace_all(&prose, &replacement));
+            prose.clear();
+            output.push_str(line);
+            if is_fence {
+                in_fence = !in_fence;
+            }
+        } else {
+            prose.push_str(line);
         }
     }
+    output.push_str(&re.replace_all(&prose, &replacement));
+
+    output
+}
+
+/// Prose reading speed used when none is configured
+pub const DEFAULT_WORDS_PER_MINUTE: u32 = 200;
+/// Code is skimmed rather than read word by word
+const CODE_WORDS_PER_MINUTE: f64 = 500.0;
+/// Time spent looking at each image
+const SECONDS_PER_IMAGE: f64 = 12.0;
+
+/// Count whitespace-separated words in the raw markdown body
+pub fn count_words(content: &str) -> usize {
+    content.split_whitespace().count()
+}
+
+/// Bodies with less plain text than this get no auto-excerpt by default
+pub const DEFAULT_EXCERPT_MIN_BODY_CHARS: usize = 160;
+
+/// Extract plain text excerpt from markdown
+pub fn extract_excerpt(content: &str, max_length: usize) -> String {
+    let plain_text = plain_text(content);
 
     // Truncate to max length at word boundary
     if plain_text.len() <= max_length {
@@ -333,13 +1153,79 @@ pub fn extract_excerpt(content: &str, max_length: usize) -> String {
     }
 }
 
-/// Extract all tags from markdown content
+/// Generate an excerpt for a post that has none.
+/// Returns None when the body has fewer than `min_body_chars` characters of
+/// plain text, where the excerpt would just repeat the body (and often the title).
+pub fn auto_excerpt(content: &str, min_body_chars: usize, max_length: usize) -> Option<String> {
+    let body = strip_first_heading(&strip_frontmatter(content));
+    if plain_text(&body).trim().chars().count() < min_body_chars {
+        return None;
+    }
+
+    Some(extract_excerpt(&body, max_length))
+}
+
+/// Normalize a submitted excerpt.
+/// Blank or whitespace-only excerpts count as missing and are generated from
+/// the body; bodies too short for `auto_excerpt` leave it empty.
+pub fn normalize_excerpt(excerpt: &str, body: &str, min_body_chars: usize) -> String {
+    let trimmed = excerpt.trim();
+    if !trimmed.is_empty() {
+        return trimmed.to_string();
+    }
+
+    auto_excerpt(body, min_body_chars, 200).unwrap_or_default()
+}
+
+/// Plain text of a markdown body with Obsidian syntax removed
+fn plain_text(content: &str) -> String {
+    // Remove Obsidian-specific syntax first
+    let mut plain = content.to_string();
+
+    // Remove wiki links
+    plain = WIKI_LINK_RE.replace_all(&plain, "$2").to_string();
+
+    // Remove tags (including nested ones)
+    plain = TAG_NAME_RE.replace_all(&plain, "").to_string();
+
+    // Remove highlights
+    plain = HIGHLIGHT_RE.replace_all(&plain, "$1").to_string();
+
+    // Parse as markdown to get plain text
+    let parser = Parser::new(&plain);
+    let mut plain_text = String::new();
+
+    for event in parser {
+        match event {
+            Event::Text(text) => plain_text.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => plain_text.push(' '),
+            _ => {}
+        }
+    }
+
+    plain_text
+}
+
+/// Extract all tags from markdown content: the `#tags` the renderer shows as tags,
+/// so none from code, URL fragments or mid-word `#`s
 pub fn extract_tags(content: &str) -> Vec<String> {
-    let tag_re = Regex::new(r"#([a-zA-Z][a-zA-Z0-9_-]*)").unwrap();
     let mut tags = HashSet::new();
+    let mut in_fence = false;
 
-    for cap in tag_re.captures_iter(content) {
-        tags.insert(cap[1].to_string());
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for cap in OBSIDIAN_TAG_RE.captures_iter(line) {
+            if let Some(tag) = cap.name("tag") {
+                tags.insert(tag.as_str().to_string());
+            }
+        }
     }
 
     tags.into_iter().collect()
@@ -347,16 +1233,104 @@ pub fn extract_tags(content: &str) -> Vec<String> {
 
 /// Extract all wiki-links from markdown content
 pub fn extract_links(content: &str) -> Vec<String> {
-    let wiki_link_re = Regex::new(r"\[\[([^\]|]+)(?:\|[^\]]+)?\]\]").unwrap();
     let mut links = HashSet::new();
 
-    for cap in wiki_link_re.captures_iter(content) {
+    for cap in WIKI_LINK_RE.captures_iter(content) {
         links.insert(cap[1].to_string());
     }
 
     links.into_iter().collect()
 }
 
+/// A wiki-link or embed found by `link_refs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkRef {
+    pub line: usize, // 1-based
+    pub target: String,
+    pub embed: bool,
+}
+
+impl LinkRef {
+    /// Embedded images resolve to assets; everything else resolves to a post
+    pub fn is_image(&self) -> bool {
+        self.embed && is_image(&self.target)
+    }
+}
+
+/// Lines outside fenced code blocks, numbered from 1
+fn prose_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut in_fence = false;
+    content.lines().enumerate().filter_map(move |(i, line)| {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            return None;
+        }
+        (!in_fence).then_some((i + 1, line))
+    })
+}
+
+/// Every wiki-link and `![[embed]]` outside code, with its line number
+pub fn link_refs(content: &str) -> Vec<LinkRef> {
+    prose_lines(content)
+        .flat_map(|(line, text)| {
+            LINK_REF_RE
+                .captures_iter(text)
+                .map(|caps| LinkRef {
+                    line,
+                    target: caps[2].trim().to_string(),
+                    embed: !caps[1].is_empty(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Lint warnings that need no database: unknown callout types and skipped heading levels
+pub fn lint_structure(content: &str) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let mut previous_level: Option<usize> = None;
+
+    for (line, text) in prose_lines(content) {
+        if let Some(caps) = LINT_CALLOUT_RE.captures(text) {
+            if CalloutType::lookup(&caps[1]).is_none() {
+                warnings.push(LintWarning {
+                    line,
+                    kind: LintKind::UnknownCallout,
+                    message: format!("Unknown callout type '{}' renders as a plain note", &caps[1]),
+                });
+            }
+        }
+
+        if let Some(caps) = ATX_HEADING_RE.captures(text) {
+            let level = caps[1].len();
+            if let Some(previous) = previous_level {
+                if level > previous + 1 {
+                    warnings.push(LintWarning {
+                        line,
+                        kind: LintKind::SkippedHeadingLevel,
+                        message: format!("Heading jumps from h{} to h{}", previous, level),
+                    });
+                }
+            }
+            previous_level = Some(level);
+        }
+    }
+
+    warnings
+}
+
+/// Find the post's cover image: the first `![alt](url)` or `![[image.png]]` in the body
+pub fn extract_cover_image(content: &str) -> Option<String> {
+    COVER_IMAGE_RE.captures_iter(content).find_map(|caps| {
+        if let Some(url) = caps.get(1) {
+            return Some(url.as_str().to_string());
+        }
+        let resource = caps.get(2)?.as_str();
+        is_image(resource).then(|| format!("/api/assets/{}", slugify(resource)))
+    })
+}
+
 /// Strip the first heading (h1) from markdown content
 pub fn strip_first_heading(content: &str) -> String {
     if content.starts_with("# ") {
@@ -367,8 +1341,18 @@ pub fn strip_first_heading(content: &str) -> String {
     content.to_string()
 }
 
+/// Strip the first heading only when it repeats the post title (compared slugified).
+/// Imported posts often open with a real section heading, which is kept.
+pub fn strip_title_heading(content: &str, title: &str) -> String {
+    let first_line = content.lines().next().unwrap_or("");
+    match first_line.strip_prefix("# ") {
+        Some(heading) if slugify(heading) == slugify(title) => strip_first_heading(content),
+        _ => content.to_string(),
+    }
+}
+
 /// Convert a title to a URL slug
-fn slugify(text: &str) -> String {
+pub fn slugify(text: &str) -> String {
     text.to_lowercase()
         .chars()
         .map(|c| if c.is_alphanumeric() { c } else { '-' })
@@ -379,6 +1363,11 @@ fn slugify(text: &str) -> String {
         .join("-")
 }
 
+/// The post slug a `[[Post]]` target points at; a `#Heading` part still resolves to the post
+pub fn link_slug(target: &str) -> String {
+    slugify(target.split('#').next().unwrap_or(target))
+}
+
 /// Check if a resource name is an image
 fn is_image(resource: &str) -> bool {
     let lower = resource.to_lowercase();
@@ -401,70 +1390,371 @@ fn escape_html(input: &str) -> String {
         .replace('\'', "&#39;")
 }
 
-/// Sanitize HTML while preserving Obsidian elements
-fn sanitize_html(html: &str) -> String {
-    let mut builder = Builder::default();
+/// Attributes the renderer emits, by tag (`class` is governed by ALLOWED_CLASSES)
+const ALLOWED_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href", "data-page"]),
+    ("span", &["data-tag", "data-block-id", "data-math", "id"]),
+    ("div", &[
+        "data-page", "data-section", "data-block", "data-callout-type", "data-lang", "data-diagram",
+        "data-math",
+    ]),
+    // No inline event handlers: the frontend binds copy/fold buttons by class
+    ("button", &["aria-label"]),
+    ("img", &["src", "alt", "loading"]),
+    ("h1", &["id"]),
+    ("h2", &["id"]),
+    ("h3", &["id"]),
+    ("h4", &["id"]),
+    ("h5", &["id"]),
+    ("h6", &["id"]),
+];
+
+/// Media tags and attributes trusted authors may use in raw HTML
+const TRUSTED_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("iframe", &[
+        "src", "title", "width", "height", "allow", "allowfullscreen", "frameborder", "loading",
+    ]),
+    ("video", &["src", "controls", "poster", "width", "height"]),
+    ("audio", &["src", "controls"]),
+    ("source", &["src", "type"]),
+];
 
-    // Allow our custom elements and attributes
-    use std::collections::HashMap;
-    use std::collections::HashSet;
-
-    let mut tag_attributes = HashMap::new();
-    tag_attributes.insert("a", HashSet::from(["data-page"]));
-    tag_attributes.insert("span", HashSet::from(["data-tag", "data-block-id", "id"]));
-    tag_attributes.insert("div", HashSet::from(["data-page", "data-callout-type", "data-lang", "data-diagram"]));
-    tag_attributes.insert("button", HashSet::from(["onclick", "aria-label"]));
-    tag_attributes.insert("img", HashSet::from(["src", "alt", "loading"]));
-
-    let mut allowed_classes = HashMap::new();
-    allowed_classes.insert("a", HashSet::from(["wiki-link"]));
-    allowed_classes.insert("span", HashSet::from([
-        "inline-code", "bold", "italic", "strikethrough", "highlight",
-        "fold-icon", "loading-icon"
-    ]));
-    let mut div_classes = HashSet::from([
-        "obsidian-embed", "callout", "callout-header", "callout-content",
-        "code-block", "code-header", "mermaid-diagram", "mermaid-loading",
-        "mermaid-content"
-    ]);
-
-    // Add callout color classes
-    let callout_class_names = [
+/// Fixed classes the renderer emits, by tag. Per-document classes (syntax highlight
+/// tokens, code languages) are collected from the HTML by `sanitizer`.
+const ALLOWED_CLASSES: &[(&str, &[&str])] = &[
+    ("a", &["wiki-link"]),
+    ("span", &[
+        "inline-code", "bold", "italic", "strikethrough", "highlight", "math", "math-inline",
+        // Icons and labels inside wiki-links, tags, embeds, callouts, code blocks and diagrams
+        "link-icon", "obsidian-tag", "tag-icon", "block-ref", "embed-icon", "callout-icon",
+        "callout-title", "fold-icon", "code-lang", "copy-icon", "loading-icon",
+    ]),
+    ("div", &[
+        "obsidian-embed", "callout", "callout-header", "callout-content", "is-collapsed",
+        "code-block", "code-header", "mermaid-diagram", "mermaid-loading", "mermaid-content",
+        "math", "math-display",
+        // Callout colors
         "callout-rosewater", "callout-flamingo", "callout-pink", "callout-mauve", "callout-red",
         "callout-maroon", "callout-peach", "callout-yellow", "callout-green", "callout-teal",
-        "callout-sky", "callout-sapphire", "callout-blue", "callout-lavender", "callout-surface2"
-    ];
-    for class_name in callout_class_names {
-        div_classes.insert(class_name);
+        "callout-sky", "callout-sapphire", "callout-blue", "callout-lavender", "callout-surface2",
+    ]),
+    ("button", &["callout-fold", "code-copy"]),
+    ("code", &["inline-code"]),
+    ("mark", &["obsidian-highlight"]),
+    ("img", &["obsidian-embed-image"]),
+    ("th", &["text-left", "text-center", "text-right"]),
+    ("td", &["text-left", "text-center", "text-right"]),
+];
+
+/// Language classes on fenced code (`language-rust`)
+static CODE_LANGUAGE_CLASS_RE: LazyLock<Regex> =
+    LazyLock::new(|| compile_regex(r"\blanguage-[a-zA-Z0-9_+#-]+"));
+
+/// The sanitizer for `html` rendered with `opts`.
+/// The allowlist is the constants above: anything a pre- or postprocessing pass adds
+/// (a class, an attribute, a new element) must be listed there too, or it is stripped.
+/// `test_sanitizer_keeps_every_custom_element` renders one of each to catch omissions.
+pub fn sanitizer<'a>(html: &'a str, opts: &'a RenderOptions) -> Builder<'a> {
+    let mut builder = Builder::default();
+
+    // Buttons aren't in ammonia's defaults; tables are listed so they don't rely on them
+    builder.add_tags(["button"]).add_tags(TABLE_TAGS);
+
+    let mut tag_attributes: HashMap<&str, HashSet<&str>> = ALLOWED_ATTRIBUTES
+        .iter()
+        .map(|(tag, attrs)| (*tag, attrs.iter().copied().collect()))
+        .collect();
+    if opts.profile == SanitizerProfile::Trusted {
+        tag_attributes.extend(
+            TRUSTED_ATTRIBUTES
+                .iter()
+                .map(|(tag, attrs)| (*tag, attrs.iter().copied().collect())),
+        );
+        builder.add_tags(TRUSTED_ATTRIBUTES.iter().map(|(tag, _)| *tag));
     }
 
-    allowed_classes.insert("div", div_classes);
-    allowed_classes.insert("button", HashSet::from(["callout-fold", "code-copy"]));
-    allowed_classes.insert("code", HashSet::from(["inline-code"]));
-    allowed_classes.insert("mark", HashSet::from(["obsidian-highlight"]));
-    allowed_classes.insert("img", HashSet::from(["obsidian-embed-image"]));
+    let mut allowed_classes: HashMap<&str, HashSet<&str>> = ALLOWED_CLASSES
+        .iter()
+        .map(|(tag, classes)| (*tag, classes.iter().copied().collect()))
+        .collect();
+    // Highlight and language classes depend on the grammar, so allow whichever were emitted
+    if let Some(spans) = allowed_classes.get_mut("span") {
+        spans.extend(HIGHLIGHT_CLASS_RE.find_iter(html).map(|m| m.as_str()));
+    }
+    if let Some(code) = allowed_classes.get_mut("code") {
+        code.extend(CODE_LANGUAGE_CLASS_RE.find_iter(html).map(|m| m.as_str()));
+    }
+
+    let url_schemes: HashSet<&str> = opts
+        .url_schemes
+        .iter()
+        .map(String::as_str)
+        .filter(|scheme| !BLOCKED_URL_SCHEMES.contains(scheme))
+        .collect();
 
     builder
         .link_rel(Some("noopener noreferrer"))
+        .url_schemes(url_schemes)
         .url_relative(UrlRelative::PassThrough)
         .tag_attributes(tag_attributes)
         .allowed_classes(allowed_classes);
+    builder
+}
 
-    // Callout color classes are now added to allowed_classes above
-
-    builder.clean(html).to_string()
+/// Sanitize HTML while preserving Obsidian elements
+pub fn sanitize_html(html: &str, opts: &RenderOptions) -> String {
+    sanitizer(html, opts).clean(html).to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Attribute text of every tag in `html`, with quoted values removed
+    /// so only attribute names (and unquoted junk) remain
+    fn tag_attribute_names(html: &str) -> Vec<String> {
+        let tag_re = Regex::new(r"<[a-zA-Z][^>]*>").unwrap();
+        let quoted_re = Regex::new(r#""[^"]*"|'[^']*'"#).unwrap();
+        tag_re
+            .find_iter(html)
+            .map(|tag| quoted_re.replace_all(tag.as_str(), "").to_lowercase())
+            .collect()
+    }
+
+    /// XSS invariants every rendered document must satisfy
+    fn assert_no_script_vectors(html: &str) {
+        let lower = html.to_lowercase();
+        assert!(!lower.contains("<script"), "script tag survived: {}", html);
+
+        let handler_re = Regex::new(r"\son[a-z]+\s*=").unwrap();
+        for attrs in tag_attribute_names(html) {
+            assert!(!handler_re.is_match(&attrs), "event handler survived: {}", html);
+        }
+
+        let js_url_re = Regex::new(r#"(?i)(href|src|action|formaction|poster)\s*=\s*["']?\s*javascript:"#).unwrap();
+        assert!(!js_url_re.is_match(html), "javascript: URL survived: {}", html);
+    }
+
+    /// Markdown and HTML fragments, benign and hostile, for the fuzzer to combine
+    const FUZZ_FRAGMENTS: &[&str] = &[
+        "# Heading\n",
+        "> [!note] Title\n> body\n",
+        "> [!warning]- Folded\n",
+        "```js\nalert(1)\n```\n",
+        "```mermaid\ngraph TD; A-->B\n```\n",
+        "[[Page]]",
+        "[[Page|Alias]]",
+        "![[image.png]]",
+        "![[Note]]",
+        "#tag ",
+        "#nested/tag ",
+        "==highlight==",
+        "| a | b |\n|:--|--:|\n| 1 | 2 |\n",
+        "[link](https://example.com)",
+        "[js](javascript:alert(1))",
+        "[js](JaVaScRiPt:alert(1))",
+        "![img](javascript:alert(1))",
+        "<script>alert(1)</script>",
+        "<SCRIPT SRC=//evil.example/x.js></SCRIPT>",
+        "<img src=x onerror=alert(1)>",
+        "<a href=\"javascript:alert(1)\">x</a>",
+        "<button onclick=\"alert(1)\">b</button>",
+        "<div onmouseover='alert(1)'>d</div>",
+        "<svg onload=alert(1)>",
+        "<iframe src=\"javascript:alert(1)\"></iframe>",
+        "<video poster=javascript:alert(1)></video>",
+        "[[x\" onclick=\"alert(1)]]",
+        "> [!note] <img src=x onerror=alert(1)>\n",
+        "```\" onmouseover=\"alert(1)\nx\n```\n",
+        "\n\n",
+        " ",
+    ];
+
+    proptest::proptest! {
+        #[test]
+        fn fuzz_rendered_html_has_no_script_vectors(
+            picks in proptest::collection::vec(0..FUZZ_FRAGMENTS.len(), 0..12),
+            noise in "\\PC{0,40}",
+            trusted in proptest::bool::ANY,
+        ) {
+            let mut doc: String = picks.iter().map(|&i| FUZZ_FRAGMENTS[i]).collect();
+            doc.push_str(&noise);
+
+            let profile = if trusted { SanitizerProfile::Trusted } else { SanitizerProfile::Safe };
+            let html = render_obsidian_markdown_with(&doc, &RenderOptions { profile, ..Default::default() });
+            assert_no_script_vectors(&html);
+        }
+    }
+
+    #[test]
+    fn test_known_xss_payloads_are_neutralised() {
+        for fragment in FUZZ_FRAGMENTS {
+            for profile in [SanitizerProfile::Safe, SanitizerProfile::Trusted] {
+                let html = render_obsidian_markdown_with(fragment, &RenderOptions { profile, ..Default::default() });
+                assert_no_script_vectors(&html);
+            }
+        }
+    }
+
     #[test]
     fn test_wiki_links() {
         let content = "Check out [[My Page]] and [[Other Page|this link]]";
         let processed = preprocess_obsidian_syntax(content);
         assert!(processed.contains(r#"href="/blogs/my-page""#));
         assert!(processed.contains("this link</a>"));
+
+        assert_eq!(link_slug("My Page#Setup Steps"), "my-page");
+        assert_eq!(link_slug("My Page"), "my-page");
+    }
+
+    #[test]
+    fn test_block_ids_on_interior_lines() {
+        let content = "First paragraph ^intro\n\nSecond paragraph ^second-para\n\nClosing line";
+        let processed = preprocess_obsidian_syntax(content);
+        assert!(processed.contains(r#"data-block-id="intro""#));
+        assert!(processed.contains(r#"data-block-id="second-para""#));
+        assert!(processed.ends_with("Closing line"));
+    }
+
+    #[test]
+    fn test_block_ids_on_first_middle_and_last_lines() {
+        let content = "Opening ^first\nMiddle ^middle\nClosing ^last";
+        let processed = preprocess_obsidian_syntax(content);
+        for id in ["first", "middle", "last"] {
+            assert!(processed.contains(&format!(r#"id="block-{}""#, id)), "missing {}", id);
+        }
+        assert_eq!(processed.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_block_ids_ignored_in_code_blocks() {
+        let content = "Text ^real\n\n```\nlet x = y ^fake\n```\n\n~~~\n2 ^other\n~~~";
+        let processed = preprocess_obsidian_syntax(content);
+        assert!(processed.contains(r#"data-block-id="real""#));
+        assert!(processed.contains("let x = y ^fake\n"));
+        assert!(processed.contains("2 ^other\n"));
+    }
+
+    #[test]
+    fn test_inline_math_inside_callout() {
+        let content = "> [!note] Pythagoras\n>\n> We have $a^2 + b_1 * b_2 = c^2$ here.";
+        let html = render_obsidian_markdown(content);
+        assert!(html.contains("callout-content"));
+        assert!(html.contains(r#"data-math="a^2 + b_1 * b_2 = c^2""#), "{}", html);
+        assert!(!html.contains("<em>"));
+        assert!(!html.contains("block-ref"));
+    }
+
+    #[test]
+    fn test_math_in_list_items_and_currency() {
+        let html = render_obsidian_markdown("- first $x*y*z$\n- costs $5 and $10\n- `$not math$`");
+        assert!(html.contains(r#"data-math="x*y*z""#), "{}", html);
+        assert!(html.contains("costs $5 and $10"));
+        assert_eq!(html.matches("data-math").count(), 1);
+
+        let display = render_obsidian_markdown("> [!tip]\n> $$\n> \\sum_i x_i\n> $$");
+        assert!(display.contains(r#"class="math math-display""#), "{}", display);
+        assert!(display.contains(r#"data-math="\sum_i x_i""#), "{}", display);
+    }
+
+    #[test]
+    fn test_link_schemes() {
+        let html = render_obsidian_markdown(
+            "[bad](javascript:alert(1)) [mail](mailto:me@example.com) [call](tel:+123) [[Other Post]]",
+        );
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains(r#"href="mailto:me@example.com""#));
+        assert!(!html.contains("tel:"));
+        assert!(html.contains(r#"href="/blogs/other-post""#));
+
+        let schemes = parse_url_schemes("https, tel:, JavaScript, data");
+        assert_eq!(schemes, vec!["https", "tel"]);
+        let opts = RenderOptions::default().with_url_schemes(&schemes);
+        let html = render_obsidian_markdown_with("[call](tel:+123) [x](http://a.example)", &opts);
+        assert!(html.contains(r#"href="tel:+123""#));
+        assert!(!html.contains("http://a.example"));
+    }
+
+    #[test]
+    fn test_outline_tree_and_counts() {
+        let content = "# Guide\n\nIntro words here.\n\n## Setup\n\n### Install\n\n### Configure\n\n## Usage\n\n```rust\nfn main() { println!(\"skipped\"); }\n```\n\n![diagram](/img/flow.png) and ![[shot.png]] see [docs](https://example.com) or [[Other Post]].";
+        let outline = outline_tree(build_toc(content, 6));
+
+        let shape: Vec<(&str, Vec<(&str, usize)>)> = outline[0]
+            .children
+            .iter()
+            .map(|child| {
+                (
+                    child.heading.text.as_str(),
+                    child.children.iter().map(|c| (c.heading.text.as_str(), c.children.len())).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].heading.text, "Guide");
+        assert_eq!(
+            shape,
+            vec![("Setup", vec![("Install", 0), ("Configure", 0)]), ("Usage", vec![])]
+        );
+
+        let counts = document_counts(content);
+        assert_eq!(counts.code_blocks, 1);
+        assert_eq!(counts.images, 2);
+        assert_eq!(counts.links, 2);
+        assert_eq!(document_counts("# Hi\n\nOne two three.\n\n```\nlet skipped = 1;\n```").words, 4);
+    }
+
+    #[test]
+    fn test_title_heading_stripped_only_when_it_matches() {
+        let content = "# Hello, World!\n\nFirst paragraph.";
+        assert_eq!(strip_title_heading(content, "Hello World"), "First paragraph.");
+
+        let imported = "# Background\n\nFirst paragraph.";
+        assert_eq!(strip_title_heading(imported, "Hello World"), imported);
+    }
+
+    #[test]
+    fn test_lint_structure() {
+        let content = "# Title\n\n> [!note] Fine\n> [!bogus] Odd\n\n#### Too deep\n\n```\n> [!nope]\n## code\n```\n## Back\n### Ok";
+        let warnings = lint_structure(content);
+        let found: Vec<(usize, LintKind)> = warnings.iter().map(|w| (w.line, w.kind)).collect();
+        assert_eq!(
+            found,
+            vec![(4, LintKind::UnknownCallout), (6, LintKind::SkippedHeadingLevel)]
+        );
+    }
+
+    #[test]
+    fn test_link_refs_track_lines() {
+        let content = "See [[First Post]]\n\n```\n[[Not A Link]]\n```\n![[diagram.png|300]] and ![[Other Note]]";
+        let refs = link_refs(content);
+        assert_eq!(refs.len(), 3);
+        assert_eq!((refs[0].line, refs[0].target.as_str(), refs[0].is_image()), (1, "First Post", false));
+        assert_eq!((refs[1].line, refs[1].target.as_str(), refs[1].is_image()), (6, "diagram.png", true));
+        assert_eq!((refs[2].line, refs[2].embed, refs[2].is_image()), (6, true, false));
+    }
+
+    #[test]
+    fn test_rendering_reuses_compiled_regexes() {
+        let post = "# Title\n\nSee [[Other|that]] and #rust ==hi== ^id\n\n![[a.png]] $x$\n\n> [!note] N\n> body\n\n```mermaid\ngraph TD; A-->B\n```\n\n| a |\n|:-:|\n| 1 |\n\n```rust\nfn main() {}\n```";
+        let exercise = || {
+            render_obsidian_markdown(post);
+            extract_tags(post);
+            extract_links(post);
+            extract_excerpt(post, 100);
+            extract_cover_image(post);
+            calculate_reading_time(post);
+            link_refs(post);
+            lint_structure(post);
+        };
+
+        // The first pass may compile each pattern once; later passes must not
+        exercise();
+        let compiled = REGEX_COMPILES.load(std::sync::atomic::Ordering::Relaxed);
+        for _ in 0..3 {
+            exercise();
+        }
+        assert_eq!(REGEX_COMPILES.load(std::sync::atomic::Ordering::Relaxed), compiled);
     }
 
     #[test]
@@ -474,6 +1764,114 @@ mod tests {
         assert_eq!(tags.len(), 2);
         assert!(tags.contains(&"important".to_string()));
         assert!(tags.contains(&"urgent".to_string()));
+
+        // Only what the renderer turns into tags
+        let content = "#first line, `#inline`, a [link](/page#section) and C#sharp.\n\n\
+                       ```css\n#header { color: #fff; }\n```\n";
+        assert_eq!(extract_tags(content), vec!["first"]);
+        let html = render_obsidian_markdown(content);
+        assert_eq!(html.matches("obsidian-tag").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn test_embed_targets() {
+        assert_eq!(split_embed_target("Note"), ("Note", None));
+        assert_eq!(
+            split_embed_target("Note#Setup Steps"),
+            ("Note", Some(EmbedTarget::Section("Setup Steps")))
+        );
+        assert_eq!(split_embed_target("Note#^abc123"), ("Note", Some(EmbedTarget::Block("abc123"))));
+        assert_eq!(split_embed_target("Note^abc123"), ("Note", Some(EmbedTarget::Block("abc123"))));
+
+        let html = render_obsidian_markdown("![[Note#Setup]]\n\n![[Note^abc123]]\n\n![[Note]]\n");
+        assert!(html.contains(r#"data-page="Note" data-section="Setup""#), "{}", html);
+        assert!(html.contains(r#"data-page="Note" data-block="abc123""#), "{}", html);
+        assert_eq!(html.matches("obsidian-embed").count(), 3);
+        assert!(!html.contains("wiki-link"));
+
+        // Embed markup survives the sanitizer, image embeds included
+        let html = render_obsidian_markdown("![[diagram.png]]\n\n![[Other Note]]\n");
+        for class in ["obsidian-embed-image", "obsidian-embed", "embed-icon"] {
+            let kept = html.split('"').any(|value| value.split(' ').any(|c| c == class));
+            assert!(kept, "class {} was stripped from {}", class, html);
+        }
+    }
+
+    #[test]
+    fn test_tags_and_highlights_skip_code() {
+        let content = "A #real tag and ==marked== text.\n\n\
+            Inline `#FF0000` and `a == b == c` stay as code.\n\n\
+            ```css\n.alert { color: #FF0000; }\n```\n\n\
+            ```rust\nassert!(x == y == z);\n```\n";
+        let html = render_obsidian_markdown(content);
+
+        assert!(html.contains(r#"data-tag="real""#));
+        assert!(html.contains(r#"<mark class="obsidian-highlight">marked</mark>"#));
+        assert!(html.contains(r#"<code class="inline-code">#FF0000</code>"#));
+        assert!(html.contains(r#"<code class="inline-code">a == b == c</code>"#));
+        assert!(html.contains("FF0000"));
+        assert_eq!(html.matches("obsidian-tag").count(), 1, "{}", html);
+        assert_eq!(html.matches("<mark").count(), 1, "{}", html);
+    }
+
+    #[test]
+    fn test_nested_tags() {
+        let tags = extract_tags("Notes on #programming/rust and #misc/");
+        assert!(tags.contains(&"programming/rust".to_string()));
+        assert!(tags.contains(&"misc".to_string()));
+
+        let html = render_obsidian_markdown("Filed under #programming/rust");
+        assert!(html.contains(r#"data-tag="programming/rust""#));
+    }
+
+    #[test]
+    fn test_multi_paragraph_callout() {
+        let content = "> [!warning] Careful\n> First paragraph.\n>\n> Second paragraph.\n>\n> - a list item\n>\n> > nested quote\n\nAfter.";
+        let html = render_obsidian_markdown(content);
+        assert!(html.contains("callout-yellow"), "{}", html);
+        assert!(html.contains(r#"<span class="callout-title">Careful</span>"#), "{}", html);
+
+        let body = &html[html.find("callout-content").unwrap()..];
+        let after = body.find("After.").unwrap();
+        for part in ["<p>First paragraph.</p>", "<p>Second paragraph.</p>", "a list item", "<blockquote>"] {
+            let at = body.find(part).unwrap_or_else(|| panic!("missing {} in {}", part, html));
+            assert!(at < after, "{} escaped the callout", part);
+        }
+        assert!(!html.contains("is-collapsed"));
+    }
+
+    #[test]
+    fn test_callout_nesting_beyond_limit_stays_plain() {
+        let html = render_obsidian_markdown_with(
+            "> [!note] Outer\n> > [!tip] Inner\n> > > [!warning] Too deep",
+            &RenderOptions::default().with_max_callout_depth(2),
+        );
+        assert!(html.contains("callout-blue"), "{}", html);
+        assert!(html.contains("callout-teal"), "{}", html);
+        assert!(!html.contains("callout-yellow"), "{}", html);
+        assert!(html.contains("[!warning] Too deep"), "{}", html);
+
+        // Far past the limit: no runaway recursion, and the markup survives intact
+        let depth = 5_000;
+        let nested = format!(
+            "{}<p>[!note] bottom</p>{}",
+            BLOCKQUOTE_OPEN.repeat(depth),
+            BLOCKQUOTE_CLOSE.repeat(depth)
+        );
+        let processed = postprocess_callouts(&nested, DEFAULT_MAX_CALLOUT_DEPTH);
+        assert_eq!(processed, nested);
+    }
+
+    #[test]
+    fn test_collapsed_callout() {
+        let html = render_obsidian_markdown("> [!note]- Hidden by default\n> Body text.");
+        assert!(html.contains("callout callout-blue is-collapsed"), "{}", html);
+        assert!(html.contains(r#"<span class="callout-title">Hidden by default</span>"#));
+        assert!(html.contains("<p>Body text.</p>"));
+
+        let html = render_obsidian_markdown("> [!note]+ Open\n> Body text.");
+        assert!(html.contains("callout callout-blue\""), "{}", html);
+        assert!(!html.contains("is-collapsed"));
     }
 
     #[test]
@@ -483,6 +1881,287 @@ mod tests {
 
         let warning = CalloutType::from_str("warning");
         assert_eq!(warning.color, "yellow");
+
+        for (alert, name) in [
+            ("NOTE", "note"),
+            ("TIP", "tip"),
+            ("IMPORTANT", "important"),
+            ("WARNING", "warning"),
+            ("CAUTION", "caution"),
+        ] {
+            assert_eq!(CalloutType::lookup(alert).map(|c| c.name), Some(name));
+        }
+    }
+
+    #[test]
+    fn test_github_alert_titled_by_type() {
+        let html = render_obsidian_markdown("> [!WARNING]\n> Mind the gap.");
+        assert!(html.contains("callout-yellow"), "{}", html);
+        assert!(html.contains(r#"<span class="callout-title">Warning</span>"#), "{}", html);
+        assert!(html.contains("<p>Mind the gap.</p>"));
+
+        let html = render_obsidian_markdown("> [!CAUTION]\n> Irreversible.");
+        assert!(html.contains(r#"data-callout-type="caution""#), "{}", html);
+        assert!(html.contains(r#"<span class="callout-title">Caution</span>"#));
+    }
+
+    #[test]
+    fn test_trusted_html_depends_on_author_role() {
+        let content = r#"<iframe src="https://www.youtube.com/embed/abc"></iframe>"#;
+
+        let admin = render_obsidian_markdown_with(content, &RenderOptions::for_author(Role::Admin, true));
+        assert!(admin.contains("<iframe"));
+
+        let author = render_obsidian_markdown_with(content, &RenderOptions::for_author(Role::Author, true));
+        assert!(!author.contains("<iframe"));
+
+        let not_opted_in = render_obsidian_markdown_with(content, &RenderOptions::for_author(Role::Admin, false));
+        assert!(!not_opted_in.contains("<iframe"));
+    }
+
+    #[test]
+    fn test_toc_max_depth() {
+        let content = "# Title\n\n## Section\n\n### Detail\n\n#### Deep\n";
+        let toc = build_toc(content, 2);
+        let levels: Vec<u8> = toc.iter().map(|h| h.level).collect();
+        assert_eq!(levels, vec![1, 2]);
+        assert_eq!(toc[1].slug, "section");
+
+        // Excluded headings still get anchors
+        let html = render_obsidian_markdown(content);
+        assert!(html.contains(r#"<h3 id="detail">"#));
+        assert!(html.contains(r#"<h4 id="deep">"#));
+    }
+
+    #[test]
+    fn test_code_highlighting() {
+        let rust = render_obsidian_markdown("```rust\nfn main() {}\n```\n");
+        assert!(rust.contains(r#"<span class="hl-"#));
+
+        let unknown = render_obsidian_markdown("```nosuchlang\na < b\n```\n");
+        assert!(unknown.contains("a &lt; b"));
+        assert!(!unknown.contains("hl-"));
+    }
+
+    #[test]
+    fn test_table_alignment_classes() {
+        let content = "| Item | Price |\n| ---- | ----: |\n| Tea | 3 |\n";
+        let html = render_obsidian_markdown(content);
+        assert!(html.contains(r#"<th class="text-right">Price</th>"#));
+        assert!(html.contains(r#"<td class="text-right">3</td>"#));
+        assert!(!html.contains("style="));
+    }
+
+    #[test]
+    fn test_sanitizer_keeps_every_custom_element() {
+        let content = "> [!note]- Folded\n> Callout body\n\n\
+            A [[Wiki Page]] link, a #topic tag, ==highlighted== text and $x^2$ inline. ^block-1\n\n\
+            ```mermaid\ngraph TD; A-->B\n```\n\n\
+            ```rust\nfn main() { let x = 1; }\n```\n";
+        let html = render_obsidian_markdown(content);
+
+        for class in [
+            "callout", "callout-header", "callout-icon", "callout-title", "callout-fold",
+            "fold-icon", "callout-content", "is-collapsed", "wiki-link", "link-icon",
+            "obsidian-tag", "tag-icon", "obsidian-highlight", "math-inline", "block-ref",
+            "mermaid-diagram", "mermaid-loading", "mermaid-content", "loading-icon", "code-block",
+            "code-header", "code-lang", "code-copy", "copy-icon", "language-rust",
+        ] {
+            let kept = html.split('"').any(|value| value.split(' ').any(|c| c == class));
+            assert!(kept, "class {} was stripped from {}", class, html);
+        }
+        for attr in [
+            "data-callout-type", "data-page", "data-tag", "data-math", "data-block-id",
+            "data-diagram", "data-lang", "aria-label",
+        ] {
+            assert!(html.contains(attr), "attribute {} was stripped from {}", attr, html);
+        }
+        assert!(html.contains("<button"), "buttons were stripped from {}", html);
+        assert!(html.contains("hl-"), "highlight classes were stripped from {}", html);
+    }
+
+    #[test]
+    fn test_table_alignment_survives_sanitizing() {
+        let content = "| Left | Center | Right |\n| :--- | :----: | ----: |\n| a | b | c |\n";
+        let html = render_obsidian_markdown(content);
+        for tag in TABLE_TAGS {
+            assert!(html.contains(&format!("<{}", tag)), "missing <{}> in {}", tag, html);
+        }
+        assert!(html.contains(r#"<th class="text-left">Left</th>"#));
+        assert!(html.contains(r#"<th class="text-center">Center</th>"#));
+        assert!(html.contains(r#"<th class="text-right">Right</th>"#));
+        assert!(html.contains(r#"<td class="text-left">a</td>"#));
+        assert!(html.contains(r#"<td class="text-center">b</td>"#));
+        assert!(html.contains(r#"<td class="text-right">c</td>"#));
+    }
+
+    #[test]
+    fn test_extract_headings_unique_slugs() {
+        let content = "# Title\n\n## Setup\n\n### Install\n\n## Setup\n\n##### Too deep\n";
+        let headings = extract_headings(content);
+        let slugs: Vec<&str> = headings.iter().map(|h| h.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["setup", "install", "setup-2"]);
+
+        let html = render_obsidian_markdown(content);
+        assert!(html.contains(r#"<h2 id="setup">"#));
+        assert!(html.contains(r#"<h2 id="setup-2">"#));
+    }
+
+    #[test]
+    fn test_every_heading_gets_a_stable_id() {
+        // A later explicit id keeps its name; the earlier auto id steps aside
+        let content = "## Intro
+
+## !!!
+
+## About {#intro}
+";
+        let html = render_obsidian_markdown(content);
+        assert!(html.contains(r#"<h2 id="intro-2">Intro</h2>"#), "{}", html);
+        assert!(html.contains(r#"<h2 id="section">"#), "{}", html);
+        assert!(html.contains(r#"<h2 id="intro">About</h2>"#), "{}", html);
+
+        assert_eq!(html, render_obsidian_markdown(content));
+        let slugs: Vec<String> = build_toc(content, 6).into_iter().map(|h| h.slug).collect();
+        assert_eq!(slugs, vec!["intro-2", "section", "intro"]);
+    }
+
+    #[test]
+    fn test_render_timings_cover_all_phases() {
+        let (html, timings) = render_with_timings("# Hi\n\n> [!note]\n> ==body==", &RenderOptions::default());
+        assert!(!html.is_empty());
+
+        let phases: Vec<&str> = timings.iter().map(|(phase, _)| *phase).collect();
+        assert_eq!(
+            phases,
+            vec!["preprocess", "parse", "callouts", "highlights", "mermaid", "tables", "sanitize"]
+        );
+        assert!(timings.iter().all(|(_, d)| d.as_secs_f64() >= 0.0));
+    }
+
+    #[test]
+    fn test_strict_frontmatter() {
+        let valid = "---\ntitle: Hello\ntags: [rust]\ndate: 2024-05-01\n---\nBody";
+        assert!(validate_frontmatter_strict(valid).is_empty());
+
+        let missing_tags = "---\ntitle: Hello\ndate: 2024-05-01\n---\nBody";
+        let errors = validate_frontmatter_strict(missing_tags);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "tags");
+
+        let none = validate_frontmatter_strict("Just a body");
+        assert_eq!(none[0].field, "frontmatter");
+    }
+
+    #[test]
+    fn test_parse_frontmatter() {
+        let note = "---\ntitle: Ownership\ntags: [rust, '#memory']\ndescription: Who frees what\n\
+                    published: true\n---\n# Ownership\n\nBody text.";
+        let (frontmatter, body) = parse_frontmatter(note);
+        assert_eq!(
+            frontmatter,
+            Some(Frontmatter {
+                title: Some("Ownership".to_string()),
+                tags: vec!["rust".to_string(), "memory".to_string()],
+                excerpt: Some("Who frees what".to_string()),
+            })
+        );
+        assert_eq!(body, "# Ownership\n\nBody text.");
+
+        let (frontmatter, _) = parse_frontmatter("---\ntags: rust, wasm\n---\nBody");
+        assert_eq!(frontmatter.unwrap().tags, vec!["rust", "wasm"]);
+
+        // Not YAML metadata: left alone
+        let prose = "---\nJust a rule: then text\nand more\n---\nBody";
+        assert_eq!(parse_frontmatter(prose), (None, prose.to_string()));
+        assert_eq!(parse_frontmatter("Plain").0, None);
+
+        // The block never reaches the rendered page
+        let html = render_obsidian_markdown(note);
+        assert!(!html.contains("description"), "{}", html);
+        assert!(html.contains("Body text."));
+    }
+
+    #[test]
+    fn test_extract_cover_image() {
+        assert_eq!(
+            extract_cover_image("Intro\n\n![Cover](https://example.com/a.png \"title\")\n![[b.png]]"),
+            Some("https://example.com/a.png".to_string())
+        );
+        assert_eq!(
+            extract_cover_image("![[Other Note]]\n![[Hero Shot.png]]"),
+            Some("/api/assets/hero-shot-png".to_string())
+        );
+        assert_eq!(extract_cover_image("No images here"), None);
+    }
+
+    #[test]
+    fn test_auto_excerpt_skips_short_bodies() {
+        let one_sentence = "# Hello\n\nJust a quick note.";
+        assert_eq!(auto_excerpt(one_sentence, DEFAULT_EXCERPT_MIN_BODY_CHARS, 200), None);
+
+        let long = format!("# Hello\n\n{}", "Plenty of words to summarise here. ".repeat(10));
+        let excerpt = auto_excerpt(&long, DEFAULT_EXCERPT_MIN_BODY_CHARS, 200).unwrap();
+        assert!(excerpt.starts_with("Plenty of words"));
+        assert!(excerpt.chars().count() <= 203);
+    }
+
+    #[test]
+    fn test_blank_excerpt_is_generated() {
+        let body = format!("# Imported\n\n{}", "An imported post with a real body of text. ".repeat(8));
+
+        let generated = normalize_excerpt("   \n", &body, DEFAULT_EXCERPT_MIN_BODY_CHARS);
+        assert!(generated.starts_with("An imported post"));
+
+        assert_eq!(normalize_excerpt("  Kept as written ", &body, DEFAULT_EXCERPT_MIN_BODY_CHARS), "Kept as written");
+    }
+
+    #[tokio::test]
+    async fn test_slow_render_hits_deadline() {
+        let slow = || {
+            std::thread::sleep(Duration::from_millis(300));
+            "<p>late</p>".to_string()
+        };
+        let result = run_with_deadline(Duration::from_millis(20), slow).await;
+        assert!(matches!(result, Err(AppError::ServiceUnavailable(_))));
+
+        let html = render_with_deadline("Quick *post*".to_string(), RenderOptions::default(), DEFAULT_RENDER_TIMEOUT)
+            .await
+            .unwrap();
+        assert!(html.contains("<em>post</em>"));
+    }
+
+    #[tokio::test]
+    async fn test_blocking_renders_leave_runtime_responsive() {
+        let heavy = || {
+            std::thread::sleep(Duration::from_millis(300));
+            String::new()
+        };
+        let renders: Vec<_> = (0..4)
+            .map(|_| tokio::spawn(run_with_deadline(DEFAULT_RENDER_TIMEOUT, heavy)))
+            .collect();
+
+        // A cheap task on the same (single-threaded) runtime still completes promptly
+        let started = Instant::now();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(started.elapsed() < Duration::from_millis(200));
+
+        for render in renders {
+            assert!(render.await.unwrap().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_markdown_size_limit() {
+        assert!(check_markdown_size(&"a".repeat(MAX_MARKDOWN_BYTES)).is_ok());
+        let result = check_markdown_size(&"a".repeat(MAX_MARKDOWN_BYTES + 1));
+        assert!(matches!(result, Err(AppError::BadRequest(ref m)) if m.contains("512 KB")));
+    }
+
+    #[tokio::test]
+    async fn test_render_panic_maps_to_internal_error() {
+        let result = run_with_deadline(DEFAULT_RENDER_TIMEOUT, || panic!("bad render")).await;
+        assert!(matches!(result, Err(AppError::Internal(_))));
     }
 
     #[test]
@@ -490,4 +2169,19 @@ mod tests {
         assert_eq!(calculate_reading_time("hello world"), "1 min read");
         assert_eq!(calculate_reading_time(&"word ".repeat(400)), "2 min read");
     }
+
+    #[test]
+    fn test_reading_time_discounts_code_and_counts_images() {
+        // 400 words of code alone read at 500 WPM, not 200
+        let code = format!("Intro\n\n```rust\n{}\n```\n", "let x = 1; ".repeat(100));
+        assert_eq!(calculate_reading_time(&code), "1 min read");
+
+        // 200 words of prose plus ten images (two extra minutes)
+        let images = "![[shot.png]]\n".repeat(5) + &"![alt](a.png)\n".repeat(5);
+        let post = format!("{}\n{}", "word ".repeat(200), images);
+        assert_eq!(calculate_reading_time(&post), "3 min read");
+
+        // Custom speed
+        assert_eq!(calculate_reading_time_with(&"word ".repeat(400), 100), "4 min read");
+    }
 }
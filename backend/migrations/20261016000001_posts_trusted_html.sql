@@ -0,0 +1,2 @@
+-- Per-post opt-in to the permissive (trusted) markdown sanitizer profile
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS trusted_html BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,4 @@
+-- Scheduled publishing: posts stay hidden until publish_at passes
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS publish_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS posts_publish_at_idx ON posts (publish_at) WHERE published = false;
@@ -0,0 +1,11 @@
+-- Outgoing wiki-links per post, used for backlinks
+CREATE TABLE IF NOT EXISTS post_links (
+    id UUID PRIMARY KEY,
+    from_post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    to_post_slug TEXT NOT NULL,
+    link_text TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS post_links_to_post_slug_idx ON post_links (to_post_slug);
+CREATE INDEX IF NOT EXISTS post_links_from_post_id_idx ON post_links (from_post_id);
@@ -0,0 +1,6 @@
+-- Multiple authors: admins manage every post, authors only their own
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'author'
+    CHECK (role IN ('admin', 'author'));
+
+-- The bootstrap account predates roles
+UPDATE users SET role = 'admin' WHERE username = 'admin';
@@ -0,0 +1,11 @@
+-- Autosaved edits, kept apart from the live post until it is published
+CREATE TABLE IF NOT EXISTS post_drafts (
+    id UUID PRIMARY KEY,
+    post_id UUID NOT NULL UNIQUE REFERENCES posts(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    excerpt TEXT NOT NULL DEFAULT '',
+    body TEXT NOT NULL,
+    author_id UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
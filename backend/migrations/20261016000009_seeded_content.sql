@@ -0,0 +1,3 @@
+-- Demo content created by the seed endpoint, so it can be removed without touching real posts
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS seeded BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE tags ADD COLUMN IF NOT EXISTS seeded BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- Live posts can opt out of search engine indexing
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS noindex BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,3 @@
+-- Read counts for the popular posts listing
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS view_count BIGINT NOT NULL DEFAULT 0;
+CREATE INDEX IF NOT EXISTS idx_posts_view_count ON posts (view_count DESC);
@@ -0,0 +1,9 @@
+-- Old slugs of renamed posts, so their URLs keep resolving.
+-- Redirects point at the post, not a slug, so chains of renames resolve in one hop.
+CREATE TABLE IF NOT EXISTS slug_redirects (
+    old_slug TEXT PRIMARY KEY,
+    post_id UUID NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS slug_redirects_post_id_idx ON slug_redirects (post_id);
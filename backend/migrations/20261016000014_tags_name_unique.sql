@@ -0,0 +1,24 @@
+-- Tag names are unique regardless of case or surrounding whitespace.
+-- Existing duplicates are merged into the oldest tag before the index is built.
+CREATE TEMPORARY TABLE tag_merges AS
+SELECT id, keep_id
+FROM (
+    SELECT id,
+           first_value(id) OVER (PARTITION BY lower(btrim(name)) ORDER BY created_at, id) AS keep_id
+    FROM tags
+) ranked
+WHERE id <> keep_id;
+
+INSERT INTO post_tags (post_id, tag_id)
+SELECT pt.post_id, m.keep_id
+FROM post_tags pt
+JOIN tag_merges m ON m.id = pt.tag_id
+ON CONFLICT DO NOTHING;
+
+DELETE FROM tags WHERE id IN (SELECT id FROM tag_merges);
+
+DROP TABLE tag_merges;
+
+UPDATE tags SET name = lower(btrim(name)) WHERE name <> lower(btrim(name));
+
+CREATE UNIQUE INDEX IF NOT EXISTS tags_name_lower_key ON tags (lower(name));
@@ -0,0 +1,13 @@
+-- Whether a post has ever been public. Incremental sync only reports the removal of posts
+-- mirrors could have seen; drafts and scheduled posts stay invisible until they go live.
+ALTER TABLE posts ADD COLUMN IF NOT EXISTS was_public BOOLEAN NOT NULL DEFAULT false;
+UPDATE posts SET was_public = true WHERE published_at IS NOT NULL AND published_at <= now();
+
+-- Purged posts that were once public, so sync can still report them as deleted
+CREATE TABLE IF NOT EXISTS post_tombstones (
+    post_id UUID PRIMARY KEY,
+    slug TEXT NOT NULL,
+    purged_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS post_tombstones_purged_at_idx ON post_tombstones (purged_at, post_id);
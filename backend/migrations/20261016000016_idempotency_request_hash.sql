@@ -0,0 +1,4 @@
+-- Tie each key to the request body it was first used with, and lease in-progress claims
+-- so a request that died mid-flight (timeout, dropped connection) doesn't block retries
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS request_hash TEXT NOT NULL DEFAULT '';
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ NOT NULL DEFAULT now();
@@ -1,5 +1,6 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use uuid::Uuid;
 
 // User model
@@ -9,10 +10,40 @@ pub struct User {
     pub username: String,
     #[serde(skip)]
     pub password_hash: String,
+    pub role: Role,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// User role.
+/// - Admins manage every post and user and may opt posts into trusted HTML
+/// - Authors only manage their own posts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    #[default]
+    Author,
+}
+
+impl Role {
+    /// Name stored in the `users.role` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Author => "author",
+        }
+    }
+
+    /// Parse a stored role name; unknown values get the least privilege
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "admin" => Role::Admin,
+            _ => Role::Author,
+        }
+    }
+}
+
 // Post models
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Post {
@@ -26,9 +57,64 @@ pub struct Post {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub author_id: Uuid,
+    pub trusted_html: bool, // Opt-in to the permissive sanitizer profile
+    pub noindex: bool, // Live but hidden from search engines
+    pub publish_at: Option<DateTime<Utc>>, // Scheduled go-live time
+    pub deleted_at: Option<DateTime<Utc>>, // Set while the post is in the trash
+    pub author: AuthorInfo,
     pub tags: Vec<Tag>,
 }
 
+impl Post {
+    /// Whether the post is visible on public routes (published, not trashed, not scheduled for later)
+    pub fn is_live(&self) -> bool {
+        self.published
+            && self.deleted_at.is_none()
+            && self.publish_at.is_none_or(|at| at <= Utc::now())
+    }
+
+    /// Latest moment the post changed as the public sees it: an edit, a trashing,
+    /// or a scheduled publish time passing
+    pub fn changed_at(&self) -> DateTime<Utc> {
+        let now = Utc::now();
+        [self.deleted_at, self.publish_at.filter(|at| *at <= now)]
+            .into_iter()
+            .flatten()
+            .fold(self.updated_at, DateTime::max)
+    }
+}
+
+/// A live, untagged post titled after its slug, for tests to adjust with `..post(..)`
+#[cfg(test)]
+pub(crate) fn post(slug: &str, body: &str) -> Post {
+    let now = Utc::now();
+    Post {
+        id: Uuid::new_v4(),
+        slug: slug.to_string(),
+        title: slug.to_string(),
+        excerpt: String::new(),
+        body: body.to_string(),
+        published: true,
+        published_at: Some(now),
+        created_at: now,
+        updated_at: now,
+        author_id: Uuid::new_v4(),
+        trusted_html: false,
+        noindex: false,
+        publish_at: None,
+        deleted_at: None,
+        author: Default::default(),
+        tags: vec![],
+    }
+}
+
+/// Public view of a post's author; deliberately only id and username
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthorInfo {
+    pub id: Uuid,
+    pub username: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostSummary {
     pub id: Uuid,
@@ -37,9 +123,22 @@ pub struct PostSummary {
     pub excerpt: String,
     pub published_at: DateTime<Utc>,
     pub reading_time: String,
+    pub author: AuthorInfo,
     pub tags: Vec<Tag>,
 }
 
+/// A post change for incremental sync
+#[derive(Debug, Clone, Serialize)]
+pub struct PostChange {
+    pub id: Uuid,
+    pub slug: String,
+    pub changed_at: DateTime<Utc>,
+    /// No longer public (trashed, unpublished, rescheduled or purged): mirrors should drop it
+    pub deleted: bool,
+    /// Current summary, for posts that are live
+    pub post: Option<PostSummary>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdminPostSummary {
     pub id: String,
@@ -49,25 +148,48 @@ pub struct AdminPostSummary {
     pub published_at: String,
     pub reading_time: String,
     pub tags: Vec<Tag>,
+    pub word_count: usize,
+    pub tag_count: usize,
+    /// False for drafts; scheduled posts are published with a future `publish_at`
+    pub published: bool,
+    pub publish_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreatePostRequest {
-    pub slug: String,
+    #[serde(default)]
+    pub slug: String, // Derived from the title when empty
     pub title: String,
-    pub excerpt: String,
+    #[serde(default)]
+    pub excerpt: String, // Generated from the body when blank
     pub body: String,
     pub tags: Vec<Uuid>, // Tag IDs
     pub published: bool,
+    #[serde(default)]
+    pub trusted_html: bool,
+    #[serde(default)]
+    pub noindex: bool,
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UpdatePostRequest {
     pub slug: Option<String>,
     pub title: Option<String>,
     pub excerpt: Option<String>,
     pub body: Option<String>,
     pub tags: Option<Vec<Uuid>>,
+    pub trusted_html: Option<bool>,
+    pub noindex: Option<bool>,
+    pub publish_at: Option<DateTime<Utc>>,
+    /// The post's `updated_at` as the editor last saw it; when set, the update
+    /// is refused if someone else has saved the post since
+    #[serde(default)]
+    pub expected_updated_at: Option<DateTime<Utc>>,
+    /// Fill fields this update leaves out from the autosave slot, then clear the slot
+    #[serde(default)]
+    pub promote_autosave: bool,
 }
 
 // Tag models
@@ -79,6 +201,106 @@ pub struct Tag {
     pub created_at: DateTime<Utc>,
 }
 
+/// Catppuccin palette names accepted as tag colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatppuccinColor {
+    Rosewater,
+    Flamingo,
+    Pink,
+    Mauve,
+    Red,
+    Maroon,
+    Peach,
+    Yellow,
+    Green,
+    Teal,
+    Sky,
+    Sapphire,
+    Blue,
+    Lavender,
+    Surface0,
+    Surface1,
+    Surface2,
+    Overlay0,
+    Overlay1,
+    Overlay2,
+    Subtext0,
+    Subtext1,
+    Text,
+    Base,
+    Mantle,
+    Crust,
+}
+
+impl CatppuccinColor {
+    pub const ALL: [CatppuccinColor; 26] = [
+        CatppuccinColor::Rosewater,
+        CatppuccinColor::Flamingo,
+        CatppuccinColor::Pink,
+        CatppuccinColor::Mauve,
+        CatppuccinColor::Red,
+        CatppuccinColor::Maroon,
+        CatppuccinColor::Peach,
+        CatppuccinColor::Yellow,
+        CatppuccinColor::Green,
+        CatppuccinColor::Teal,
+        CatppuccinColor::Sky,
+        CatppuccinColor::Sapphire,
+        CatppuccinColor::Blue,
+        CatppuccinColor::Lavender,
+        CatppuccinColor::Surface0,
+        CatppuccinColor::Surface1,
+        CatppuccinColor::Surface2,
+        CatppuccinColor::Overlay0,
+        CatppuccinColor::Overlay1,
+        CatppuccinColor::Overlay2,
+        CatppuccinColor::Subtext0,
+        CatppuccinColor::Subtext1,
+        CatppuccinColor::Text,
+        CatppuccinColor::Base,
+        CatppuccinColor::Mantle,
+        CatppuccinColor::Crust,
+    ];
+
+    /// Palette name as stored in `tags.color`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatppuccinColor::Rosewater => "rosewater",
+            CatppuccinColor::Flamingo => "flamingo",
+            CatppuccinColor::Pink => "pink",
+            CatppuccinColor::Mauve => "mauve",
+            CatppuccinColor::Red => "red",
+            CatppuccinColor::Maroon => "maroon",
+            CatppuccinColor::Peach => "peach",
+            CatppuccinColor::Yellow => "yellow",
+            CatppuccinColor::Green => "green",
+            CatppuccinColor::Teal => "teal",
+            CatppuccinColor::Sky => "sky",
+            CatppuccinColor::Sapphire => "sapphire",
+            CatppuccinColor::Blue => "blue",
+            CatppuccinColor::Lavender => "lavender",
+            CatppuccinColor::Surface0 => "surface0",
+            CatppuccinColor::Surface1 => "surface1",
+            CatppuccinColor::Surface2 => "surface2",
+            CatppuccinColor::Overlay0 => "overlay0",
+            CatppuccinColor::Overlay1 => "overlay1",
+            CatppuccinColor::Overlay2 => "overlay2",
+            CatppuccinColor::Subtext0 => "subtext0",
+            CatppuccinColor::Subtext1 => "subtext1",
+            CatppuccinColor::Text => "text",
+            CatppuccinColor::Base => "base",
+            CatppuccinColor::Mantle => "mantle",
+            CatppuccinColor::Crust => "crust",
+        }
+    }
+
+    /// Look up a palette name; exact, lowercase match only
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == name)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTagRequest {
     pub name: String,
@@ -92,29 +314,120 @@ pub struct PostTag {
     pub tag_id: Uuid,
 }
 
+/// Printed in place of passwords and raw tokens, so `{:?}` on auth models is safe to log
+pub struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
 // Auth models
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl fmt::Debug for LoginRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginRequest")
+            .field("username", &self.username)
+            .field("password", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
     pub user: UserInfo,
 }
 
+impl fmt::Debug for LoginResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginResponse")
+            .field("token", &Redacted)
+            .field("refresh_token", &Redacted)
+            .field("user", &self.user)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+impl fmt::Debug for RefreshRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefreshRequest")
+            .field("refresh_token", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RefreshResponse {
+    pub token: String,
+}
+
+impl fmt::Debug for RefreshResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefreshResponse")
+            .field("token", &Redacted)
+            .finish()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserInfo {
     pub id: Uuid,
     pub username: String,
+    pub role: Role,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub role: Role,
+}
+
+impl fmt::Debug for CreateUserRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateUserRequest")
+            .field("username", &self.username)
+            .field("password", &Redacted)
+            .field("role", &self.role)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+impl fmt::Debug for ChangePasswordRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangePasswordRequest")
+            .field("current_password", &Redacted)
+            .field("new_password", &Redacted)
+            .finish()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: Uuid, // user id
     pub username: String,
+    #[serde(default)]
+    pub role: Role, // tokens issued before roles existed fall back to author
     pub exp: i64,
     pub iat: i64,
 }
@@ -123,18 +436,91 @@ pub struct Claims {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarkdownPreviewRequest {
     pub markdown: String,
+    #[serde(default)]
+    pub trusted_html: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 pub struct MarkdownPreviewResponse {
     pub html: String,
     pub reading_time: String,
+    /// Title from the front-matter, used when the post's own title is left empty
+    pub title: Option<String>,
+    /// Tag names used in the body or listed in its front-matter, sorted
+    pub tags: Vec<String>,
+    /// Wiki-link targets (image embeds excluded), sorted
+    pub links: Vec<String>,
+    /// The subset of `links` with no matching post yet
+    pub unresolved_links: Vec<String>,
+    /// The front-matter excerpt, or the one that would be generated if none is given
+    pub excerpt: String,
+    pub outline: Vec<crate::markdown::OutlineNode>,
+}
+
+// Editor outline
+#[derive(Debug, Deserialize)]
+pub struct OutlineRequest {
+    pub markdown: String,
+    /// Deepest heading level included (defaults to every level)
+    pub max_depth: Option<u8>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutlineResponse {
+    pub outline: Vec<crate::markdown::OutlineNode>,
+    #[serde(flatten)]
+    pub counts: crate::markdown::DocumentCounts,
 }
 
-// Draft model (for unpublished posts)
+// Render diagnostics
+#[derive(Debug, Serialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenderTimingResponse {
+    pub slug: String,
+    pub total_ms: f64,
+    pub phases: Vec<PhaseTiming>,
+}
+
+/// Social preview metadata for a post (OpenGraph / Twitter cards)
+#[derive(Debug, Serialize)]
+pub struct PostMeta {
+    pub title: String,
+    pub description: String,
+    pub author: AuthorInfo,
+    pub published_at: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub canonical_url: String,
+    pub image: String,
+    pub robots: String, // content for <meta name="robots">
+}
+
+/// One `<url>` of the sitemap
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub slug: String,
+    pub updated_at: DateTime<Utc>,
+    pub noindex: bool,
+}
+
+/// Where a post would sit among published posts once it goes live
+#[derive(Debug, Serialize)]
+pub struct NavPreview {
+    pub slug: String,
+    pub published_at: DateTime<Utc>, // the date the adjacency was computed for
+    pub previous: Option<PostSummary>,
+    pub next: Option<PostSummary>,
+}
+
+// Draft model: the autosaved state of a post's edits, merged in on publish
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Draft {
     pub id: Uuid,
+    pub post_id: Uuid,
     pub slug: String,
     pub title: String,
     pub excerpt: String,
@@ -144,6 +530,195 @@ pub struct Draft {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveDraftRequest {
+    pub title: String,
+    #[serde(default)]
+    pub excerpt: String,
+    pub body: String,
+}
+
+/// Editor autosave: only the fields being typed in; omitted ones keep their saved value
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AutosaveRequest {
+    pub title: Option<String>,
+    pub body: Option<String>,
+}
+
+/// Demo content created by a seed run, or removed by clearing it
+#[derive(Debug, Default, Serialize)]
+pub struct SeedReport {
+    pub posts: Vec<String>, // slugs
+    pub tags: Vec<String>,  // names
+    pub skipped: bool,      // posts already existed and the seed was not forced
+}
+
+/// What a reindex run touched
+#[derive(Debug, Default, Serialize)]
+pub struct ReindexReport {
+    /// Posts examined, including drafts and trashed posts
+    pub posts: usize,
+    /// Posts whose stored links differed from their body and were rewritten
+    pub posts_updated: usize,
+    /// Link rows written for those posts
+    pub links_written: usize,
+    pub batches: usize,
+}
+
+// Export / import
+/// Bumped whenever `BlogExport` changes shape
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// The whole blog as one JSON document, for backups and migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlogExport {
+    pub schema_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub tags: Vec<Tag>,
+    pub posts: Vec<ExportedPost>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedPost {
+    pub slug: String,
+    pub title: String,
+    pub excerpt: String,
+    pub body: String,
+    pub published: bool,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub trusted_html: bool,
+    pub noindex: bool,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub tags: Vec<String>, // Tag names, so associations survive a move between databases
+}
+
+impl From<Post> for ExportedPost {
+    fn from(p: Post) -> Self {
+        Self {
+            slug: p.slug,
+            title: p.title,
+            excerpt: p.excerpt,
+            body: p.body,
+            published: p.published,
+            published_at: p.published_at,
+            created_at: p.created_at,
+            updated_at: p.updated_at,
+            trusted_html: p.trusted_html,
+            noindex: p.noindex,
+            publish_at: p.publish_at,
+            tags: p.tags.into_iter().map(|t| t.name).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub posts: Vec<String>,   // slugs created
+    pub tags: Vec<String>,    // names created
+    pub skipped: Vec<String>, // slugs that already existed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultFileStatus {
+    Created,
+    Updated,
+    Skipped, // slug already taken and overwrite was off
+    Failed,
+}
+
+/// Outcome of importing one file from an Obsidian vault
+#[derive(Debug, Serialize)]
+pub struct VaultFileResult {
+    pub file: String,
+    pub slug: Option<String>,
+    pub status: VaultFileStatus,
+    pub error: Option<String>,
+}
+
+impl VaultFileResult {
+    pub fn failed(file: String, slug: Option<String>, error: String) -> Self {
+        Self {
+            file,
+            slug,
+            status: VaultFileStatus::Failed,
+            error: Some(error),
+        }
+    }
+}
+
+// Markdown lint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintRequest {
+    pub markdown: String,
+}
+
+/// Bucket size for the publishing timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl StatsPeriod {
+    /// Parse the `period` query value; missing means month
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(str::trim).unwrap_or("month") {
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            "month" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    /// Field name for `date_trunc`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineBucket {
+    pub bucket: NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintKind {
+    UnresolvedLink,
+    UnknownCallout,
+    MissingImage,
+    SkippedHeadingLevel,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintWarning {
+    pub line: usize, // 1-based
+    pub kind: LintKind,
+    pub message: String,
+}
+
+/// Outcome of claiming an `Idempotency-Key`
+#[derive(Debug)]
+pub enum IdempotencyClaim {
+    /// First use: process the request, then store its response
+    New,
+    /// The first request with this key hasn't finished yet
+    InProgress,
+    /// The key was first used with a different request body
+    Mismatch,
+    /// Already processed: replay the stored response
+    Replay(serde_json::Value),
+}
+
 // Session model for authentication
 #[derive(Debug, Clone)]
 pub struct Session {
@@ -177,6 +752,22 @@ pub struct PostLink {
     pub created_at: DateTime<Utc>,
 }
 
+/// A post linking to a page that doesn't exist
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkingPost {
+    pub slug: String,
+    pub title: String,
+}
+
+/// A wiki-link target with no post behind it, and the posts that link there
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedLink {
+    pub target: String,
+    /// Stored links to the target: one per distinct link text in each linking post
+    pub reference_count: usize,
+    pub posts: Vec<LinkingPost>,
+}
+
 // Statistics model
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PostStats {
@@ -187,6 +778,14 @@ pub struct PostStats {
     pub tag_count: i64,
 }
 
+// Search hit: a post summary plus a highlighted snippet of the matching body text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub post: PostSummary,
+    pub snippet: Option<String>,
+}
+
 // Search result model
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -211,6 +810,63 @@ impl Default for PaginationParams {
     }
 }
 
+impl PaginationParams {
+    /// Largest page size a client may request
+    pub const MAX_PER_PAGE: u32 = 100;
+
+    /// Effective 1-based page number
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    /// Effective page size, clamped to 1..=MAX_PER_PAGE
+    pub fn per_page(&self) -> u32 {
+        self.per_page.unwrap_or(20).clamp(1, Self::MAX_PER_PAGE)
+    }
+
+    /// SQL LIMIT/OFFSET for the requested page
+    pub fn limit_offset(&self) -> (i64, i64) {
+        let per_page = self.per_page() as i64;
+        (per_page, (self.page() as i64 - 1) * per_page)
+    }
+}
+
+// Paginated response envelope
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: i64, params: &PaginationParams) -> Self {
+        Self {
+            items,
+            total,
+            page: params.page(),
+            per_page: params.per_page(),
+        }
+    }
+}
+
+// Validation error for a single request/content field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
 // API Response wrapper
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
@@ -236,3 +892,53 @@ impl<T> ApiResponse<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_request_debug_hides_password() {
+        let login = LoginRequest {
+            username: "admin".to_string(),
+            password: "hunter2-correct-horse".to_string(),
+        };
+        let debug = format!("{:?}", login);
+        assert!(debug.contains("admin"));
+        assert!(debug.contains("[redacted]"));
+        assert!(!debug.contains("hunter2-correct-horse"));
+
+        let refresh = RefreshRequest { refresh_token: "raw-refresh-token".to_string() };
+        assert!(!format!("{:?}", refresh).contains("raw-refresh-token"));
+    }
+
+    #[test]
+    fn test_pagination_limit_offset() {
+        let params = PaginationParams { page: Some(3), per_page: Some(10) };
+        assert_eq!(params.limit_offset(), (10, 20));
+
+        let clamped = PaginationParams { page: Some(0), per_page: Some(1000) };
+        assert_eq!(clamped.limit_offset(), (PaginationParams::MAX_PER_PAGE as i64, 0));
+    }
+
+    #[test]
+    fn test_author_info_exposes_only_id_and_username() {
+        let author = AuthorInfo {
+            id: Uuid::new_v4(),
+            username: "admin".to_string(),
+        };
+        let json = serde_json::to_value(&author).unwrap();
+        let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["id", "username"]);
+    }
+
+    #[test]
+    fn test_catppuccin_color_names() {
+        for color in CatppuccinColor::ALL {
+            assert_eq!(CatppuccinColor::from_name(color.as_str()), Some(color));
+        }
+        assert_eq!(CatppuccinColor::from_name("surface1"), Some(CatppuccinColor::Surface1));
+        assert_eq!(CatppuccinColor::from_name("Blue"), None);
+        assert_eq!(CatppuccinColor::from_name("#ff0000"), None);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Failed attempts allowed per window before a client is locked out
+pub const MAX_LOGIN_FAILURES: u32 = 5;
+/// Window in which failed attempts are counted
+pub const LOGIN_FAILURE_WINDOW: Duration = Duration::from_secs(15 * 60);
+/// First lockout duration; doubles with each consecutive lockout
+pub const BASE_LOCKOUT: Duration = Duration::from_secs(60);
+/// Upper bound for the exponential backoff
+pub const MAX_LOCKOUT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+struct Attempts {
+    failures: u32,
+    window_start: Instant,
+    lockouts: u32,
+    locked_until: Option<Instant>,
+}
+
+/// Per-IP failed-login tracker with exponential lockouts.
+/// Shared by the real and decoy login endpoints so they behave identically.
+pub struct LoginLimiter {
+    max_failures: u32,
+    window: Duration,
+    base_lockout: Duration,
+    attempts: Mutex<HashMap<IpAddr, Attempts>>,
+}
+
+impl Default for LoginLimiter {
+    fn default() -> Self {
+        Self::new(MAX_LOGIN_FAILURES, LOGIN_FAILURE_WINDOW, BASE_LOCKOUT)
+    }
+}
+
+impl LoginLimiter {
+    pub fn new(max_failures: u32, window: Duration, base_lockout: Duration) -> Self {
+        Self {
+            max_failures,
+            window,
+            base_lockout,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check whether `ip` may attempt a login; Err carries the time left on its lockout
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    /// Record a failed login attempt from `ip`
+    pub fn record_failure(&self, ip: IpAddr) {
+        self.record_failure_at(ip, Instant::now())
+    }
+
+    /// Clear the history for `ip` after a successful login
+    pub fn record_success(&self, ip: IpAddr) {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        attempts.remove(&ip);
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+        match attempts.get(&ip).and_then(|a| a.locked_until) {
+            Some(until) if until > now => Err(until - now),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_failure_at(&self, ip: IpAddr, now: Instant) {
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Keep memory bounded by dropping idle, unlocked clients once the map grows
+        if attempts.len() > 4096 {
+            let window = self.window;
+            attempts.retain(|_, a| {
+                a.locked_until.is_some_and(|until| until > now)
+                    || now.duration_since(a.window_start) < window
+            });
+        }
+
+        let entry = attempts.entry(ip).or_insert(Attempts {
+            failures: 0,
+            window_start: now,
+            lockouts: 0,
+            locked_until: None,
+        });
+
+        if now.duration_since(entry.window_start) >= self.window {
+            entry.failures = 0;
+            entry.window_start = now;
+        }
+
+        entry.failures += 1;
+        if entry.failures >= self.max_failures {
+            let factor = 2u32.saturating_pow(entry.lockouts);
+            let lockout = self.base_lockout.saturating_mul(factor).min(MAX_LOCKOUT);
+            entry.lockouts += 1;
+            entry.locked_until = Some(now + lockout);
+            entry.failures = 0;
+            entry.window_start = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockout_after_failures_with_backoff() {
+        let limiter = LoginLimiter::new(3, Duration::from_secs(600), Duration::from_secs(60));
+        let ip: IpAddr = "198.51.100.4".parse().unwrap();
+        let start = Instant::now();
+
+        for _ in 0..2 {
+            limiter.record_failure_at(ip, start);
+        }
+        assert!(limiter.check_at(ip, start).is_ok());
+
+        // Third failure locks the client out for the base duration
+        limiter.record_failure_at(ip, start);
+        assert_eq!(limiter.check_at(ip, start), Err(Duration::from_secs(60)));
+        assert!(limiter.check_at(ip, start + Duration::from_secs(61)).is_ok());
+
+        // The next lockout doubles
+        let later = start + Duration::from_secs(61);
+        for _ in 0..3 {
+            limiter.record_failure_at(ip, later);
+        }
+        assert_eq!(limiter.check_at(ip, later), Err(Duration::from_secs(120)));
+
+        limiter.record_success(ip);
+        assert!(limiter.check_at(ip, later).is_ok());
+    }
+}
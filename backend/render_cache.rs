@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::markdown::SanitizerProfile;
+
+/// Rendered posts kept in memory by default
+pub const DEFAULT_RENDER_CACHE_CAPACITY: usize = 256;
+
+/// What a cached render was produced from; any change means a re-render
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RenderKey {
+    updated_at: DateTime<Utc>,
+    profile: SanitizerProfile,
+}
+
+struct Entry {
+    key: RenderKey,
+    html: String,
+    last_used: u64,
+}
+
+struct Entries {
+    map: HashMap<Uuid, Entry>,
+    clock: u64,
+}
+
+/// Least-recently-used cache of rendered post HTML, keyed by post id.
+/// - An entry only hits while the post's `updated_at` and sanitizer profile match
+/// - `clear` drops everything, for changes that affect posts without touching them (tags)
+pub struct RenderCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl Default for RenderCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_RENDER_CACHE_CAPACITY)
+    }
+}
+
+impl RenderCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// Cached HTML for this version of the post, if any
+    pub fn get(&self, post_id: Uuid, updated_at: DateTime<Utc>, profile: SanitizerProfile) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.clock += 1;
+        let now = entries.clock;
+
+        let entry = entries.map.get_mut(&post_id)?;
+        if entry.key != (RenderKey { updated_at, profile }) {
+            return None;
+        }
+        entry.last_used = now;
+        Some(entry.html.clone())
+    }
+
+    /// Store a render, evicting the least recently used entry when full
+    pub fn insert(&self, post_id: Uuid, updated_at: DateTime<Utc>, profile: SanitizerProfile, html: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.clock += 1;
+        let now = entries.clock;
+
+        if !entries.map.contains_key(&post_id) && entries.map.len() >= self.capacity {
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                entries.map.remove(&oldest);
+            }
+        }
+
+        entries.map.insert(
+            post_id,
+            Entry {
+                key: RenderKey { updated_at, profile },
+                html,
+                last_used: now,
+            },
+        );
+    }
+
+    /// Forget one post's render
+    pub fn invalidate(&self, post_id: Uuid) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.map.remove(&post_id);
+    }
+
+    /// Forget every render
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.map.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_hits_only_for_same_version() {
+        let cache = RenderCache::new(4);
+        let id = Uuid::new_v4();
+        let updated = Utc::now();
+
+        cache.insert(id, updated, SanitizerProfile::Safe, "<p>v1</p>".to_string());
+        assert_eq!(cache.get(id, updated, SanitizerProfile::Safe).as_deref(), Some("<p>v1</p>"));
+        assert_eq!(cache.get(id, updated + Duration::seconds(1), SanitizerProfile::Safe), None);
+        assert_eq!(cache.get(id, updated, SanitizerProfile::Trusted), None);
+
+        cache.clear();
+        assert_eq!(cache.get(id, updated, SanitizerProfile::Safe), None);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = RenderCache::new(2);
+        let updated = Utc::now();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        cache.insert(a, updated, SanitizerProfile::Safe, "a".to_string());
+        cache.insert(b, updated, SanitizerProfile::Safe, "b".to_string());
+        cache.get(a, updated, SanitizerProfile::Safe);
+        cache.insert(c, updated, SanitizerProfile::Safe, "c".to_string());
+
+        assert!(cache.get(a, updated, SanitizerProfile::Safe).is_some());
+        assert!(cache.get(b, updated, SanitizerProfile::Safe).is_none());
+        assert!(cache.get(c, updated, SanitizerProfile::Safe).is_some());
+    }
+}
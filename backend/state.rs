@@ -1,5 +1,20 @@
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::auth::PreviousSecret;
+use crate::models::Post;
+use crate::markdown::{
+    DEFAULT_EXCERPT_MIN_BODY_CHARS, DEFAULT_MAX_CALLOUT_DEPTH, DEFAULT_RENDER_TIMEOUT, DEFAULT_URL_SCHEMES,
+};
+use crate::ratelimit::LoginLimiter;
+use crate::render_cache::RenderCache;
+use crate::visitor::{HitWindow, DEFAULT_TRUSTED_PROXIES};
+use crate::webhook::{Webhook, WebhookEvent};
+
+/// How long repeat views from the same client count as one
+const VIEW_DEDUP_WINDOW: Duration = Duration::from_secs(6 * 60 * 60);
 
 /// Application state shared across all handlers
 #[derive(Clone)]
@@ -8,8 +23,36 @@ pub struct AppState {
     pub pool: PgPool,
     /// JWT secret for token signing and verification
     pub jwt_secret: String,
+    /// Previous JWT secret, still accepted for verification during a rotation
+    pub previous_jwt_secret: Option<PreviousSecret>,
     /// Optional: Frontend URL for CORS configuration
     pub frontend_url: Option<String>,
+    /// Salt mixed into client IP hashes (raw IPs are never stored)
+    pub ip_salt: String,
+    /// Per-client view deduplication keyed by hashed IP
+    pub view_dedup: Arc<HitWindow>,
+    /// Reject posts whose front-matter lacks required fields
+    pub strict_frontmatter: bool,
+    /// Refuse to publish posts without a cover image
+    pub require_cover_image: bool,
+    /// Bodies shorter than this (plain-text chars) get no auto-generated excerpt
+    pub excerpt_min_body_chars: usize,
+    /// How long a request waits for markdown rendering before returning 503
+    pub render_timeout: Duration,
+    /// URL schemes kept in rendered links and images
+    pub url_schemes: Vec<String>,
+    /// Deepest blockquote nesting that still gets callout processing
+    pub max_callout_depth: usize,
+    /// Tags a post must share with another to count as related to it
+    pub related_min_common_tags: i64,
+    /// Rendered post HTML, reused until the post changes
+    pub render_cache: Arc<RenderCache>,
+    /// Failed-login throttling shared by the real and decoy login routes
+    pub login_limiter: Arc<LoginLimiter>,
+    /// Proxies in front of the app whose `X-Forwarded-For` entries are trusted
+    pub trusted_proxies: usize,
+    /// Optional: notified of post lifecycle events (`WEBHOOK_URL`)
+    pub webhook: Option<Arc<Webhook>>,
 }
 
 impl AppState {
@@ -18,7 +61,21 @@ impl AppState {
         Self {
             pool,
             jwt_secret,
+            previous_jwt_secret: None,
             frontend_url: None,
+            ip_salt: Uuid::new_v4().to_string(),
+            view_dedup: Arc::new(HitWindow::new(VIEW_DEDUP_WINDOW, 1)),
+            strict_frontmatter: false,
+            require_cover_image: false,
+            excerpt_min_body_chars: DEFAULT_EXCERPT_MIN_BODY_CHARS,
+            render_timeout: DEFAULT_RENDER_TIMEOUT,
+            url_schemes: DEFAULT_URL_SCHEMES.map(String::from).to_vec(),
+            max_callout_depth: DEFAULT_MAX_CALLOUT_DEPTH,
+            related_min_common_tags: 1,
+            render_cache: Arc::new(RenderCache::default()),
+            login_limiter: Arc::new(LoginLimiter::default()),
+            trusted_proxies: DEFAULT_TRUSTED_PROXIES,
+            webhook: None,
         }
     }
 
@@ -27,7 +84,28 @@ impl AppState {
         Self {
             pool,
             jwt_secret,
+            previous_jwt_secret: None,
             frontend_url: Some(frontend_url),
+            ip_salt: Uuid::new_v4().to_string(),
+            view_dedup: Arc::new(HitWindow::new(VIEW_DEDUP_WINDOW, 1)),
+            strict_frontmatter: false,
+            require_cover_image: false,
+            excerpt_min_body_chars: DEFAULT_EXCERPT_MIN_BODY_CHARS,
+            render_timeout: DEFAULT_RENDER_TIMEOUT,
+            url_schemes: DEFAULT_URL_SCHEMES.map(String::from).to_vec(),
+            max_callout_depth: DEFAULT_MAX_CALLOUT_DEPTH,
+            related_min_common_tags: 1,
+            render_cache: Arc::new(RenderCache::default()),
+            login_limiter: Arc::new(LoginLimiter::default()),
+            trusted_proxies: DEFAULT_TRUSTED_PROXIES,
+            webhook: None,
+        }
+    }
+
+    /// Send `event` for `post` to the configured webhook, if any, without waiting on it
+    pub fn notify(&self, event: WebhookEvent, post: &Post) {
+        if let Some(webhook) = &self.webhook {
+            webhook.notify(event, post, self.frontend_url.as_deref());
         }
     }
 }
@@ -0,0 +1,179 @@
+use sha2::{Digest, Sha256};
+use shuttle_axum::axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::state::AppState;
+
+/// Proxies in front of the app by default (Shuttle's router)
+pub const DEFAULT_TRUSTED_PROXIES: usize = 1;
+
+/// Client IP extractor.
+/// - Behind `trusted_proxies` proxies, takes the `X-Forwarded-For` entry the
+///   outermost of them appended; entries left of it are client-supplied and ignored
+/// - Falls back to the socket address (the server is run with connect info)
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+    AppState: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    fn from_request_parts(parts: &mut Parts, state: &S) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
+        let trusted_proxies = AppState::from_ref(state).trusted_proxies;
+        let ip = client_ip_from_parts(parts, trusted_proxies);
+        async move { Ok(ClientIp(ip)) }
+    }
+}
+
+fn client_ip_from_parts(parts: &Parts, trusted_proxies: usize) -> Option<IpAddr> {
+    // Several X-Forwarded-For headers form one list, in order
+    let hops: Vec<&str> = parts
+        .headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect();
+
+    // Each trusted proxy appends the address it saw, so the client is
+    // `trusted_proxies` entries from the right. Fewer entries than that means
+    // the header didn't come through our proxies and can't be trusted.
+    let forwarded = trusted_proxies
+        .checked_sub(1)
+        .and_then(|skip| hops.len().checked_sub(skip + 1))
+        .and_then(|i| hops[i].parse().ok());
+
+    let socket = || {
+        parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    };
+
+    forwarded.or_else(socket)
+}
+
+/// Hash a client IP with a server-side salt so raw addresses are never stored
+pub fn hash_ip(ip: &IpAddr, salt: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(ip.to_string().as_bytes())
+        .finalize();
+
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Sliding-window hit counter keyed by hashed client identity.
+/// - Views use `max_hits = 1` so repeat views within the window count once
+/// - Comments can use a higher limit to throttle spam per client
+pub struct HitWindow {
+    window: Duration,
+    max_hits: usize,
+    hits: Mutex<HashMap<String, Vec<Instant>>>,
+}
+
+impl HitWindow {
+    pub fn new(window: Duration, max_hits: usize) -> Self {
+        Self {
+            window,
+            max_hits,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a hit for `key`, returning whether it falls within the limit
+    pub fn record(&self, key: &str) -> bool {
+        self.record_at(key, Instant::now())
+    }
+
+    fn record_at(&self, key: &str, now: Instant) -> bool {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Keep memory bounded by sweeping stale keys once the map grows
+        if hits.len() > 1024 {
+            hits.retain(|_, times| {
+                times.retain(|t| now.duration_since(*t) < self.window);
+                !times.is_empty()
+            });
+        }
+
+        let times = hits.entry(key.to_string()).or_default();
+        times.retain(|t| now.duration_since(*t) < self.window);
+
+        if times.len() >= self.max_hits {
+            return false;
+        }
+
+        times.push(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shuttle_axum::axum::http::Request;
+
+    fn parts(forwarded_for: &[&str], socket: Option<&str>) -> Parts {
+        let mut req = Request::builder();
+        for value in forwarded_for {
+            req = req.header("x-forwarded-for", *value);
+        }
+        let (mut parts, _) = req.body(()).unwrap().into_parts();
+        if let Some(addr) = socket {
+            parts.extensions.insert(ConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+        }
+        parts
+    }
+
+    #[test]
+    fn test_client_ip_trusts_only_proxy_appended_hops() {
+        let ip = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+
+        // The client's own X-Forwarded-For entries are ignored; the proxy's append wins
+        let spoofed = parts(&["1.2.3.4, 203.0.113.7"], Some("10.0.0.1:443"));
+        assert_eq!(client_ip_from_parts(&spoofed, 1), ip("203.0.113.7"));
+        assert_eq!(client_ip_from_parts(&spoofed, 2), ip("1.2.3.4"));
+        let split = parts(&["1.2.3.4", "203.0.113.7"], None);
+        assert_eq!(client_ip_from_parts(&split, 1), ip("203.0.113.7"));
+
+        // Too few hops, or no trusted proxies: the socket address
+        assert_eq!(client_ip_from_parts(&spoofed, 3), ip("10.0.0.1"));
+        assert_eq!(client_ip_from_parts(&spoofed, 0), ip("10.0.0.1"));
+        let direct = parts(&[], Some("198.51.100.2:5000"));
+        assert_eq!(client_ip_from_parts(&direct, 1), ip("198.51.100.2"));
+        assert_eq!(client_ip_from_parts(&parts(&[], None), 1), None);
+    }
+
+    #[test]
+    fn test_hash_ip_hides_raw_address() {
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let hash = hash_ip(&ip, "salt");
+        assert_eq!(hash, hash_ip(&ip, "salt"));
+        assert_ne!(hash, hash_ip(&ip, "other-salt"));
+        assert!(!hash.contains("203.0.113.7"));
+    }
+
+    #[test]
+    fn test_views_within_window_count_once() {
+        let views = HitWindow::new(Duration::from_secs(3600), 1);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let key = hash_ip(&ip, "salt");
+        let start = Instant::now();
+
+        assert!(views.record_at(&key, start));
+        assert!(!views.record_at(&key, start + Duration::from_secs(60)));
+        assert!(views.record_at(&key, start + Duration::from_secs(3601)));
+    }
+}
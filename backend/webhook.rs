@@ -0,0 +1,143 @@
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::models::Post;
+
+/// Post lifecycle events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebhookEvent {
+    Created,
+    Updated,
+    Published,
+    Deleted,
+}
+
+impl WebhookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Created => "post.created",
+            Self::Updated => "post.updated",
+            Self::Published => "post.published",
+            Self::Deleted => "post.deleted",
+        }
+    }
+
+    /// Accepts the short form (`publish`, `created`) or the full event name (`post.published`)
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        match value.strip_prefix("post.").unwrap_or(&value) {
+            "create" | "created" => Some(Self::Created),
+            "update" | "updated" => Some(Self::Updated),
+            "publish" | "published" => Some(Self::Published),
+            "delete" | "deleted" => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the comma-separated `WEBHOOK_EVENTS` secret; without one only publishes are sent
+pub fn parse_events(value: Option<&str>) -> anyhow::Result<HashSet<WebhookEvent>> {
+    let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+        return Ok(HashSet::from([WebhookEvent::Published]));
+    };
+
+    value
+        .split(',')
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| {
+            WebhookEvent::parse(v).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown WEBHOOK_EVENTS entry '{}'; use create, update, publish or delete",
+                    v.trim()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Time allowed for one delivery; a receiver that hangs would otherwise pin its task forever
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Outgoing notifications to a single configured URL
+pub struct Webhook {
+    url: String,
+    events: HashSet<WebhookEvent>,
+    client: reqwest::Client,
+}
+
+impl Webhook {
+    pub fn new(url: String, events: HashSet<WebhookEvent>) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+        Ok(Self {
+            url,
+            events,
+            client,
+        })
+    }
+
+    /// Fire `event` for `post` in the background.
+    /// Delivery failures are only logged; they never fail the request that caused them.
+    pub fn notify(&self, event: WebhookEvent, post: &Post, frontend_url: Option<&str>) {
+        if !self.events.contains(&event) {
+            return;
+        }
+
+        let body = payload(event, post, frontend_url);
+        let request = self.client.post(&self.url).json(&body).send();
+        let slug = post.slug.clone();
+        tokio::spawn(async move {
+            match request.await.and_then(|response| response.error_for_status()) {
+                Ok(_) => tracing::info!("Webhook {} sent for {}", event.name(), slug),
+                Err(e) => tracing::warn!("Webhook {} for {} failed: {}", event.name(), slug, e),
+            }
+        });
+    }
+}
+
+/// `{ event, slug, title, url }`, with the post URL made absolute against the frontend URL
+fn payload(event: WebhookEvent, post: &Post, frontend_url: Option<&str>) -> Value {
+    let base = frontend_url.unwrap_or("").trim_end_matches('/');
+    json!({
+        "event": event.name(),
+        "slug": post.slug,
+        "title": post.title,
+        "url": format!("{}/blogs/{}", base, post.slug),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::post;
+
+    #[test]
+    fn test_webhook_events_and_payload() {
+        assert_eq!(
+            parse_events(None).unwrap(),
+            HashSet::from([WebhookEvent::Published])
+        );
+        assert_eq!(
+            parse_events(Some("create, post.updated,DELETE")).unwrap(),
+            HashSet::from([
+                WebhookEvent::Created,
+                WebhookEvent::Updated,
+                WebhookEvent::Deleted
+            ])
+        );
+        assert!(parse_events(Some("publish,archive")).is_err());
+
+        let post = Post {
+            title: "Hello".to_string(),
+            ..post("hello", "")
+        };
+        assert_eq!(
+            payload(WebhookEvent::Published, &post, Some("https://blog.example/")),
+            json!({
+                "event": "post.published",
+                "slug": "hello",
+                "title": "Hello",
+                "url": "https://blog.example/blogs/hello",
+            })
+        );
+    }
+}